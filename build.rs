@@ -0,0 +1,22 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Compresses the Unicode Character Database files shipped in the repository
+/// into `OUT_DIR`, where `src/unicode_data.rs` embeds them as a fallback for
+/// machines without a local copy.
+fn main() {
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    for name in ["UnicodeData.txt", "Blocks.txt"] {
+        println!("cargo:rerun-if-changed={}", name);
+        let data = fs::read(name).unwrap_or_else(|e| panic!("cannot read {}: {}", name, e));
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&data).expect("in-memory write");
+        let compressed = encoder.finish().expect("in-memory write");
+        fs::write(Path::new(&out_dir).join(format!("{}.gz", name)), compressed)
+            .expect("write compressed UCD file");
+    }
+}
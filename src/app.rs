@@ -1,42 +1,69 @@
+use crate::clipboard;
 use crate::db::Database;
+use crate::redact;
 use crate::secrets::Secrets;
+use crate::tasks::{TaskFinished, Tasks};
 use crate::tools::{
-    EncoderDecoderTool, HttpRequestInspectorTool, JwtDecoderTool, OrgResearchTool,
-    RepoExplorerTool, TokenInspectorTool, Tool, UnicodeInspectorTool,
+    CacheTool, EncoderDecoderTool, HttpRequestInspectorTool, JwtDecoderTool, OrgResearchTool,
+    RepoExplorerTool, TokenInspectorTool, Tool, UnicodeInspectorTool, WatchlistTool,
 };
-use arboard::Clipboard;
-use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+use crate::ui::components;
+use crossterm::event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph, Tabs},
 };
 use reqwest::Client;
+use secrecy::{ExposeSecret, SecretBox};
 use std::io;
-use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::config::Config;
+use crate::events::{AppEvent, Events};
+use crate::keymap::{Action, Keymap};
+use crate::ui::components::TextInput;
+use crate::ui::palette::{Entry, Palette};
+
+/// Upper bound on a pasted token; the buffer is allocated once at this size
+/// so typing never reallocates and leaves copies behind.
+const TOKEN_INPUT_CAPACITY: usize = 256;
+
+/// What a command palette entry does.
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    /// Selects the tool at the index.
+    Tool(usize),
+    /// An app-wide action from the keymap.
+    Global(Action),
+    /// A keymap action of the tool at the index.
+    Action(usize, Action),
+    /// One of the tool's own palette commands, by id.
+    Command(usize, &'static str),
+}
 
 pub struct App {
     tab_index: usize,
     tools: Vec<Box<dyn Tool>>,
     message: String,
-    search_mode: bool,
-    search_query: String,
-    search_results: Vec<usize>,
-    search_selected: usize,
+    palette: Palette<Target>,
+    credential_mode: bool,
+    credential_selected: usize,
+    /// Token typed into the "configure a token" panel; zeroized on submit.
+    token_input: TextInput,
     #[allow(dead_code)]
-    db: Arc<Mutex<Database>>,
+    db: Database,
     #[allow(dead_code)]
     client: Client,
-    #[allow(dead_code)]
     secrets: Secrets,
-    #[allow(dead_code)]
     config: Config,
+    keymap: Keymap,
+    tasks: Tasks,
+    events: Events,
 }
 
 impl App {
     pub fn new(
-        db: Arc<Mutex<Database>>,
+        db: Database,
         secrets: Secrets,
         config: Config,
     ) -> Result<Self, Box<dyn std::error::Error>> {
@@ -44,42 +71,329 @@ impl App {
             .user_agent("Dev-Toolbox/1.0")
             .https_only(true)
             .build()?;
+        let (tasks, task_events) = Tasks::new();
 
         let tools: Vec<Box<dyn Tool>> = vec![
-            Box::new(OrgResearchTool::new(&client, &secrets)?),
-            Box::new(RepoExplorerTool::new(Arc::clone(&db), &client, &secrets)?),
-            Box::new(UnicodeInspectorTool::new(Arc::clone(&db), &config)?),
-            Box::new(TokenInspectorTool::new(&client, &secrets)),
+            Box::new(OrgResearchTool::new(db.clone(), &client, &secrets, &tasks)?),
+            Box::new(RepoExplorerTool::new(
+                db.clone(),
+                &client,
+                &secrets,
+                &tasks,
+            )?),
+            Box::new(UnicodeInspectorTool::new(db.clone(), &config)?),
+            Box::new(TokenInspectorTool::new(
+                db.clone(),
+                &client,
+                &secrets,
+                &config,
+                &tasks,
+            )),
             Box::new(EncoderDecoderTool::new()),
-            Box::new(HttpRequestInspectorTool::new(&client)),
+            Box::new(HttpRequestInspectorTool::new(&client, &tasks)),
             Box::new(JwtDecoderTool::new()),
+            Box::new(WatchlistTool::new(
+                db.clone(),
+                &client,
+                &secrets,
+                &config,
+                &tasks,
+            )?),
+            Box::new(CacheTool::new(db.clone(), &config, &tasks)),
         ];
 
+        let message = if secrets.github_token().expose_secret().is_empty() {
+            "No GitHub token configured: GitHub tools are disabled until you enter one. Offline tools work normally."
+        } else {
+            "Welcome to Dev-Toolbox! Use shortcuts below to navigate."
+        };
+
         Ok(App {
             tab_index: 0,
             tools,
-            message: String::from("Welcome to Dev-Toolbox! Use shortcuts below to navigate."),
-            search_mode: false,
-            search_query: String::new(),
-            search_results: Vec::new(),
-            search_selected: 0,
+            message: message.to_string(),
+            palette: Palette::new(),
+            credential_mode: false,
+            credential_selected: 0,
+            token_input: TextInput::secret(TOKEN_INPUT_CAPACITY),
             db,
             client,
             secrets,
+            tasks,
+            events: Events::new(Duration::from_millis(config.tick_rate_ms), task_events),
+            keymap: config.keymap()?,
             config,
         })
     }
 
-    fn update_search(&mut self) {
-        let query = self.search_query.to_lowercase();
-        self.search_results = self
-            .tools
-            .iter()
-            .enumerate()
-            .filter(|(_, t)| t.name().to_lowercase().contains(&query))
-            .map(|(i, _)| i)
-            .collect();
-        self.search_selected = 0;
+    /// Hands a finished task to the tool that started it. A result from a
+    /// tool other than the selected one is prefixed with the tool's name.
+    fn finish_task(&mut self, done: TaskFinished) {
+        let Some(index) = self.tools.iter().position(|t| t.name() == done.tool) else {
+            return;
+        };
+        if let Some(message) = self.tools[index].on_task_finished() {
+            self.message = if index == self.tab_index {
+                message
+            } else {
+                format!("{}: {}", done.tool, message)
+            };
+        }
+    }
+
+    /// Cancels the selected tool's running tasks, if it has any.
+    fn cancel_tasks(&mut self) -> bool {
+        let cancelled = self.tasks.cancel(self.tools[self.tab_index].name());
+        if cancelled.is_empty() {
+            return false;
+        }
+        while let Some(done) = self.events.try_task_finished() {
+            self.finish_task(done);
+        }
+        self.message = format!("Cancelled: {}", cancelled.join(", "));
+        true
+    }
+
+    /// Switches to another tool, letting both know about the change.
+    fn select_tool(&mut self, index: usize) {
+        if index == self.tab_index || index >= self.tools.len() {
+            return;
+        }
+        self.tools[self.tab_index].on_blur();
+        self.tab_index = index;
+        if let Some(message) = self.tools[index].on_focus() {
+            self.message = message;
+        }
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.message = message;
+    }
+
+    /// Whether the selected tool needs a GitHub token it does not have.
+    fn needs_token(&self) -> bool {
+        let tool = &self.tools[self.tab_index];
+        tool.requires_github_token() && !self.secrets.has_token_for(tool.name())
+    }
+
+    fn render_token_panel(&self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(3)])
+            .split(area);
+
+        let text = vec![
+            Line::from(Span::styled(
+                format!("{} needs a GitHub token.", self.tools[self.tab_index].name()),
+                Style::default().fg(Color::Yellow).bold(),
+            )),
+            Line::from(""),
+            Line::from("Configure one permanently by either:"),
+            Line::from("  - setting GITHUB_TOKEN or adding it to the .env file in the config directory,"),
+            Line::from("  - logging in with the GitHub CLI (gh auth login),"),
+            Line::from("  - storing it in the encrypted vault (dev-toolbox secrets add default)."),
+            Line::from(""),
+            Line::from("Or paste a token below to use it for this session only. It is kept in memory and never written to disk."),
+        ];
+        let info = Paragraph::new(text)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                "Configure a Token",
+                Style::default().fg(Color::Yellow),
+            )));
+        f.render_widget(info, chunks[0]);
+
+        self.token_input.render(
+            f,
+            chunks[1],
+            Block::default().borders(Borders::ALL).title(Span::styled(
+                "Token (Enter: Use for Session, Esc: Clear)",
+                Style::default().fg(Color::Green),
+            )),
+            true,
+        );
+    }
+
+    fn handle_token_input(&mut self, key: crossterm::event::KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.token_input.clear(),
+            KeyCode::Enter if !self.token_input.is_empty() => {
+                self.secrets
+                    .set_session_token(SecretBox::new(self.token_input.value().into()));
+                self.token_input.clear();
+                self.message =
+                    "Token set for this session. Press Enter in a GitHub tool to load data."
+                        .to_string();
+            }
+            _ => {
+                self.token_input.handle_key(&key);
+            }
+        }
+    }
+
+    /// Copies `text` with secrets redacted and reports it in the status bar.
+    fn copy(&mut self, text: &str, what: &str) {
+        let text = redact::redact(text).into_owned();
+        self.message = match clipboard::copy(
+            &text,
+            self.config.clipboard_clear_secs,
+            self.config.clipboard_primary_selection,
+        ) {
+            Ok(primary_error) => {
+                let mut message = format!("Copied {} to clipboard!", what);
+                if self.config.clipboard_clear_secs > 0 {
+                    message.push_str(&format!(
+                        " (Cleared in {}s if unchanged)",
+                        self.config.clipboard_clear_secs
+                    ));
+                }
+                if let Some(e) = primary_error {
+                    message.push_str(&format!(" Primary selection unavailable: {}", e));
+                }
+                message
+            }
+            Err(e) => format!("Clipboard unavailable: {}", e),
+        };
+    }
+
+    /// Opens the command palette. The selected tool's entries come first,
+    /// then app-wide actions, tool switching and the other tools' entries.
+    fn open_palette(&mut self) {
+        let chord = |action| {
+            self.keymap
+                .chord(action)
+                .map(|chord| chord.to_string())
+                .unwrap_or_default()
+        };
+        let tool_entries = |index: usize| {
+            let tool = &self.tools[index];
+            let actions = Action::all()
+                .filter(|action| action.tools().contains(&tool.name()))
+                .map(|action| Entry {
+                    title: format!("{}: {}", tool.name(), action.label()),
+                    hint: chord(action),
+                    prompt: None,
+                    target: Target::Action(index, action),
+                });
+            let commands = tool.commands().iter().map(move |command| Entry {
+                title: format!("{}: {}", tool.name(), command.title),
+                hint: String::new(),
+                prompt: command.prompt,
+                target: Target::Command(index, command.id),
+            });
+            actions.chain(commands).collect::<Vec<_>>()
+        };
+
+        let mut entries = tool_entries(self.tab_index);
+        entries.extend(
+            Action::all()
+                .filter(|action| action.is_global() && *action != Action::Search)
+                .map(|action| Entry {
+                    title: action.label().to_string(),
+                    hint: chord(action),
+                    prompt: None,
+                    target: Target::Global(action),
+                }),
+        );
+        entries.extend(self.tools.iter().enumerate().map(|(index, tool)| Entry {
+            title: format!("Go to {}", tool.name()),
+            hint: if index < 9 {
+                format!("Alt+{}", index + 1)
+            } else {
+                String::new()
+            },
+            prompt: None,
+            target: Target::Tool(index),
+        }));
+        for index in (0..self.tools.len()).filter(|&index| index != self.tab_index) {
+            entries.extend(tool_entries(index));
+        }
+        self.palette.open(entries);
+    }
+
+    /// Runs an app-wide action other than `Quit`, from its key or the
+    /// command palette.
+    fn run_global(&mut self, action: Action) {
+        let tool_name = self.tools[self.tab_index].name();
+        match action {
+            Action::Search => self.open_palette(),
+            Action::Credentials => {
+                let current = self.secrets.credential_for(tool_name).map(|(name, _)| name);
+                self.credential_selected = self
+                    .secrets
+                    .list()
+                    .iter()
+                    .position(|(name, _)| Some(name) == current.as_ref())
+                    .unwrap_or(0);
+                self.credential_mode = true;
+            }
+            Action::RevealSecrets => {
+                let reveal = !redact::is_revealed();
+                redact::set_reveal(reveal);
+                self.message = if reveal {
+                    let hide = self
+                        .keymap
+                        .chord(Action::RevealSecrets)
+                        .map(|chord| format!(" Press {} to hide them.", chord))
+                        .unwrap_or_default();
+                    format!("Secrets are shown unmasked in the status bar, clipboard, exports and HTTP Inspector.{}", hide)
+                } else {
+                    "Secrets are masked again.".to_string()
+                };
+            }
+            Action::Copy => {
+                let tool = &mut self.tools[self.tab_index];
+                let selection = tool
+                    .focused_input()
+                    .and_then(|input| input.selected_text())
+                    .map(str::to_string);
+                let (text, what) = match (selection, tool.primary_result()) {
+                    (Some(selection), _) => (selection, "selection"),
+                    (None, Some(result)) => (result, "result"),
+                    (None, None) => (self.message.clone(), "status"),
+                };
+                self.copy(&text, what);
+            }
+            Action::CopyRow => match self.tools[self.tab_index].selected_row() {
+                Some(row) => self.copy(&row, "row"),
+                None => self.message = "No row selected".to_string(),
+            },
+            Action::NextTool => self.select_tool((self.tab_index + 1) % self.tools.len()),
+            Action::PreviousTool => {
+                let count = self.tools.len();
+                self.select_tool((self.tab_index + count - 1) % count)
+            }
+            Action::NextField => self.tools[self.tab_index].cycle_focus(false),
+            Action::PreviousField => self.tools[self.tab_index].cycle_focus(true),
+            Action::CancelTask if !self.cancel_tasks() => {
+                self.message = format!("{} has no running task", tool_name);
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs what was picked in the command palette. Returns whether the app
+    /// should quit.
+    async fn run_palette(&mut self, target: Target, argument: String) -> bool {
+        let index = match target {
+            Target::Global(Action::Quit) => return true,
+            Target::Global(action) => {
+                self.run_global(action);
+                return false;
+            }
+            Target::Tool(index) | Target::Action(index, _) | Target::Command(index, _) => index,
+        };
+        self.select_tool(index);
+        if self.needs_token() {
+            return false;
+        }
+        let result = match target {
+            Target::Action(_, action) => self.tools[index].handle_action(action).await,
+            Target::Command(_, id) => self.tools[index].run_command(id, argument).await,
+            Target::Tool(_) | Target::Global(_) => return false,
+        };
+        self.message = result.unwrap_or_else(|e| e.to_string());
+        false
     }
 
     pub async fn run(
@@ -87,6 +401,7 @@ impl App {
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     ) -> io::Result<()> {
         loop {
+            let running = self.tasks.running();
             terminal.draw(|f| {
                 let chunks = Layout::default()
                     .direction(Direction::Vertical)
@@ -98,32 +413,88 @@ impl App {
                     ])
                     .split(f.area());
 
-                let tab_titles = self.tools.iter()
+                let tab_titles = self
+                    .tools
+                    .iter()
                     .map(|t| Line::from(Span::styled(t.name(), Style::default().fg(Color::Cyan))))
                     .collect::<Vec<Line>>();
                 let tabs = Tabs::new(tab_titles)
-                    .block(Block::default().borders(Borders::ALL).title(Span::styled("Dev-Toolbox", Style::default().fg(Color::Green))))
+                    .block(Block::default().borders(Borders::ALL).title(Span::styled(
+                        "Dev-Toolbox",
+                        Style::default().fg(Color::Green),
+                    )))
                     .select(self.tab_index)
                     .style(Style::default().fg(Color::White))
                     .highlight_style(Style::default().fg(Color::Yellow).bold());
                 f.render_widget(tabs, chunks[0]);
 
-                self.tools[self.tab_index].render(f, chunks[1]);
+                if self.needs_token() {
+                    self.render_token_panel(f, chunks[1]);
+                } else {
+                    self.tools[self.tab_index].render(f, chunks[1]);
+                }
 
-                let message = Paragraph::new(self.message.as_str())
-                    .block(Block::default().borders(Borders::ALL).title(Span::styled("Status", Style::default().fg(Color::Magenta))))
+                let mut status = vec![];
+                if redact::is_revealed() {
+                    status.push(Span::styled(
+                        "SECRETS REVEALED | ",
+                        Style::default().fg(Color::Black).bg(Color::Red).bold(),
+                    ));
+                }
+                if let Some(warning) = self.tools.iter().find_map(|t| t.status_warning()) {
+                    status.push(Span::styled(
+                        format!("⚠ {} | ", warning),
+                        Style::default().fg(Color::Red).bold(),
+                    ));
+                }
+                if let Some(task) = running.first() {
+                    let more = match running.len() {
+                        1 => String::new(),
+                        n => format!(" +{} more", n - 1),
+                    };
+                    status.push(Span::styled(
+                        format!(
+                            "{} {}: {} ({}s){} | ",
+                            components::spinner(task.elapsed),
+                            task.tool,
+                            task.progress.as_deref().unwrap_or(&task.label),
+                            task.elapsed.as_secs(),
+                            more
+                        ),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                status.push(Span::raw(redact::redact(&self.message)));
+                let message = Paragraph::new(Line::from(status))
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(Span::styled("Status", Style::default().fg(Color::Magenta))),
+                    )
                     .style(Style::default().fg(Color::White));
                 f.render_widget(message, chunks[2]);
 
-                let hints = Paragraph::new("Ctrl+F: Search | Ctrl+Q: Quit | Tab: Next Tool | Ctrl+C: Copy Status | Enter: Run/Action | Up/Down: Switch Fields | Ctrl+E: Export")
-                    .block(Block::default().borders(Borders::ALL).title(Span::styled("Hints", Style::default().fg(Color::Yellow))))
-                    .style(Style::default().fg(Color::Gray));
+                let hints = Paragraph::new(format!(
+                    "{} | Enter: Run/Action | PgUp/PgDn: Scroll",
+                    self.keymap.hints(self.tools[self.tab_index].name())
+                ))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(Span::styled("Hints", Style::default().fg(Color::Yellow))),
+                )
+                .style(Style::default().fg(Color::Gray));
                 f.render_widget(hints, chunks[3]);
 
-                if self.search_mode {
+                if self.palette.is_open() {
+                    let area = f.area();
+                    self.palette.render(f, area);
+                }
+
+                if self.credential_mode {
                     let area = f.area();
-                    let popup_width = 50;
-                    let popup_height = 10;
+                    let popup_width = 60;
+                    let popup_height = 12;
                     let popup_area = Rect::new(
                         area.width.saturating_sub(popup_width) / 2,
                         area.height.saturating_sub(popup_height) / 2,
@@ -132,105 +503,131 @@ impl App {
                     );
                     f.render_widget(Clear, popup_area);
 
-                    let search_chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([Constraint::Length(3), Constraint::Min(0)])
-                        .split(popup_area);
-
-                    let search_input = Paragraph::new(self.search_query.as_str())
-                        .block(Block::default().borders(Borders::ALL).title(Span::styled("Global Search (Esc: Cancel, Enter: Select)", Style::default().fg(Color::Green))));
-                    f.render_widget(search_input, search_chunks[0]);
-
+                    let tool = self.tools[self.tab_index].name();
+                    let current = self.secrets.credential_for(tool).map(|(name, _)| name);
                     let mut items = vec![];
-                    for (i, &idx) in self.search_results.iter().enumerate() {
-                        let style = if i == self.search_selected {
+                    for (i, (name, source)) in self.secrets.list().into_iter().enumerate() {
+                        let style = if i == self.credential_selected {
                             Style::default().bg(Color::Blue).fg(Color::White).bold()
                         } else {
                             Style::default()
                         };
-                        items.push(Line::from(Span::styled(self.tools[idx].name(), style)));
+                        let marker = if current.as_deref() == Some(name.as_str()) {
+                            "* "
+                        } else {
+                            "  "
+                        };
+                        items.push(Line::from(Span::styled(
+                            format!("{}{} ({})", marker, name, source),
+                            style,
+                        )));
                     }
                     if items.is_empty() {
-                        items.push(Line::from("No tools found."));
+                        items.push(Line::from("No credentials loaded."));
                     }
-                    let results_list = Paragraph::new(items).block(Block::default().borders(Borders::ALL));
-                    f.render_widget(results_list, search_chunks[1]);
+                    let list = Paragraph::new(items).block(
+                        Block::default().borders(Borders::ALL).title(Span::styled(
+                            format!(
+                                "Credentials for {} (Enter: Use, a: Make Active, Esc: Cancel)",
+                                tool
+                            ),
+                            Style::default().fg(Color::Green),
+                        )),
+                    );
+                    f.render_widget(list, popup_area);
                 }
             })?;
-            let event = crossterm::event::read()?;
-            match event {
-                Event::Key(key) => {
-                    if self.search_mode {
+            // Every event, including the tick, is followed by a redraw, so
+            // time-based UI such as rate-limit countdowns and task progress
+            // stays current without input.
+            match self.events.next().await? {
+                AppEvent::Tick => {
+                    for tool in &mut self.tools {
+                        if let Some(message) = tool.on_tick() {
+                            self.message = message;
+                        }
+                    }
+                }
+                AppEvent::TaskFinished(done) => self.finish_task(done),
+                AppEvent::Resize(..) => terminal.autoresize()?,
+                AppEvent::FocusGained => {
+                    if let Some(message) = self.tools[self.tab_index].on_focus() {
+                        self.message = message;
+                    }
+                }
+                AppEvent::FocusLost => self.tools[self.tab_index].on_blur(),
+                AppEvent::Input(Event::Key(key)) => {
+                    if self.credential_mode {
+                        let credentials = self.secrets.list();
+                        let tool = self.tools[self.tab_index].name();
                         match key.code {
                             KeyCode::Esc => {
-                                self.search_mode = false;
-                            }
-                            KeyCode::Enter => {
-                                if !self.search_results.is_empty() {
-                                    self.tab_index = self.search_results[self.search_selected];
-                                }
-                                self.search_mode = false;
+                                self.credential_mode = false;
                             }
                             KeyCode::Up => {
-                                self.search_selected = self.search_selected.saturating_sub(1);
+                                self.credential_selected =
+                                    self.credential_selected.saturating_sub(1);
                             }
-                            KeyCode::Down if !self.search_results.is_empty() => {
-                                self.search_selected = (self.search_selected + 1)
-                                    .min(self.search_results.len().saturating_sub(1));
+                            KeyCode::Down if !credentials.is_empty() => {
+                                self.credential_selected = (self.credential_selected + 1)
+                                    .min(credentials.len().saturating_sub(1));
                             }
-                            KeyCode::Char(c)
-                                if !key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                self.search_query.push(c);
-                                self.update_search();
+                            KeyCode::Enter => {
+                                if let Some((name, _)) = credentials.get(self.credential_selected) {
+                                    self.secrets.select_for_tool(tool, name);
+                                    self.message = format!(
+                                        "{} will use '{}' (press Enter in the tool to re-run)",
+                                        tool, name
+                                    );
+                                }
+                                self.credential_mode = false;
                             }
-                            KeyCode::Backspace => {
-                                self.search_query.pop();
-                                self.update_search();
+                            KeyCode::Char('a') => {
+                                if let Some((name, _)) = credentials.get(self.credential_selected) {
+                                    self.secrets.set_active(name);
+                                    self.message =
+                                        format!("'{}' is now the active credential", name);
+                                }
+                                self.credential_mode = false;
                             }
                             _ => {}
                         }
-                    } else {
-                        match key.code {
-                            KeyCode::Char('f')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                self.search_mode = true;
-                                self.search_query.clear();
-                                self.update_search();
+                    } else if self.palette.is_open() {
+                        if let Some((target, argument)) = self.palette.handle_key(&key) {
+                            if self.run_palette(target, argument).await {
+                                break;
                             }
-                            KeyCode::Char('q')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
+                        }
+                    } else {
+                        let tool_name = self.tools[self.tab_index].name();
+                        match self.keymap.action(&key, tool_name) {
+                            Some(Action::Quit) => break,
+                            Some(
+                                action @ (Action::Search
+                                | Action::Credentials
+                                | Action::RevealSecrets
+                                | Action::Copy
+                                | Action::CopyRow
+                                | Action::NextTool
+                                | Action::PreviousTool),
+                            ) => self.run_global(action),
+                            // Alt+1..Alt+9 jump to a tool by its position.
+                            None if key.modifiers == KeyModifiers::ALT
+                                && matches!(key.code, KeyCode::Char('1'..='9')) =>
                             {
-                                break
+                                let digit = key.code.as_char().unwrap_or('1');
+                                self.select_tool(digit as usize - '1' as usize)
                             }
-                            KeyCode::Char('c')
-                                if key
-                                    .modifiers
-                                    .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                            {
-                                if let Ok(mut clipboard) = Clipboard::new() {
-                                    let _ = clipboard.set_text(self.message.clone());
-                                    self.message =
-                                        "Copied to clipboard! (Cleared in 30s)".to_string();
-
-                                    tokio::spawn(async move {
-                                        tokio::time::sleep(tokio::time::Duration::from_secs(30))
-                                            .await;
-                                        if let Ok(mut cb) = Clipboard::new() {
-                                            let _ = cb.set_text("".to_string());
-                                        }
-                                    });
-                                }
+                            Some(Action::CancelTask) if self.cancel_tasks() => {}
+                            _ if self.needs_token() => self.handle_token_input(key),
+                            Some(action @ (Action::NextField | Action::PreviousField)) => {
+                                self.run_global(action)
                             }
-                            KeyCode::Tab => {
-                                self.tab_index = (self.tab_index + 1) % self.tools.len()
+                            Some(action) if !action.is_global() => {
+                                self.message = self.tools[self.tab_index]
+                                    .handle_action(action)
+                                    .await
+                                    .unwrap_or_else(|e| e.to_string());
                             }
                             _ => {
                                 self.message = self.tools[self.tab_index]
@@ -241,11 +638,27 @@ impl App {
                         }
                     }
                 }
-                Event::Mouse(mouse)
+                // The credential picker has no text field to paste into.
+                AppEvent::Input(Event::Paste(_)) if self.credential_mode => {}
+                AppEvent::Input(Event::Paste(text)) => {
+                    if self.palette.is_open() {
+                        self.palette.insert_str(&text);
+                    } else if self.needs_token() {
+                        self.token_input.insert_str(&text);
+                    } else if let Some(message) = self.tools[self.tab_index].handle_paste(&text) {
+                        self.message = message;
+                    }
+                }
+                AppEvent::Input(Event::Mouse(mouse))
                     if mouse.kind == MouseEventKind::Down(MouseButton::Left) && mouse.row == 1 =>
                 {
                     let tab_width = terminal.size()?.width / self.tools.len() as u16;
-                    self.tab_index = (mouse.column / tab_width) as usize;
+                    self.select_tool((mouse.column / tab_width.max(1)) as usize);
+                }
+                AppEvent::Input(Event::Mouse(mouse))
+                    if !self.palette.is_open() && !self.credential_mode && !self.needs_token() =>
+                {
+                    self.tools[self.tab_index].handle_mouse(&mouse);
                 }
                 _ => {}
             }
@@ -0,0 +1,570 @@
+use crate::config::CacheTtl;
+use crate::db;
+use crate::snapshots;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// A table in `cache.db` and how the cache tooling treats it.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheTable {
+    pub name: &'static str,
+    /// SQL expression describing one row in listings.
+    label: &'static str,
+    /// Unix timestamp column used for ages and TTLs.
+    time_column: Option<&'static str>,
+    /// Column holding the user, org or search query the row belongs to.
+    subject_column: Option<&'static str>,
+    /// Columns identifying a row across databases; `id` columns are local.
+    natural_key: &'static [&'static str],
+    /// User data rather than cache: exported, but never purged.
+    user_data: bool,
+    /// Rebuilt automatically, so purgeable but not worth exporting.
+    derived: bool,
+}
+
+pub const TABLES: &[CacheTable] = &[
+    CacheTable {
+        name: "repos",
+        label: "username || '/' || name",
+        time_column: Some("fetched_at"),
+        subject_column: Some("username"),
+        natural_key: &["username", "name"],
+        user_data: false,
+        derived: false,
+    },
+    CacheTable {
+        name: "snapshots",
+        label: "kind || ' #' || id || ' ' || subject",
+        time_column: Some("taken_at"),
+        subject_column: Some("subject"),
+        natural_key: &["kind", "subject", "taken_at"],
+        user_data: false,
+        derived: false,
+    },
+    CacheTable {
+        name: "token_inspections",
+        label: "owner || ' ' || kind || ' ' || fingerprint",
+        time_column: Some("inspected_at"),
+        subject_column: Some("owner"),
+        natural_key: &["inspected_at", "fingerprint"],
+        user_data: false,
+        derived: false,
+    },
+    CacheTable {
+        name: "watchlist",
+        label: "kind || ':' || target",
+        time_column: Some("added_at"),
+        subject_column: None,
+        natural_key: &["kind", "target"],
+        user_data: true,
+        derived: false,
+    },
+    CacheTable {
+        name: "unicode_chars",
+        label: "codepoint || ' ' || name",
+        time_column: None,
+        subject_column: None,
+        natural_key: &["codepoint"],
+        user_data: false,
+        derived: true,
+    },
+    CacheTable {
+        name: "unicode_blocks",
+        label: "name",
+        time_column: None,
+        subject_column: None,
+        natural_key: &["name"],
+        user_data: false,
+        derived: true,
+    },
+];
+
+pub fn table(name: &str) -> Result<&'static CacheTable, String> {
+    TABLES.iter().find(|t| t.name == name).ok_or_else(|| {
+        let names: Vec<&str> = TABLES.iter().map(|t| t.name).collect();
+        format!(
+            "Unknown cache table '{}' (expected one of: {})",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+impl CacheTable {
+    /// The configured TTL for this table in days, if it has one.
+    pub fn ttl_days(&self, ttl: &CacheTtl) -> Option<u64> {
+        match self.name {
+            "repos" => Some(ttl.repos),
+            "snapshots" => Some(ttl.snapshots),
+            "token_inspections" => Some(ttl.token_inspections),
+            _ => None,
+        }
+    }
+
+    pub fn is_user_data(&self) -> bool {
+        self.user_data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TableStats {
+    pub table: &'static CacheTable,
+    pub rows: i64,
+    /// Bytes used by the table and its indexes, if SQLite can report it.
+    pub bytes: Option<i64>,
+    pub oldest: Option<i64>,
+    pub newest: Option<i64>,
+}
+
+pub fn stats(conn: &Connection) -> Result<Vec<TableStats>, Box<dyn Error>> {
+    let mut all = Vec::new();
+    for table in TABLES {
+        let rows = conn.query_row(&format!("SELECT COUNT(*) FROM {}", table.name), [], |r| {
+            r.get(0)
+        })?;
+        let bytes = conn
+            .query_row(
+                "SELECT SUM(pgsize) FROM dbstat WHERE name = ?1
+                 OR name IN (SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = ?1)",
+                [table.name],
+                |r| r.get(0),
+            )
+            .ok()
+            .flatten();
+        let (oldest, newest) = match table.time_column {
+            Some(column) => conn.query_row(
+                &format!("SELECT MIN({0}), MAX({0}) FROM {1}", column, table.name),
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )?,
+            None => (None, None),
+        };
+        all.push(TableStats {
+            table,
+            rows,
+            bytes,
+            oldest,
+            newest,
+        });
+    }
+    Ok(all)
+}
+
+/// Size of the database file in bytes.
+pub fn database_size(conn: &Connection) -> Result<i64, Box<dyn Error>> {
+    let pages: i64 = conn.pragma_query_value(None, "page_count", |r| r.get(0))?;
+    let page_size: i64 = conn.pragma_query_value(None, "page_size", |r| r.get(0))?;
+    Ok(pages * page_size)
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub label: String,
+    pub at: Option<i64>,
+}
+
+/// The most recent entries of `table`, newest first where it has a timestamp.
+pub fn entries(
+    conn: &Connection,
+    table: &CacheTable,
+    limit: usize,
+) -> Result<Vec<CacheEntry>, Box<dyn Error>> {
+    let sql = match table.time_column {
+        Some(column) => format!(
+            "SELECT {}, {} FROM {} ORDER BY {} DESC LIMIT ?1",
+            table.label, column, table.name, column
+        ),
+        None => format!(
+            "SELECT {}, NULL FROM {} ORDER BY rowid LIMIT ?1",
+            table.label, table.name
+        ),
+    };
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map([limit as i64], |row| {
+        Ok(CacheEntry {
+            label: row.get(0)?,
+            at: row.get(1)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Which cached rows to delete. Filters combine; tables a filter does not
+/// apply to (e.g. an age filter on a table without timestamps) are skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PurgeFilter {
+    pub table: Option<String>,
+    /// A user or org login, or a stored search query.
+    pub subject: Option<String>,
+    pub older_than_secs: Option<i64>,
+}
+
+/// Deletes the rows matching `filter` and returns the count per table.
+/// User data such as the watchlist is never purged.
+pub fn purge(
+    conn: &mut Connection,
+    filter: &PurgeFilter,
+) -> Result<Vec<(&'static str, usize)>, Box<dyn Error>> {
+    let selected = match &filter.table {
+        Some(name) => {
+            let table = table(name)?;
+            if table.user_data {
+                return Err(format!("'{}' holds user data, not cache", table.name).into());
+            }
+            vec![table]
+        }
+        None => TABLES.iter().filter(|t| !t.user_data).collect(),
+    };
+
+    let now = snapshots::now_unix();
+    let tx = conn.transaction()?;
+    let mut deleted = Vec::new();
+    for table in selected {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+        if let Some(subject) = &filter.subject {
+            let Some(column) = table.subject_column else {
+                continue;
+            };
+            // Org Research stores `org:NAME term` queries as subjects.
+            values.push(Value::Text(subject.clone()));
+            conditions.push(format!(
+                "({0} = ?{1} COLLATE NOCASE OR {0} LIKE 'org:' || ?{1} || ' %')",
+                column,
+                values.len()
+            ));
+        }
+        if let Some(age) = filter.older_than_secs {
+            let Some(column) = table.time_column else {
+                continue;
+            };
+            values.push(Value::Integer(now - age));
+            conditions.push(format!("{} < ?{}", column, values.len()));
+        }
+        let mut sql = format!("DELETE FROM {}", table.name);
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        let count = tx.execute(&sql, params_from_iter(values))?;
+        if count > 0 {
+            deleted.push((table.name, count));
+        }
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Deletes entries older than their table's TTL.
+pub fn purge_expired(
+    conn: &mut Connection,
+    ttl: &CacheTtl,
+) -> Result<Vec<(&'static str, usize)>, Box<dyn Error>> {
+    let mut deleted = Vec::new();
+    for table in TABLES {
+        let Some(days) = table.ttl_days(ttl).filter(|d| *d > 0) else {
+            continue;
+        };
+        deleted.extend(purge(
+            conn,
+            &PurgeFilter {
+                table: Some(table.name.to_string()),
+                subject: None,
+                older_than_secs: Some(days as i64 * 86_400),
+            },
+        )?);
+    }
+    Ok(deleted)
+}
+
+pub fn vacuum(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    conn.execute_batch("VACUUM")?;
+    Ok(())
+}
+
+/// Identifies a cache archive written by [`export`].
+const ARCHIVE_FORMAT: &str = "dev-toolbox-cache";
+
+#[derive(Serialize, Deserialize)]
+struct Archive {
+    format: String,
+    schema_version: i64,
+    exported_at: i64,
+    tables: BTreeMap<String, ArchiveTable>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ArchiveTable {
+    columns: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+fn to_json(value: Value) -> Result<serde_json::Value, Box<dyn Error>> {
+    Ok(match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => i.into(),
+        Value::Real(f) => f.into(),
+        Value::Text(s) => s.into(),
+        Value::Blob(_) => return Err("Blob values cannot be exported".into()),
+    })
+}
+
+fn from_json(value: serde_json::Value) -> Result<Value, Box<dyn Error>> {
+    Ok(match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().ok_or("Invalid number in archive")?),
+        },
+        serde_json::Value::String(s) => Value::Text(s),
+        other => return Err(format!("Unexpected value in archive: {}", other).into()),
+    })
+}
+
+fn columns(conn: &Connection, table: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get(1))?;
+    Ok(names.collect::<Result<_, _>>()?)
+}
+
+/// Writes every table except derived ones to a gzip-compressed JSON archive
+/// that [`import`] can load into any database of the same or a newer schema.
+/// Returns the number of rows written.
+pub fn export(conn: &Connection, path: &Path) -> Result<usize, Box<dyn Error>> {
+    let mut archive = Archive {
+        format: ARCHIVE_FORMAT.to_string(),
+        schema_version: db::schema_version(conn)?,
+        exported_at: snapshots::now_unix(),
+        tables: BTreeMap::new(),
+    };
+    let mut total = 0;
+    for table in TABLES.iter().filter(|t| !t.derived) {
+        let columns: Vec<String> = columns(conn, table.name)?
+            .into_iter()
+            .filter(|c| c != "id")
+            .collect();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM {}",
+            columns.join(", "),
+            table.name
+        ))?;
+        let mut rows = Vec::new();
+        let mut query = stmt.query([])?;
+        while let Some(row) = query.next()? {
+            let mut values = Vec::with_capacity(columns.len());
+            for i in 0..columns.len() {
+                values.push(to_json(row.get(i)?)?);
+            }
+            rows.push(values);
+        }
+        total += rows.len();
+        archive
+            .tables
+            .insert(table.name.to_string(), ArchiveTable { columns, rows });
+    }
+
+    let file =
+        File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    serde_json::to_writer(&mut encoder, &archive)?;
+    encoder.finish()?;
+    Ok(total)
+}
+
+/// Loads an archive written by [`export`] in a single transaction. Rows with
+/// the same natural key as an existing row replace it; local row ids are
+/// reassigned. Returns the number of rows imported.
+pub fn import(conn: &mut Connection, path: &Path) -> Result<usize, Box<dyn Error>> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let archive: Archive = serde_json::from_reader(GzDecoder::new(BufReader::new(file)))
+        .map_err(|e| format!("{} is not a cache archive: {}", path.display(), e))?;
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(format!("{} is not a cache archive", path.display()).into());
+    }
+    if archive.schema_version > db::SCHEMA_VERSION {
+        return Err(format!(
+            "The archive has schema version {}, newer than this build supports ({})",
+            archive.schema_version,
+            db::SCHEMA_VERSION
+        )
+        .into());
+    }
+
+    let tx = conn.transaction()?;
+    let mut total = 0;
+    for (name, data) in archive.tables {
+        let table = table(&name)?;
+        let known = columns(&tx, table.name)?;
+        if let Some(unknown) = data.columns.iter().find(|c| !known.contains(c)) {
+            return Err(format!("Unknown column {}.{} in archive", name, unknown).into());
+        }
+        let key_positions: Vec<usize> = table
+            .natural_key
+            .iter()
+            .map(|k| data.columns.iter().position(|c| c == k))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("Archive table {} is missing its key columns", name))?;
+
+        let delete = format!(
+            "DELETE FROM {} WHERE {}",
+            table.name,
+            table
+                .natural_key
+                .iter()
+                .enumerate()
+                .map(|(i, k)| format!("{} = ?{}", k, i + 1))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        );
+        let insert = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table.name,
+            data.columns.join(", "),
+            (1..=data.columns.len())
+                .map(|i| format!("?{}", i))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for row in data.rows {
+            if row.len() != data.columns.len() {
+                return Err(format!("Malformed row in archive table {}", name).into());
+            }
+            let values = row
+                .into_iter()
+                .map(from_json)
+                .collect::<Result<Vec<_>, _>>()?;
+            tx.execute(
+                &delete,
+                params_from_iter(key_positions.iter().map(|&i| &values[i])),
+            )?;
+            tx.execute(&insert, params_from_iter(&values))?;
+            total += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(total)
+}
+
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Compact age such as `45s`, `12m`, `5h` or `3d`.
+pub fn format_age(ts: i64) -> String {
+    let secs = (snapshots::now_unix() - ts).max(0);
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::snapshots::SnapshotKind;
+    use rusqlite::params;
+
+    fn insert_repo(conn: &Connection, username: &str, name: &str, at: i64) {
+        conn.execute(
+            "INSERT INTO repos (username, name, data, fetched_at) VALUES (?1, ?2, '{}', ?3)",
+            params![username, name, at],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_purge_by_subject_age_and_ttl() {
+        let conn = &mut db::open(":memory:").unwrap();
+        let now = snapshots::now_unix();
+        insert_repo(conn, "octocat", "old", now - 40 * 86_400);
+        insert_repo(conn, "octocat", "new", now);
+        insert_repo(conn, "rustacean", "tool", now);
+        snapshots::save(conn, SnapshotKind::Orgs, "org:rust-lang team", &"[]").unwrap();
+        crate::watchlist::add(conn, crate::watchlist::WatchKind::User, "octocat").unwrap();
+
+        let deleted = purge_expired(conn, &CacheTtl::default()).unwrap();
+        assert_eq!(deleted, vec![("repos", 1)]);
+
+        let deleted = purge(
+            conn,
+            &PurgeFilter {
+                subject: Some("OctoCat".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(deleted, vec![("repos", 1)]);
+
+        let deleted = purge(
+            conn,
+            &PurgeFilter {
+                subject: Some("rust-lang".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(deleted, vec![("snapshots", 1)]);
+
+        let stats = stats(conn).unwrap();
+        let rows = |name: &str| stats.iter().find(|s| s.table.name == name).unwrap().rows;
+        assert_eq!(rows("repos"), 1);
+        assert_eq!(rows("watchlist"), 1);
+        assert!(purge(
+            conn,
+            &PurgeFilter {
+                table: Some("watchlist".into()),
+                ..Default::default()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("cache.json.gz");
+
+        let source = db::open(":memory:").unwrap();
+        insert_repo(&source, "octocat", "hello-world", 1_700_000_000);
+        snapshots::save(&source, SnapshotKind::Repos, "octocat", &"[]").unwrap();
+        assert_eq!(export(&source, &archive).unwrap(), 2);
+
+        let mut target = db::open(":memory:").unwrap();
+        snapshots::save(&target, SnapshotKind::Repos, "someone", &"[]").unwrap();
+        assert_eq!(import(&mut target, &archive).unwrap(), 2);
+        // Importing again replaces rows instead of duplicating them.
+        assert_eq!(import(&mut target, &archive).unwrap(), 2);
+
+        let stats = stats(&target).unwrap();
+        let rows = |name: &str| stats.iter().find(|s| s.table.name == name).unwrap().rows;
+        assert_eq!(rows("repos"), 1);
+        assert_eq!(rows("snapshots"), 2);
+        let fetched_at: i64 = target
+            .query_row("SELECT fetched_at FROM repos", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(fetched_at, 1_700_000_000);
+    }
+}
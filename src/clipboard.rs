@@ -0,0 +1,100 @@
+use arboard::Clipboard;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+fn digest(text: &str) -> [u8; 32] {
+    Sha256::digest(text.as_bytes()).into()
+}
+
+#[cfg(target_os = "linux")]
+fn set_primary(clipboard: &mut Clipboard, text: &str) -> Result<(), arboard::Error> {
+    use arboard::{LinuxClipboardKind, SetExtLinux};
+    clipboard
+        .set()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text(text.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_primary(_clipboard: &mut Clipboard, _text: &str) -> Result<(), arboard::Error> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn get_primary(clipboard: &mut Clipboard) -> Option<String> {
+    use arboard::{GetExtLinux, LinuxClipboardKind};
+    clipboard
+        .get()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text()
+        .ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_primary(_clipboard: &mut Clipboard) -> Option<String> {
+    None
+}
+
+/// Copies `text` to the clipboard and, if `primary` is set, to the X11
+/// primary selection (middle-click paste). With a non-zero `clear_after_secs`
+/// both are cleared after that many seconds, but only if they still hold
+/// this text: anything the user copied in the meantime is left alone.
+///
+/// Only a SHA-256 digest of the text is kept for that comparison.
+///
+/// Returns the error from setting the primary selection, if that alone
+/// failed, e.g. on a Wayland compositor without the data-control protocol.
+pub fn copy(
+    text: &str,
+    clear_after_secs: u64,
+    primary: bool,
+) -> Result<Option<arboard::Error>, arboard::Error> {
+    let mut clipboard = Clipboard::new()?;
+    clipboard.set_text(text.to_string())?;
+    let primary_error = if primary {
+        set_primary(&mut clipboard, text).err()
+    } else {
+        None
+    };
+    let primary = primary && primary_error.is_none();
+
+    if clear_after_secs > 0 {
+        let ours = digest(text);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(clear_after_secs)).await;
+            clear_if_ours(ours, primary);
+        });
+    }
+    Ok(primary_error)
+}
+
+/// Whether `current` is still the copied text with digest `ours`.
+fn is_ours(current: Option<&str>, ours: &[u8; 32]) -> bool {
+    current.is_some_and(|text| digest(text) == *ours)
+}
+
+fn clear_if_ours(ours: [u8; 32], primary: bool) {
+    let Ok(mut clipboard) = Clipboard::new() else {
+        return;
+    };
+    if is_ours(clipboard.get_text().ok().as_deref(), &ours) {
+        let _ = clipboard.clear();
+    }
+    if primary && is_ours(get_primary(&mut clipboard).as_deref(), &ours) {
+        let _ = set_primary(&mut clipboard, "");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_unchanged_text_is_ours() {
+        let ours = digest("ghp_copied");
+        assert!(is_ours(Some("ghp_copied"), &ours));
+        assert!(!is_ours(Some("copied by the user later"), &ours));
+        assert!(!is_ours(Some(""), &ours));
+        assert!(!is_ours(None, &ours));
+    }
+}
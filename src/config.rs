@@ -1,15 +1,504 @@
+use crate::keymap::Keymap;
+use crate::secrets::{CredentialSource, TokenSource};
+use crate::unicode_data::{self, DataSource};
 use dirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Version written by this build. Files without `config_version` are
+/// version 1 and are upgraded by [`migrate`].
+pub const CURRENT_CONFIG_VERSION: i64 = 3;
+
+/// Upgrades a config table by one version; index `n` upgrades version
+/// `n + 1` to `n + 2`.
+const MIGRATIONS: &[fn(&mut toml::Table)] = &[
+    // 1 -> 2: version 1 files predate `config_version` and every field added
+    // since has a default, so only the version stamp is new.
+    |_| {},
+    // 2 -> 3: the relative `UnicodeData.txt` / `Blocks.txt` defaults only
+    // worked from the repository root. Dropping them lets the data files be
+    // found in the data directories or the embedded copy instead.
+    |table| {
+        for (key, old_default) in [
+            ("unicode_data_path", "UnicodeData.txt"),
+            ("blocks_path", "Blocks.txt"),
+        ] {
+            if table.get(key).and_then(|v| v.as_str()) == Some(old_default) {
+                table.remove(key);
+            }
+        }
+    },
+];
+
+/// Top-level keys that are valid in a config file but not always written out.
+const OPTIONAL_KEYS: &[&str] = &["credentials", "tool_credentials", "keybindings"];
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct Config {
+    pub config_version: i64,
+    /// `UnicodeData.txt` to use instead of the data directories or the
+    /// embedded copy; empty to resolve automatically.
     pub unicode_data_path: String,
+    /// `Blocks.txt` to use instead of the data directories or the embedded
+    /// copy; empty to resolve automatically.
     pub blocks_path: String,
     pub cache_db_path: String,
     pub github_api_base_url: String,
+    /// Seconds between background watchlist refreshes; `0` only refreshes at startup.
+    #[serde(default = "default_watch_interval_secs")]
+    pub watch_interval_secs: u64,
+    /// Warn in the status bar when the GitHub token expires within this many days.
+    #[serde(default = "default_token_expiry_warning_days")]
+    pub token_expiry_warning_days: u32,
+    /// Seconds before copied text is cleared from the clipboard if it is
+    /// still there; `0` never clears.
+    #[serde(default = "default_clipboard_clear_secs")]
+    pub clipboard_clear_secs: u64,
+    /// Also copy to the X11 primary selection (middle-click paste) on Linux.
+    #[serde(default)]
+    pub clipboard_primary_selection: bool,
+    /// Named GitHub credentials read from an environment variable or a file.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub credentials: BTreeMap<String, CredentialSource>,
+    /// Where to look for a token when `GITHUB_TOKEN` is not set, in order;
+    /// none unless configured.
+    #[serde(default)]
+    pub token_sources: Vec<TokenSource>,
+    /// Default credential name per tool, keyed by the tool's tab name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_credentials: BTreeMap<String, String>,
+    /// How long cached data is kept before it is purged at startup.
+    pub cache_ttl_days: CacheTtl,
+    /// Milliseconds between redraws when nothing else happens, e.g. for
+    /// countdowns and task progress.
+    #[serde(default = "default_tick_rate_ms")]
+    pub tick_rate_ms: u64,
+    /// Shortcut overrides keyed by action name, e.g. `export = "ctrl+x"`;
+    /// `"none"` unbinds an action. See [`Keymap`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, String>,
+}
+
+/// Days each type of cached data is kept; `0` keeps it until purged with
+/// `dev-toolbox cache purge` or the Cache tool.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheTtl {
+    /// Repositories saved by the Repo Explorer.
+    pub repos: u64,
+    /// Org Research / Repo Explorer snapshots used for change history.
+    pub snapshots: u64,
+    /// Token Inspector history used to flag broadened tokens.
+    pub token_inspections: u64,
+}
+
+impl Default for CacheTtl {
+    fn default() -> Self {
+        CacheTtl {
+            repos: 30,
+            snapshots: 0,
+            token_inspections: 0,
+        }
+    }
+}
+
+fn default_watch_interval_secs() -> u64 {
+    900
+}
+
+fn default_clipboard_clear_secs() -> u64 {
+    30
+}
+
+fn default_tick_rate_ms() -> u64 {
+    250
+}
+
+fn default_token_expiry_warning_days() -> u32 {
+    7
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::default_with_paths()
+    }
+}
+
+/// Prefix of environment variables overriding top-level keys, e.g.
+/// `DEV_TOOLBOX_WATCH_INTERVAL_SECS=60`.
+const ENV_PREFIX: &str = "DEV_TOOLBOX_";
+
+/// Selects a `[profile.*]` section when `--profile` is not given.
+const PROFILE_ENV: &str = "DEV_TOOLBOX_PROFILE";
+
+/// Command-line options that affect which configuration layers are read.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    pub config_path: Option<PathBuf>,
+    pub profile: Option<String>,
+}
+
+/// Where each effective value came from, keyed by dotted path
+/// (`credentials.audit.env`).
+#[derive(Debug, Clone, Default)]
+pub struct Provenance(BTreeMap<String, String>);
+
+impl Provenance {
+    pub fn source(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|s| s.as_str())
+    }
+
+    fn record(&mut self, path: &str, value: &toml::Value, source: &str) {
+        let nested = format!("{}.", path);
+        self.0.retain(|k, _| k != path && !k.starts_with(&nested));
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    self.record(&format!("{}.{}", path, key), value, source);
+                }
+            }
+            _ => {
+                self.0.insert(path.to_string(), source.to_string());
+            }
+        }
+    }
+}
+
+/// The effective configuration with the provenance of every value and any
+/// warnings (unknown keys, upgraded files) found while loading it.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub provenance: Provenance,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckLevel {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// One line of `dev-toolbox config check` output.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub level: CheckLevel,
+    pub message: String,
+}
+
+impl CheckResult {
+    fn ok(message: String) -> Self {
+        CheckResult {
+            level: CheckLevel::Ok,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        CheckResult {
+            level: CheckLevel::Warning,
+            message,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        CheckResult {
+            level: CheckLevel::Error,
+            message,
+        }
+    }
+}
+
+/// One source of configuration values, in increasing precedence.
+pub struct Layer {
+    pub source: String,
+    pub values: toml::Table,
+}
+
+fn system_config_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("ProgramData")
+            .map(|dir| PathBuf::from(dir).join("dev-toolbox").join("config.toml"))
+    } else {
+        Some(PathBuf::from("/etc/dev-toolbox/config.toml"))
+    }
+}
+
+fn read_layer(path: &Path, source: String) -> Result<Layer, Box<dyn Error>> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let mut values = toml::from_str(&content).map_err(|e| format!("{}: {}", source, e))?;
+    migrate(&mut values).map_err(|e| format!("{}: {}", source, e))?;
+    Ok(Layer { source, values })
+}
+
+/// Upgrades `table` to [`CURRENT_CONFIG_VERSION`] and returns the version it
+/// had before. Files from a newer build are rejected rather than guessed at.
+pub fn migrate(table: &mut toml::Table) -> Result<i64, String> {
+    let version = match table.get("config_version") {
+        None => 1,
+        Some(toml::Value::Integer(v)) if *v >= 1 => *v,
+        Some(other) => return Err(format!("invalid config_version {}", other)),
+    };
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(format!(
+            "config_version {} is newer than this build supports ({}); please upgrade dev-toolbox",
+            version, CURRENT_CONFIG_VERSION
+        ));
+    }
+    for step in &MIGRATIONS[(version - 1) as usize..] {
+        step(table);
+    }
+    table.insert(
+        "config_version".to_string(),
+        toml::Value::Integer(CURRENT_CONFIG_VERSION),
+    );
+    Ok(version)
+}
+
+/// Upgrades an old config file in place, keeping the original next to it as
+/// `config.toml.v<N>.bak`. Returns a note describing the upgrade, if any.
+///
+/// Only the top-level keys the migrations changed are rewritten, so comments
+/// and formatting elsewhere in the file are kept.
+fn migrate_file(path: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let original: toml::Table =
+        toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut table = original.clone();
+    let version = migrate(&mut table).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if version == CURRENT_CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let mut document: toml_edit::DocumentMut = content
+        .parse()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    for key in original.keys().filter(|key| !table.contains_key(*key)) {
+        document.remove(key);
+    }
+    for (key, value) in &table {
+        if original.get(key) != Some(value) {
+            document[key] = edit_item(key, value)?;
+        }
+    }
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", version));
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup)?;
+    fs::write(path, document.to_string())?;
+    Ok(Some(format!(
+        "Upgraded {} from config version {} to {} (backup: {})",
+        path.display(),
+        version,
+        CURRENT_CONFIG_VERSION,
+        backup.display()
+    )))
+}
+
+/// Converts a value set by a migration into an item for [`migrate_file`].
+fn edit_item(key: &str, value: &toml::Value) -> Result<toml_edit::Item, Box<dyn Error>> {
+    let mut single = toml::Table::new();
+    single.insert(key.to_string(), value.clone());
+    let mut document: toml_edit::DocumentMut = toml::to_string(&single)?.parse()?;
+    Ok(document.remove(key).unwrap_or_default())
+}
+
+/// Levenshtein distance, used to suggest the intended key for a typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = (prev + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(current + 1);
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+fn unknown_key_warning(source: &str, key: &str, known: &[String]) -> String {
+    let suggestion = known
+        .iter()
+        .map(|k| (edit_distance(key, k), k))
+        .filter(|(d, _)| *d <= 3)
+        .min()
+        .map(|(_, k)| format!(" (did you mean `{}`?)", k))
+        .unwrap_or_default();
+    format!("{}: unknown key `{}` is ignored{}", source, key, suggestion)
+}
+
+/// Reports unknown keys and checks each known key on its own against the
+/// defaults, so a type error names the layer and key that caused it.
+fn check_layer(
+    values: &toml::Table,
+    source: &str,
+    defaults: &toml::Table,
+    known: &[String],
+    warnings: &mut Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    for (key, value) in values {
+        if key == "profile" {
+            continue;
+        }
+        if !known.contains(key) {
+            warnings.push(unknown_key_warning(source, key, known));
+            continue;
+        }
+        let mut candidate = defaults.clone();
+        candidate.insert(key.clone(), value.clone());
+        toml::Value::Table(candidate)
+            .try_into::<Config>()
+            .map_err(|e| format!("{}: invalid `{}`: {}", source, key, e.message()))?;
+    }
+    Ok(())
+}
+
+/// Merges `overlay` into `base`. Tables merge key by key; any other value
+/// replaces what was there.
+fn merge(
+    base: &mut toml::Table,
+    overlay: toml::Table,
+    source: &str,
+    prefix: &str,
+    provenance: &mut Provenance,
+) {
+    for (key, value) in overlay {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge(existing, table, source, &path, provenance)
+            }
+            (_, value) => {
+                provenance.record(&path, &value, source);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Parses an environment override with the type of the value it replaces.
+fn parse_env_value(raw: &str, current: &toml::Value) -> Result<toml::Value, String> {
+    match current {
+        toml::Value::String(_) => Ok(toml::Value::String(raw.to_string())),
+        toml::Value::Integer(_) => raw
+            .parse()
+            .map(toml::Value::Integer)
+            .map_err(|_| format!("expected an integer, got '{}'", raw)),
+        toml::Value::Float(_) => raw
+            .parse()
+            .map(toml::Value::Float)
+            .map_err(|_| format!("expected a number, got '{}'", raw)),
+        toml::Value::Boolean(_) => raw
+            .parse()
+            .map(toml::Value::Boolean)
+            .map_err(|_| format!("expected true or false, got '{}'", raw)),
+        _ => toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut t| t.remove("value"))
+            .filter(|v| v.same_type(current))
+            .ok_or_else(|| format!("expected a TOML {}, got '{}'", current.type_str(), raw)),
+    }
+}
+
+/// Resolves the effective configuration from file layers, an optional
+/// profile and environment variables.
+pub fn resolve(
+    layers: Vec<Layer>,
+    profile: Option<&str>,
+    env: impl Iterator<Item = (String, String)>,
+) -> Result<LoadedConfig, Box<dyn Error>> {
+    let defaults = toml::Table::try_from(Config::default_with_paths())?;
+    let known: Vec<String> = defaults
+        .keys()
+        .cloned()
+        .chain(OPTIONAL_KEYS.iter().map(|k| k.to_string()))
+        .collect();
+    let mut warnings = Vec::new();
+    let mut provenance = Provenance::default();
+    let mut merged = toml::Table::new();
+    merge(
+        &mut merged,
+        defaults.clone(),
+        "default",
+        "",
+        &mut provenance,
+    );
+    for layer in layers {
+        check_layer(
+            &layer.values,
+            &layer.source,
+            &defaults,
+            &known,
+            &mut warnings,
+        )?;
+        merge(
+            &mut merged,
+            layer.values,
+            &layer.source,
+            "",
+            &mut provenance,
+        );
+    }
+
+    let profiles = match merged.remove("profile") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => return Err("`profile` must be a table of [profile.<name>] sections".into()),
+        None => toml::Table::new(),
+    };
+    provenance.0.retain(|k, _| !k.starts_with("profile."));
+    if let Some(name) = profile {
+        let source = format!("profile '{}'", name);
+        match profiles.get(name) {
+            Some(toml::Value::Table(values)) => {
+                check_layer(values, &source, &defaults, &known, &mut warnings)?;
+                merge(&mut merged, values.clone(), &source, "", &mut provenance)
+            }
+            _ => return Err(format!("Unknown config profile '{}'", name).into()),
+        }
+    }
+
+    let mut overrides: Vec<(String, String)> = env
+        .filter(|(k, _)| {
+            k.starts_with(ENV_PREFIX) && k != PROFILE_ENV && k != crate::vault::PASSPHRASE_FILE_ENV
+        })
+        .collect();
+    overrides.sort();
+    for (var, raw) in overrides {
+        let key = var[ENV_PREFIX.len()..].to_lowercase();
+        let Some(current) = merged.get(&key).filter(|v| !v.is_table()) else {
+            warnings.push(format!(
+                "env {}: does not match a config key and is ignored",
+                var
+            ));
+            continue;
+        };
+        let value = parse_env_value(&raw, current).map_err(|e| format!("{}: {}", var, e))?;
+        provenance.record(&key, &value, &format!("env {}", var));
+        merged.insert(key, value);
+    }
+
+    let config: Config = toml::Value::Table(merged).try_into()?;
+    Ok(LoadedConfig {
+        config,
+        provenance,
+        warnings,
+    })
 }
 
 impl Config {
@@ -19,32 +508,212 @@ impl Config {
         let _ = fs::create_dir_all(&data_dir);
 
         Self {
-            unicode_data_path: "UnicodeData.txt".to_string(),
-            blocks_path: "Blocks.txt".to_string(),
+            config_version: CURRENT_CONFIG_VERSION,
+            unicode_data_path: String::new(),
+            blocks_path: String::new(),
             cache_db_path: data_dir.join("cache.db").to_string_lossy().to_string(),
             github_api_base_url: "https://api.github.com".to_string(),
+            watch_interval_secs: default_watch_interval_secs(),
+            token_expiry_warning_days: default_token_expiry_warning_days(),
+            clipboard_clear_secs: default_clipboard_clear_secs(),
+            clipboard_primary_selection: false,
+            credentials: BTreeMap::new(),
+            token_sources: Vec::new(),
+            tool_credentials: BTreeMap::new(),
+            cache_ttl_days: CacheTtl::default(),
+            tick_rate_ms: default_tick_rate_ms(),
+            keybindings: BTreeMap::new(),
         }
     }
 
-    pub fn load() -> Result<Self, Box<dyn Error>> {
+    /// Loads the layered configuration: built-in defaults, the system file,
+    /// the user file, `--config`, the selected `[profile.*]` section and
+    /// `DEV_TOOLBOX_*` environment variables, each overriding the previous.
+    pub fn load(options: &LoadOptions) -> Result<LoadedConfig, Box<dyn Error>> {
+        let mut layers = Vec::new();
+        let mut notes = Vec::new();
+
+        let system_path = system_config_path();
+        if let Some(path) = system_path.as_ref().filter(|p| p.exists()) {
+            layers.push(read_layer(path, format!("system file {}", path.display()))?);
+        }
+
         let mut config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
         config_dir.push("dev-toolbox");
         let _ = fs::create_dir_all(&config_dir);
-        let config_path = config_dir.join("config.toml");
+        let user_path = config_dir.join("config.toml");
+        if user_path.exists() {
+            notes.extend(migrate_file(&user_path)?);
+            layers.push(read_layer(
+                &user_path,
+                format!("user file {}", user_path.display()),
+            )?);
+        } else if !system_path.is_some_and(|p| p.exists()) {
+            // First run without a system-wide config: write the defaults so
+            // there is a file to edit. With a system file present this would
+            // shadow every system-wide value, so nothing is written.
+            let content = toml::to_string(&Config::default_with_paths())?;
+            fs::write(&user_path, content)?;
+        }
+
+        if let Some(path) = &options.config_path {
+            layers.push(read_layer(path, format!("--config {}", path.display()))?);
+        }
+
+        let profile = options
+            .profile
+            .clone()
+            .or_else(|| std::env::var(PROFILE_ENV).ok());
+        let mut loaded = resolve(layers, profile.as_deref(), std::env::vars())?;
+        loaded.config.validate()?;
+        let keymap = loaded.config.keymap()?;
+        notes.extend(keymap.warnings());
+        notes.append(&mut loaded.warnings);
+        loaded.warnings = notes;
+        Ok(loaded)
+    }
+
+    /// One `key = value  # source` line per effective value, for
+    /// `config show --effective`.
+    pub fn effective_lines(&self, provenance: &Provenance) -> Result<Vec<String>, Box<dyn Error>> {
+        fn walk(prefix: &str, table: &toml::Table, provenance: &Provenance, out: &mut Vec<String>) {
+            for (key, value) in table {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                match value {
+                    toml::Value::Table(nested) => walk(&path, nested, provenance, out),
+                    _ => out.push(format!(
+                        "{} = {}  # {}",
+                        path,
+                        value,
+                        provenance.source(&path).unwrap_or("default")
+                    )),
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        walk("", &toml::Table::try_from(self)?, provenance, &mut lines);
+        Ok(lines)
+    }
 
-        let config = if config_path.exists() {
-            let content = fs::read_to_string(config_path)?;
-            let config: Config = toml::from_str(&content)?;
-            config
+    /// Checks that configured files and URLs are usable, for
+    /// `dev-toolbox config check`.
+    pub fn check(&self) -> Vec<CheckResult> {
+        let mut results = Vec::new();
+
+        for (key, path, file) in [
+            (
+                "unicode_data_path",
+                &self.unicode_data_path,
+                unicode_data::UNICODE_DATA,
+            ),
+            ("blocks_path", &self.blocks_path, unicode_data::BLOCKS),
+        ] {
+            let source = unicode_data::resolve(path, file);
+            results.push(
+                if !path.is_empty() && !matches!(source, DataSource::Config(_)) {
+                    CheckResult::warning(format!("{}: {} not found, using {}", key, path, source))
+                } else {
+                    CheckResult::ok(format!("{}: {}", key, source))
+                },
+            );
+        }
+
+        let db_path = Path::new(&self.cache_db_path);
+        let parent = db_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        results.push(if !parent.is_dir() {
+            CheckResult::error(format!(
+                "cache_db_path: directory {} does not exist",
+                parent.display()
+            ))
+        } else if db_path.exists() {
+            match rusqlite::Connection::open(db_path).and_then(|c| crate::db::schema_version(&c)) {
+                Ok(v) if v > crate::db::SCHEMA_VERSION => CheckResult::error(format!(
+                    "cache_db_path: {} has schema version {}, newer than this build supports ({})",
+                    self.cache_db_path,
+                    v,
+                    crate::db::SCHEMA_VERSION
+                )),
+                Ok(v) if v < crate::db::SCHEMA_VERSION => CheckResult::ok(format!(
+                    "cache_db_path: {} (schema version {}, will be upgraded to {})",
+                    self.cache_db_path,
+                    v,
+                    crate::db::SCHEMA_VERSION
+                )),
+                Ok(_) => CheckResult::ok(format!("cache_db_path: {}", self.cache_db_path)),
+                Err(e) => CheckResult::error(format!(
+                    "cache_db_path: {} is not a usable SQLite database: {}",
+                    self.cache_db_path, e
+                )),
+            }
         } else {
-            let config = Config::default_with_paths();
-            let content = toml::to_string(&config)?;
-            fs::write(config_path, content)?;
-            config
-        };
+            CheckResult::ok(format!(
+                "cache_db_path: {} (will be created)",
+                self.cache_db_path
+            ))
+        });
 
-        config.validate()?;
-        Ok(config)
+        results.push(match self.validate() {
+            Ok(()) => CheckResult::ok(format!("github_api_base_url: {}", self.github_api_base_url)),
+            Err(e) => CheckResult::error(format!("github_api_base_url: {}", e)),
+        });
+
+        let min_tick = crate::events::MIN_TICK.as_millis() as u64;
+        if self.tick_rate_ms < min_tick {
+            results.push(CheckResult::warning(format!(
+                "tick_rate_ms: {} is below the minimum of {}ms, which is used instead",
+                self.tick_rate_ms, min_tick
+            )));
+        }
+
+        for (name, source) in &self.credentials {
+            results.push(match (&source.env, &source.file) {
+                (Some(var), None) if std::env::var_os(var).is_some() => {
+                    CheckResult::ok(format!("credentials.{}: env {}", name, var))
+                }
+                (Some(var), None) => CheckResult::warning(format!(
+                    "credentials.{}: {} is not set; the credential is skipped",
+                    name, var
+                )),
+                (None, Some(file)) if Path::new(file).is_file() => {
+                    CheckResult::ok(format!("credentials.{}: file {}", name, file))
+                }
+                (None, Some(file)) => {
+                    CheckResult::error(format!("credentials.{}: {} not found", name, file))
+                }
+                _ => CheckResult::error(format!(
+                    "credentials.{}: set exactly one of `env` or `file`",
+                    name
+                )),
+            });
+        }
+
+        // Bindings that load but will not fire are reported with the other
+        // load warnings.
+        results.push(match self.keymap() {
+            Ok(_) if self.keybindings.is_empty() => {
+                CheckResult::ok("keybindings: defaults".to_string())
+            }
+            Ok(_) => CheckResult::ok(format!(
+                "keybindings: {} customized",
+                self.keybindings.len()
+            )),
+            Err(e) => CheckResult::error(e.to_string()),
+        });
+
+        results
+    }
+
+    /// The shortcuts with the `[keybindings]` overrides applied.
+    pub fn keymap(&self) -> Result<Keymap, Box<dyn Error>> {
+        Ok(Keymap::new(&self.keybindings).map_err(|e| format!("keybindings: {}", e))?)
     }
 
     pub fn validate(&self) -> Result<(), Box<dyn Error>> {
@@ -87,4 +756,113 @@ mod tests {
         config.github_api_base_url = "https://malicious.com".to_string();
         assert!(config.validate().is_err());
     }
+
+    fn layer(source: &str, content: &str) -> Layer {
+        Layer {
+            source: source.to_string(),
+            values: toml::from_str(content).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_layers_profile_and_env() {
+        let layers = vec![
+            layer("system", "watch_interval_secs = 600\ntoken_expiry_warning_days = 14"),
+            layer(
+                "user",
+                "watch_interval_secs = 300\n[profile.work]\nclipboard_clear_secs = 0\n[profile.work.tool_credentials]\nWatchlist = \"work\"",
+            ),
+        ];
+        let env = vec![
+            (
+                "DEV_TOOLBOX_TOKEN_EXPIRY_WARNING_DAYS".to_string(),
+                "3".to_string(),
+            ),
+            ("DEV_TOOLBOX_PROFILE".to_string(), "ignored".to_string()),
+            ("UNRELATED".to_string(), "1".to_string()),
+        ];
+        let LoadedConfig {
+            config, provenance, ..
+        } = resolve(layers, Some("work"), env.into_iter()).unwrap();
+
+        assert_eq!(config.watch_interval_secs, 300);
+        assert_eq!(config.token_expiry_warning_days, 3);
+        assert_eq!(config.clipboard_clear_secs, 0);
+        assert_eq!(config.tool_credentials["Watchlist"], "work");
+        assert_eq!(provenance.source("watch_interval_secs"), Some("user"));
+        assert_eq!(
+            provenance.source("token_expiry_warning_days"),
+            Some("env DEV_TOOLBOX_TOKEN_EXPIRY_WARNING_DAYS")
+        );
+        assert_eq!(
+            provenance.source("tool_credentials.Watchlist"),
+            Some("profile 'work'")
+        );
+        assert_eq!(provenance.source("cache_db_path"), Some("default"));
+
+        assert!(resolve(Vec::new(), Some("missing"), std::iter::empty()).is_err());
+        let bad_env = vec![(
+            "DEV_TOOLBOX_WATCH_INTERVAL_SECS".to_string(),
+            "soon".to_string(),
+        )];
+        assert!(resolve(Vec::new(), None, bad_env.into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_migrate_file_with_backup_and_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let original = "# Refresh every minute.\nwatch_interval_secs = 60 # seconds\n\
+                        unicode_data_path = \"UnicodeData.txt\"\n\n\
+                        [keybindings]\n# Easier to reach.\nquit = \"ctrl+x\"\n";
+        fs::write(&path, original).unwrap();
+
+        let note = migrate_file(&path).unwrap().unwrap();
+        assert!(note.contains(&format!(
+            "from config version 1 to {}",
+            CURRENT_CONFIG_VERSION
+        )));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("config.toml.v1.bak")).unwrap(),
+            original
+        );
+        let content = fs::read_to_string(&path).unwrap();
+        for comment in ["# Refresh every minute.", "# seconds", "# Easier to reach."] {
+            assert!(content.contains(comment), "{} lost:\n{}", comment, content);
+        }
+        let upgraded: toml::Table = toml::from_str(&content).unwrap();
+        assert_eq!(
+            upgraded["config_version"].as_integer(),
+            Some(CURRENT_CONFIG_VERSION)
+        );
+        assert_eq!(upgraded["watch_interval_secs"].as_integer(), Some(60));
+        assert!(!upgraded.contains_key("unicode_data_path"));
+        assert_eq!(upgraded["keybindings"]["quit"].as_str(), Some("ctrl+x"));
+        assert!(migrate_file(&path).unwrap().is_none());
+
+        let mut newer: toml::Table = toml::from_str("config_version = 99").unwrap();
+        assert!(migrate(&mut newer).is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys_warn_and_bad_values_name_the_key() {
+        let loaded = resolve(
+            vec![layer("user", "watch_intreval_secs = 60\nfoo = 1")],
+            None,
+            std::iter::empty(),
+        )
+        .unwrap();
+        assert_eq!(loaded.warnings.len(), 2);
+        assert!(loaded.warnings[1].contains("did you mean `watch_interval_secs`?"));
+
+        let err = resolve(
+            vec![layer("user", "watch_interval_secs = \"soon\"")],
+            None,
+            std::iter::empty(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("user: invalid `watch_interval_secs`"));
+    }
 }
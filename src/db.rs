@@ -1,43 +1,319 @@
 use rusqlite::Connection;
 use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
 
+/// Schema migrations, applied in order. The schema version stored in SQLite's
+/// `user_version` is the number of scripts applied so far; append new scripts
+/// and never edit released ones.
+///
+/// Version 1 is the schema from before versioning. Its statements keep `IF NOT
+/// EXISTS` because unversioned databases may already contain any of the tables.
+const MIGRATIONS: &[&str] = &[
+    // 1: baseline
+    "CREATE TABLE IF NOT EXISTS repos (
+        username TEXT NOT NULL,
+        name TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (username, name)
+    );
+    CREATE TABLE IF NOT EXISTS unicode_chars (
+        codepoint TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        block TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS unicode_blocks (
+        name TEXT PRIMARY KEY,
+        range_start TEXT NOT NULL,
+        range_end TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        subject TEXT NOT NULL,
+        taken_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS watchlist (
+        kind TEXT NOT NULL,
+        target TEXT NOT NULL,
+        added_at INTEGER NOT NULL,
+        PRIMARY KEY (kind, target)
+    );
+    CREATE TABLE IF NOT EXISTS token_inspections (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        inspected_at INTEGER NOT NULL,
+        owner TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        scopes TEXT NOT NULL,
+        expires_at INTEGER,
+        fingerprint TEXT NOT NULL
+    );",
+    // 2: indexes for snapshot and token history lookups
+    "CREATE INDEX snapshots_subject ON snapshots (kind, subject, taken_at);
+    CREATE INDEX token_inspections_owner ON token_inspections (owner, inspected_at);",
+    // 3: fetch time of cached repositories, for cache TTLs. Existing rows
+    // count from the upgrade.
+    "ALTER TABLE repos ADD COLUMN fetched_at INTEGER NOT NULL DEFAULT 0;
+    UPDATE repos SET fetched_at = CAST(strftime('%s', 'now') AS INTEGER);",
+];
+
+/// The schema version this build creates and expects.
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Statements kept prepared per connection by `prepare_cached`.
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Opens the cache database in WAL mode and upgrades its schema if needed.
+/// Before an existing database is migrated it is copied to `<path>.v<N>.bak`.
+///
+/// The UI goes through [`Database`]; one-shot CLI commands can use the
+/// connection directly.
+pub fn open(path: &str) -> Result<Connection, Box<dyn Error>> {
+    let mut conn = Connection::open(path)?;
+    // WAL lets readers such as `dev-toolbox cache stats` run while the app
+    // writes; in-memory databases report "memory" and are unaffected.
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    migrate(&mut conn, Some(Path::new(path)))?;
+    Ok(conn)
+}
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Handle to the cache database. The connection lives on a dedicated thread
+/// that runs jobs one at a time, so queries and imports never block a
+/// runtime worker or hold a lock the UI waits on. Clones share the thread,
+/// which exits once every handle is dropped.
+#[derive(Clone)]
 pub struct Database {
-    conn: Connection,
+    jobs: mpsc::Sender<Job>,
 }
 
 impl Database {
     pub fn new(path: &str) -> Result<Self, Box<dyn Error>> {
-        let conn = Connection::open(path)?;
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS repos (
-                username TEXT NOT NULL,
-                name TEXT NOT NULL,
-                data TEXT NOT NULL,
-                PRIMARY KEY (username, name)
-            )",
-            [],
-        )?;
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS unicode_chars (
-                codepoint TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                block TEXT NOT NULL
-            )",
-            [],
-        )?;
+        Ok(Self::from_connection(open(path)?))
+    }
+
+    pub fn from_connection(mut conn: Connection) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("dev-toolbox-db".into())
+            .spawn(move || {
+                for job in queue {
+                    // A panicking job drops its reply channel, which the
+                    // caller sees as an error; later jobs still run.
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| job(&mut conn)));
+                }
+            })
+            .expect("failed to spawn the database thread");
+        Database { jobs }
+    }
+
+    fn submit<R, F>(
+        &self,
+        f: F,
+        reply: impl FnOnce(Result<R, String>) + Send + 'static,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnOnce(&mut Connection) -> Result<R, Box<dyn Error>> + Send + 'static,
+    {
+        self.jobs
+            .send(Box::new(move |conn| {
+                reply(f(conn).map_err(|e| e.to_string()))
+            }))
+            .map_err(|_| "The database thread has stopped".into())
+    }
+
+    /// Runs `f` on the database thread and waits for its result without
+    /// blocking the runtime.
+    pub async fn call<R, F>(&self, f: F) -> Result<R, Box<dyn Error>>
+    where
+        F: FnOnce(&mut Connection) -> Result<R, Box<dyn Error>> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (reply, result) = oneshot::channel();
+        self.submit(f, move |r| {
+            let _ = reply.send(r);
+        })?;
+        Ok(result
+            .await
+            .map_err(|_| "The database thread dropped the request")??)
+    }
+
+    /// Like [`call`](Self::call), but blocks the current thread. Only for
+    /// synchronous code that cannot await, such as saving caches on shutdown.
+    pub fn call_blocking<R, F>(&self, f: F) -> Result<R, Box<dyn Error>>
+    where
+        F: FnOnce(&mut Connection) -> Result<R, Box<dyn Error>> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (reply, result) = mpsc::channel();
+        self.submit(f, move |r| {
+            let _ = reply.send(r);
+        })?;
+        Ok(result
+            .recv()
+            .map_err(|_| "The database thread dropped the request")??)
+    }
+}
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Applies pending migrations, each in its own transaction together with the
+/// `user_version` bump, so a failed script leaves the previous version intact.
+/// If `backup_path` is given and the database already holds tables, a backup
+/// is written first. Returns the version the database was at.
+fn migrate(conn: &mut Connection, backup_path: Option<&Path>) -> Result<i64, Box<dyn Error>> {
+    let version = schema_version(conn)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "The cache database has schema version {}, but this build only supports up to {}. \
+             Upgrade dev-toolbox or point `cache_db_path` at another file.",
+            version, SCHEMA_VERSION
+        )
+        .into());
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(version);
+    }
+
+    let has_tables: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get(0),
+    )?;
+    if let Some(path) = backup_path.filter(|_| has_tables) {
+        let backup = format!("{}.v{}.bak", path.display(), version);
+        if Path::new(&backup).exists() {
+            std::fs::remove_file(&backup)?;
+        }
+        conn.execute("VACUUM INTO ?1", [&backup])
+            .map_err(|e| format!("Cannot back up the cache database to {}: {}", backup, e))?;
+    }
+
+    for (index, script) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let target = index as i64 + 1;
+        let tx = conn.transaction()?;
+        tx.execute_batch(script).map_err(|e| {
+            format!(
+                "Cache database migration to version {} failed: {}",
+                target, e
+            )
+        })?;
+        tx.pragma_update(None, "user_version", target)?;
+        tx.commit()?;
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrade_unversioned_database_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.db");
+        {
+            // The original three-table schema, without a user_version.
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE repos (
+                    username TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    data TEXT NOT NULL,
+                    PRIMARY KEY (username, name)
+                );
+                CREATE TABLE unicode_chars (
+                    codepoint TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    block TEXT NOT NULL
+                );
+                CREATE TABLE unicode_blocks (
+                    name TEXT PRIMARY KEY,
+                    range_start TEXT NOT NULL,
+                    range_end TEXT NOT NULL
+                );
+                INSERT INTO repos VALUES ('octocat', 'hello-world', '{}');",
+            )
+            .unwrap();
+        }
+
+        let conn = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        let repos: i64 = conn
+            .query_row("SELECT COUNT(*) FROM repos", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(repos, 1);
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS unicode_blocks (
-                name TEXT PRIMARY KEY,
-                range_start TEXT NOT NULL,
-                range_end TEXT NOT NULL
-            )",
+            "INSERT INTO watchlist (kind, target, added_at) VALUES ('user', 'octocat', 0)",
             [],
-        )?;
+        )
+        .unwrap();
 
-        Ok(Database { conn })
+        let backup = Connection::open(dir.path().join("cache.db.v0.bak")).unwrap();
+        assert_eq!(schema_version(&backup).unwrap(), 0);
+        let backed_up: i64 = backup
+            .query_row("SELECT COUNT(*) FROM repos", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(backed_up, 1);
+
+        // Reopening an up-to-date database is a no-op.
+        drop(conn);
+        open(path.to_str().unwrap()).unwrap();
     }
 
-    pub fn conn(&mut self) -> &mut Connection {
-        &mut self.conn
+    #[test]
+    fn test_fresh_and_newer_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.db");
+        open(path.to_str().unwrap()).unwrap();
+        assert!(!dir.path().join("cache.db.v0.bak").exists());
+
+        let mut conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        let err = migrate(&mut conn, None).unwrap_err();
+        assert!(err.to_string().contains("schema version"));
+    }
+
+    #[tokio::test]
+    async fn test_actor_runs_jobs_and_survives_failures() {
+        let db = Database::new(":memory:").unwrap();
+        let version = db.call(|conn| Ok(schema_version(conn)?)).await.unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        let err = db
+            .call(|conn| Ok(conn.execute("INSERT INTO missing VALUES (1)", [])?))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing"));
+        assert!(db
+            .call(|_| -> Result<(), Box<dyn Error>> { panic!("job panicked") })
+            .await
+            .is_err());
+
+        let clone = db.clone();
+        clone
+            .call(|conn| {
+                Ok(conn.execute(
+                    "INSERT INTO watchlist (kind, target, added_at) VALUES ('user', 'octocat', 0)",
+                    [],
+                )?)
+            })
+            .await
+            .unwrap();
+        let rows: i64 = db
+            .call(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM watchlist", [], |r| r.get(0))?))
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
     }
 }
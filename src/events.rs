@@ -0,0 +1,89 @@
+use crate::tasks::TaskFinished;
+use crossterm::event::{Event, EventStream};
+use futures_util::StreamExt;
+use std::io;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+/// Lower bound for `tick_rate_ms`, so a typo cannot spin the CPU.
+pub const MIN_TICK: Duration = Duration::from_millis(16);
+
+/// Everything the event loop reacts to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    /// A key press, mouse action or paste.
+    Input(Event),
+    Resize(u16, u16),
+    FocusGained,
+    FocusLost,
+    /// Time to redraw time-based UI and let tools run periodic work.
+    Tick,
+    TaskFinished(TaskFinished),
+}
+
+/// Multiplexes terminal input, the tick timer and background task
+/// completions, so the loop never blocks on any one of them.
+pub struct Events {
+    input: EventStream,
+    tick: Interval,
+    tasks: mpsc::UnboundedReceiver<TaskFinished>,
+}
+
+impl Events {
+    pub fn new(tick_rate: Duration, tasks: mpsc::UnboundedReceiver<TaskFinished>) -> Self {
+        let mut tick = time::interval(tick_rate.max(MIN_TICK));
+        // After a slow frame, tick once rather than catching up in a burst.
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        Events {
+            input: EventStream::new(),
+            tick,
+            tasks,
+        }
+    }
+
+    /// Waits for the next event. Task completions and input are preferred
+    /// over ticks when several are ready.
+    pub async fn next(&mut self) -> io::Result<AppEvent> {
+        tokio::select! {
+            biased;
+            Some(done) = self.tasks.recv() => Ok(AppEvent::TaskFinished(done)),
+            input = self.input.next() => match input {
+                Some(event) => Ok(Self::classify(event?)),
+                None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Terminal input closed")),
+            },
+            _ = self.tick.tick() => Ok(AppEvent::Tick),
+        }
+    }
+
+    /// A task completion that is already queued, for code that must handle
+    /// it before the next loop iteration.
+    pub fn try_task_finished(&mut self) -> Option<TaskFinished> {
+        self.tasks.try_recv().ok()
+    }
+
+    fn classify(event: Event) -> AppEvent {
+        match event {
+            Event::Resize(width, height) => AppEvent::Resize(width, height),
+            Event::FocusGained => AppEvent::FocusGained,
+            Event::FocusLost => AppEvent::FocusLost,
+            event => AppEvent::Input(event),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_terminal_events() {
+        assert_eq!(
+            Events::classify(Event::Resize(80, 24)),
+            AppEvent::Resize(80, 24)
+        );
+        assert_eq!(Events::classify(Event::FocusLost), AppEvent::FocusLost);
+        let paste = Event::Paste("text".into());
+        assert_eq!(Events::classify(paste.clone()), AppEvent::Input(paste));
+    }
+}
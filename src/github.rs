@@ -1,4 +1,77 @@
-use reqwest::header::HeaderMap;
+use crate::models::github::{Release, Repository, User};
+use reqwest::header::{HeaderMap, LINK};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+
+/// Most items GitHub returns on one page of a list endpoint.
+const PER_PAGE: u32 = 100;
+
+/// Repositories whose releases `fetch_repos` fetches, one request each.
+const RELEASE_REPOS: usize = 5;
+
+/// The kind of GitHub credential, identified by its prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenKind {
+    Classic,
+    FineGrained,
+    OAuth,
+    UserToServer,
+    Installation,
+    Refresh,
+    #[default]
+    Unknown,
+}
+
+impl TokenKind {
+    pub fn from_token(token: &str) -> Self {
+        match token {
+            t if t.starts_with("github_pat_") => TokenKind::FineGrained,
+            t if t.starts_with("ghp_") => TokenKind::Classic,
+            t if t.starts_with("gho_") => TokenKind::OAuth,
+            t if t.starts_with("ghu_") => TokenKind::UserToServer,
+            t if t.starts_with("ghs_") => TokenKind::Installation,
+            t if t.starts_with("ghr_") => TokenKind::Refresh,
+            _ => TokenKind::Unknown,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TokenKind::Classic => "Classic personal access token",
+            TokenKind::FineGrained => "Fine-grained personal access token",
+            TokenKind::OAuth => "OAuth app user token",
+            TokenKind::UserToServer => "GitHub App user token",
+            TokenKind::Installation => "GitHub App installation token",
+            TokenKind::Refresh => "GitHub App refresh token",
+            TokenKind::Unknown => "Unknown token type",
+        }
+    }
+
+    /// Whether the token reports its permissions through `x-oauth-scopes`.
+    /// Fine-grained and GitHub App tokens carry per-repository permissions
+    /// instead, which can only be discovered by probing.
+    pub fn reports_scopes(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Classic | TokenKind::OAuth | TokenKind::Unknown
+        )
+    }
+}
+
+/// Parses the `github-authentication-token-expiration` header, e.g.
+/// `2026-05-01 12:00:00 UTC` or `2026-05-01 12:00:00 -0700`, into a Unix
+/// timestamp.
+pub fn parse_token_expiration(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let normalized = match value.strip_suffix(" UTC") {
+        Some(rest) => format!("{} +0000", rest),
+        None => value.to_string(),
+    };
+    chrono::DateTime::parse_from_str(&normalized, "%Y-%m-%d %H:%M:%S %z")
+        .ok()
+        .map(|dt| dt.timestamp())
+}
 
 pub fn check_token_scopes(headers: &HeaderMap) -> Option<String> {
     if let Some(scopes_header) = headers.get("x-oauth-scopes") {
@@ -15,3 +88,182 @@ pub fn check_token_scopes(headers: &HeaderMap) -> Option<String> {
     }
     None
 }
+
+/// The `rel="next"` URL of a `Link` header, if there are more pages.
+pub fn next_page(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// Fetches every page of a list endpoint, following the `Link` headers.
+/// Returns the items and the headers of the first response.
+pub async fn get_all_pages<T: DeserializeOwned>(
+    client: &Client,
+    token: &str,
+    url: &str,
+) -> Result<(Vec<T>, HeaderMap), Box<dyn Error>> {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let mut next = Some(format!("{}{}per_page={}", url, separator, PER_PAGE));
+    let mut items = Vec::new();
+    let mut first_headers = None;
+    while let Some(url) = next {
+        let resp = client
+            .get(&url)
+            .header("Authorization", format!("token {}", token))
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(format!("GitHub API error: {}", resp.status()).into());
+        }
+        next = next_page(resp.headers());
+        first_headers.get_or_insert_with(|| resp.headers().clone());
+        items.extend(resp.json::<Vec<T>>().await?);
+    }
+    Ok((items, first_headers.unwrap_or_default()))
+}
+
+/// Fetches all public repositories of a user or organization, together with
+/// the releases of the first few, and the scope warning for the token used.
+pub async fn fetch_repos(
+    client: &Client,
+    token: &str,
+    owner: &str,
+) -> Result<(Vec<Repository>, Option<String>), Box<dyn Error>> {
+    let url = format!("https://api.github.com/users/{}/repos", owner);
+    let (mut repos, headers) = get_all_pages::<Repository>(client, token, &url).await?;
+    let scope_warning = check_token_scopes(&headers);
+
+    // Releases take a request per repository, so only the first few are
+    // fetched; the others are marked so their releases are not diffed.
+    for repo in repos.iter_mut().take(RELEASE_REPOS) {
+        if let Some(releases) = fetch_releases(client, token, owner, &repo.name).await? {
+            repo.releases = releases;
+            repo.releases_fetched = true;
+        }
+    }
+
+    Ok((repos, scope_warning))
+}
+
+/// Fetches a single repository and its releases.
+pub async fn fetch_repo(
+    client: &Client,
+    token: &str,
+    owner: &str,
+    name: &str,
+) -> Result<Repository, Box<dyn Error>> {
+    let url = format!("https://api.github.com/repos/{}/{}", owner, name);
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("token {}", token))
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        return Err(format!("GitHub API error: {}", resp.status()).into());
+    }
+
+    let mut repo: Repository = resp.json().await?;
+    if let Some(releases) = fetch_releases(client, token, owner, name).await? {
+        repo.releases = releases;
+        repo.releases_fetched = true;
+    }
+    Ok(repo)
+}
+
+/// The releases of a repository, or `None` if GitHub did not return them.
+async fn fetch_releases(
+    client: &Client,
+    token: &str,
+    owner: &str,
+    name: &str,
+) -> Result<Option<Vec<Release>>, Box<dyn Error>> {
+    let url = format!("https://api.github.com/repos/{}/{}/releases", owner, name);
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("token {}", token))
+        .send()
+        .await?;
+    if resp.status().is_success() {
+        Ok(Some(resp.json().await?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Fetches the public members of an organization.
+pub async fn fetch_public_members(
+    client: &Client,
+    token: &str,
+    org: &str,
+) -> Result<Vec<User>, Box<dyn Error>> {
+    let url = format!("https://api.github.com/orgs/{}/public_members", org);
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("token {}", token))
+        .send()
+        .await?;
+    if resp.status().is_success() {
+        Ok(resp.json().await?)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_kind_from_prefix() {
+        assert_eq!(TokenKind::from_token("ghp_abc"), TokenKind::Classic);
+        assert_eq!(
+            TokenKind::from_token("github_pat_11ABC_def"),
+            TokenKind::FineGrained
+        );
+        assert_eq!(TokenKind::from_token("ghs_abc"), TokenKind::Installation);
+        assert_eq!(TokenKind::from_token("gho_abc"), TokenKind::OAuth);
+        assert_eq!(TokenKind::from_token("0123abcd"), TokenKind::Unknown);
+        assert!(!TokenKind::FineGrained.reports_scopes());
+    }
+
+    #[test]
+    fn test_next_page_follows_link_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(next_page(&headers), None);
+        headers.insert(
+            LINK,
+            r#"<https://api.github.com/user/1/repos?per_page=100&page=1>; rel="prev", <https://api.github.com/user/1/repos?per_page=100&page=3>; rel="next", <https://api.github.com/user/1/repos?per_page=100&page=5>; rel="last""#
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            next_page(&headers).as_deref(),
+            Some("https://api.github.com/user/1/repos?per_page=100&page=3")
+        );
+    }
+
+    #[test]
+    fn test_parse_token_expiration() {
+        assert_eq!(
+            parse_token_expiration("2026-05-01 12:00:00 UTC"),
+            Some(1777636800)
+        );
+        assert_eq!(
+            parse_token_expiration("2026-05-01 05:00:00 -0700"),
+            Some(1777636800)
+        );
+        assert_eq!(parse_token_expiration("never"), None);
+    }
+}
@@ -0,0 +1,565 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Something a shortcut does. The `[keybindings]` config section maps the
+/// action names (`export = "ctrl+e"`) to key chords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Search,
+    Credentials,
+    RevealSecrets,
+    Quit,
+    Copy,
+    CopyRow,
+    NextTool,
+    PreviousTool,
+    NextField,
+    PreviousField,
+    CancelTask,
+    Export,
+    ShowChanges,
+    ToggleOption,
+    Lookup,
+    ToggleMode,
+    ToggleFormat,
+    CycleMethod,
+    Refresh,
+    PurgeExpired,
+    Vacuum,
+}
+
+struct ActionInfo {
+    action: Action,
+    name: &'static str,
+    label: &'static str,
+    default: &'static str,
+    /// Tools that handle the action, by tab name; empty for app-wide actions.
+    tools: &'static [&'static str],
+}
+
+const ACTIONS: &[ActionInfo] = &[
+    ActionInfo {
+        action: Action::Search,
+        name: "search",
+        label: "Command Palette",
+        default: "ctrl+f",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::Credentials,
+        name: "credentials",
+        label: "Credentials",
+        default: "ctrl+k",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::RevealSecrets,
+        name: "reveal_secrets",
+        label: "Reveal Secrets",
+        default: "ctrl+o",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::Quit,
+        name: "quit",
+        label: "Quit",
+        default: "ctrl+q",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::Copy,
+        name: "copy",
+        label: "Copy Result",
+        default: "ctrl+c",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::CopyRow,
+        name: "copy_row",
+        label: "Copy Row",
+        default: "ctrl+y",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::NextTool,
+        name: "next_tool",
+        label: "Next Tool",
+        default: "alt+right",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::PreviousTool,
+        name: "previous_tool",
+        label: "Previous Tool",
+        default: "alt+left",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::NextField,
+        name: "next_field",
+        label: "Next Field",
+        default: "tab",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::PreviousField,
+        name: "previous_field",
+        label: "Previous Field",
+        default: "shift+tab",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::CancelTask,
+        name: "cancel_task",
+        label: "Cancel Task",
+        default: "esc",
+        tools: &[],
+    },
+    ActionInfo {
+        action: Action::Export,
+        name: "export",
+        label: "Export",
+        default: "ctrl+e",
+        tools: &[
+            "Org Research",
+            "Repo Explorer",
+            "Unicode Inspector",
+            "Cache",
+        ],
+    },
+    ActionInfo {
+        action: Action::ShowChanges,
+        name: "show_changes",
+        label: "Show Changes",
+        default: "ctrl+d",
+        tools: &["Org Research", "Repo Explorer"],
+    },
+    ActionInfo {
+        action: Action::ToggleOption,
+        name: "toggle_option",
+        label: "Toggle Option",
+        default: "ctrl+a",
+        tools: &["Org Research", "Unicode Inspector"],
+    },
+    ActionInfo {
+        action: Action::Lookup,
+        name: "lookup",
+        label: "Lookup",
+        default: "ctrl+l",
+        tools: &["Unicode Inspector"],
+    },
+    ActionInfo {
+        action: Action::ToggleMode,
+        name: "toggle_mode",
+        label: "Toggle Mode",
+        default: "alt+m",
+        tools: &["Encoder/Decoder"],
+    },
+    ActionInfo {
+        action: Action::ToggleFormat,
+        name: "toggle_format",
+        label: "Toggle Format",
+        default: "ctrl+t",
+        tools: &["Encoder/Decoder"],
+    },
+    ActionInfo {
+        action: Action::CycleMethod,
+        name: "cycle_method",
+        label: "Method",
+        default: "alt+m",
+        tools: &["HTTP Inspector"],
+    },
+    ActionInfo {
+        action: Action::Refresh,
+        name: "refresh",
+        label: "Refresh",
+        default: "ctrl+r",
+        tools: &["Token Inspector", "Watchlist", "Cache"],
+    },
+    ActionInfo {
+        action: Action::PurgeExpired,
+        name: "purge_expired",
+        label: "Purge Expired",
+        default: "ctrl+t",
+        tools: &["Cache"],
+    },
+    ActionInfo {
+        action: Action::Vacuum,
+        name: "vacuum",
+        label: "Vacuum",
+        default: "ctrl+v",
+        tools: &["Cache"],
+    },
+];
+
+/// Chords that terminals send as another key unless the keyboard
+/// enhancement protocol is available, so a binding to them never fires.
+const INDISTINGUISHABLE: &[(&str, &str)] = &[
+    ("ctrl+m", "Enter"),
+    ("ctrl+j", "Enter"),
+    ("ctrl+i", "Tab"),
+    ("ctrl+h", "Backspace"),
+];
+
+/// Chords the text fields and editors use for editing, which a binding
+/// takes away from them.
+const EDITING_KEYS: &[&str] = &[
+    "ctrl+w",
+    "ctrl+u",
+    "ctrl+g",
+    "ctrl+left",
+    "ctrl+right",
+    "ctrl+home",
+    "ctrl+end",
+    "ctrl+backspace",
+];
+
+impl Action {
+    /// Every action, in the order of the `[keybindings]` reference.
+    pub fn all() -> impl Iterator<Item = Action> {
+        ACTIONS.iter().map(|info| info.action)
+    }
+
+    fn info(self) -> &'static ActionInfo {
+        ACTIONS
+            .iter()
+            .find(|info| info.action == self)
+            .expect("every action is listed in ACTIONS")
+    }
+
+    /// The key used for the action in `[keybindings]`.
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// A short description, shown in the footer hints and command palette.
+    pub fn label(self) -> &'static str {
+        self.info().label
+    }
+
+    /// Tools that handle the action, by tab name; empty for app-wide actions.
+    pub fn tools(self) -> &'static [&'static str] {
+        self.info().tools
+    }
+
+    /// Whether the app handles the action itself rather than passing it to
+    /// the selected tool.
+    pub fn is_global(self) -> bool {
+        self.info().tools.is_empty()
+    }
+
+    fn applies_to(self, tool: &str) -> bool {
+        let tools = self.info().tools;
+        tools.is_empty() || tools.contains(&tool)
+    }
+
+    /// Whether two actions can be triggered in the same tool, so they must
+    /// not share a chord.
+    fn overlaps(self, other: Action) -> bool {
+        let (a, b) = (self.info().tools, other.info().tools);
+        a.is_empty() || b.is_empty() || a.iter().any(|tool| b.contains(tool))
+    }
+}
+
+/// A key with its modifiers, e.g. `ctrl+e` or `shift+tab`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Normalizes a key event the way chords are written: letters are
+    /// lowercase with an explicit `shift`, and `shift` is implied for
+    /// `BackTab` and for other shifted characters such as `?`.
+    pub fn from_event(key: &KeyEvent) -> Self {
+        let mut modifiers =
+            key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match key.code {
+            KeyCode::Char(c) if c.is_uppercase() => {
+                modifiers |= KeyModifiers::SHIFT;
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            KeyCode::Char(c) if !c.is_alphabetic() && c != ' ' => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c)
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        KeyChord { code, modifiers }
+    }
+
+    /// Parses `ctrl+e`, `alt+right`, `shift+tab`, `f5`, ... (case-insensitive).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim().to_lowercase();
+        let (mods, key) = match text.rsplit_once('+') {
+            // `ctrl++` binds the plus key.
+            Some((mods, "")) => (mods.strip_suffix('+').unwrap_or(mods), "+"),
+            Some((mods, key)) => (mods, key),
+            None => ("", text.as_str()),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for part in mods.split('+').filter(|part| !part.is_empty()) {
+            modifiers |= match part {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", part, text)),
+            };
+        }
+        let code = match key {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "space" => KeyCode::Char(' '),
+            _ if key.len() > 1 && key.starts_with('f') => match key[1..].parse() {
+                Ok(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(format!("unknown key `{}`", text)),
+            },
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("unknown key `{}`", text)),
+                }
+            }
+        };
+        // Written the same way `from_event` normalizes, so both compare equal.
+        Ok(KeyChord::from_event(&KeyEvent::new(code, modifiers)))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+/// The active chord for every action: the defaults with the `[keybindings]`
+/// overrides applied.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Option<KeyChord>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&BTreeMap::new()).expect("the default keymap is valid")
+    }
+}
+
+impl Keymap {
+    /// Applies `overrides` (action name to chord, or `"none"` to unbind) to
+    /// the defaults. Unknown actions, unparsable chords and two actions on
+    /// the same chord in the same tool are errors.
+    pub fn new(overrides: &BTreeMap<String, String>) -> Result<Self, String> {
+        for name in overrides.keys() {
+            if !ACTIONS.iter().any(|info| info.name == name) {
+                return Err(format!("unknown action `{}`", name));
+            }
+        }
+        let mut bindings = Vec::new();
+        for info in ACTIONS {
+            let chord = match overrides.get(info.name).map(String::as_str) {
+                Some("none") | Some("") => None,
+                Some(chord) => {
+                    Some(KeyChord::parse(chord).map_err(|e| format!("`{}`: {}", info.name, e))?)
+                }
+                None => Some(KeyChord::parse(info.default).expect("default chords parse")),
+            };
+            bindings.push((info.action, chord));
+        }
+
+        for (i, (a, chord_a)) in bindings.iter().enumerate() {
+            for (b, chord_b) in &bindings[i + 1..] {
+                if chord_a.is_some() && chord_a == chord_b && a.overlaps(*b) {
+                    return Err(format!(
+                        "`{}` and `{}` are both bound to {}",
+                        a.name(),
+                        b.name(),
+                        chord_a.unwrap()
+                    ));
+                }
+            }
+        }
+        Ok(Keymap { bindings })
+    }
+
+    /// Bindings that load but will not work as expected.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (action, chord) in &self.bindings {
+            let Some(chord) = chord else { continue };
+            for (text, key) in INDISTINGUISHABLE {
+                if KeyChord::parse(text).as_ref() == Ok(chord) {
+                    warnings.push(format!(
+                        "keybindings.{}: {} arrives as {} in most terminals and will not fire",
+                        action.name(),
+                        chord,
+                        key
+                    ));
+                }
+            }
+            if EDITING_KEYS
+                .iter()
+                .any(|text| KeyChord::parse(text).as_ref() == Ok(chord))
+            {
+                warnings.push(format!(
+                    "keybindings.{}: {} replaces a text editing key",
+                    action.name(),
+                    chord
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// The action `key` triggers in `tool`, if any.
+    pub fn action(&self, key: &KeyEvent, tool: &str) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(action, bound)| *bound == Some(chord) && action.applies_to(tool))
+            .map(|(action, _)| *action)
+    }
+
+    /// The chord bound to `action`, if it is bound.
+    pub fn chord(&self, action: Action) -> Option<KeyChord> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, chord)| *chord)
+    }
+
+    /// Footer hints for `tool`: its own actions first, then the app-wide ones.
+    pub fn hints(&self, tool: &str) -> String {
+        let (global, local): (Vec<_>, Vec<_>) = self
+            .bindings
+            .iter()
+            .filter(|(action, _)| action.applies_to(tool))
+            .filter_map(|(action, chord)| Some((*action, (*chord)?)))
+            .partition(|(action, _)| action.is_global());
+        local
+            .iter()
+            .chain(&global)
+            .map(|(action, chord)| format!("{}: {}", chord, action.label()))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_matches_key_events() {
+        let chord = KeyChord::parse("Ctrl+Shift+E").unwrap();
+        let event = KeyEvent::new(
+            KeyCode::Char('E'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        assert_eq!(chord, KeyChord::from_event(&event));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+E");
+
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(
+            KeyChord::parse("shift+tab").unwrap(),
+            KeyChord::from_event(&back_tab)
+        );
+        assert_eq!(
+            KeyChord::parse("alt+right").unwrap().to_string(),
+            "Alt+Right"
+        );
+        assert_eq!(KeyChord::parse("f5").unwrap().to_string(), "F5");
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn test_overrides_scope_and_conflicts() {
+        let keymap = Keymap::new(&overrides(&[("export", "ctrl+x"), ("vacuum", "none")])).unwrap();
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&ctrl('x'), "Cache"), Some(Action::Export));
+        assert_eq!(keymap.action(&ctrl('e'), "Cache"), None);
+        assert_eq!(keymap.action(&ctrl('v'), "Cache"), None);
+        // Export is not an action of the JWT Decoder.
+        assert_eq!(keymap.action(&ctrl('x'), "JWT Decoder"), None);
+        assert_eq!(
+            keymap.action(&ctrl('f'), "JWT Decoder"),
+            Some(Action::Search)
+        );
+
+        // Different tools may share a chord, but not within one tool or
+        // with an app-wide action.
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(&ctrl('t'), "Cache"),
+            Some(Action::PurgeExpired)
+        );
+        assert_eq!(
+            keymap.action(&ctrl('t'), "Encoder/Decoder"),
+            Some(Action::ToggleFormat)
+        );
+        assert!(Keymap::new(&overrides(&[("vacuum", "ctrl+r")])).is_err());
+        assert!(Keymap::new(&overrides(&[("lookup", "ctrl+f")])).is_err());
+        assert!(Keymap::new(&overrides(&[("no_such_action", "ctrl+f")])).is_err());
+    }
+
+    #[test]
+    fn test_warnings_and_hints() {
+        assert!(Keymap::default().warnings().is_empty());
+        let keymap = Keymap::new(&overrides(&[("toggle_mode", "ctrl+m")])).unwrap();
+        assert_eq!(keymap.warnings().len(), 1);
+        assert!(keymap.warnings()[0].contains("Enter"));
+
+        let hints = Keymap::default().hints("Encoder/Decoder");
+        assert!(hints
+            .starts_with("Alt+M: Toggle Mode | Ctrl+T: Toggle Format | Ctrl+F: Command Palette"));
+        assert!(!hints.contains("Vacuum"));
+    }
+}
@@ -1,8 +1,19 @@
 pub mod app;
+pub mod cache;
+pub mod clipboard;
 pub mod config;
 pub mod db;
+pub mod events;
 pub mod github;
+pub mod keymap;
 pub mod models;
+pub mod redact;
 pub mod secrets;
+pub mod snapshots;
+pub mod tasks;
+pub mod token_history;
 pub mod tools;
 pub mod ui;
+pub mod unicode_data;
+pub mod vault;
+pub mod watchlist;
@@ -1,17 +1,23 @@
 use clap::{Arg, Command};
 use dev_toolbox::app::App;
-use dev_toolbox::config::Config;
-use dev_toolbox::db::Database;
-use dev_toolbox::secrets::Secrets;
+use dev_toolbox::cache;
+use dev_toolbox::config::{CheckLevel, Config, LoadOptions, LoadedConfig};
+use dev_toolbox::db::{self, Database};
+use dev_toolbox::github::TokenKind;
+use dev_toolbox::secrets::{self, Secrets};
+use dev_toolbox::snapshots;
+use dev_toolbox::token_history;
+use dev_toolbox::unicode_data;
+use dev_toolbox::vault::{self, Vault};
+use dev_toolbox::watchlist;
 use ratatui::{backend::CrosstermBackend, Terminal};
 use secrecy::ExposeSecret;
 use std::error::Error;
 use std::io;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let config = Config::load()?;
     let matches = Command::new("Dev-Toolbox")
         .version(env!("CARGO_PKG_VERSION"))
         .about("A modular CLI toolbox for GitHub and Unicode analysis")
@@ -27,41 +33,537 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .action(clap::ArgAction::SetTrue)
                 .help("Allow loading .env from current working directory (insecure)"),
         )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("PATH")
+                .global(true)
+                .help("Read this config file on top of the system and user config"),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .value_name("NAME")
+                .global(true)
+                .help("Apply the [profile.NAME] section of the config"),
+        )
+        .arg(
+            Arg::new("no-vault")
+                .long("no-vault")
+                .action(clap::ArgAction::SetTrue)
+                .help("Do not unlock the secrets vault at startup"),
+        )
+        .subcommand(
+            Command::new("cache")
+                .about("Inspect, expire and move the cache database")
+                .subcommand_required(true)
+                .subcommand(Command::new("stats").about("Show rows, size and age per table"))
+                .subcommand(
+                    Command::new("list")
+                        .about("List the newest entries of a table with their age")
+                        .arg(Arg::new("table").required(true))
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .value_name("N")
+                                .default_value("50")
+                                .value_parser(clap::value_parser!(usize)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("purge")
+                        .about("Delete cached entries (never the watchlist)")
+                        .arg(
+                            Arg::new("subject")
+                                .long("subject")
+                                .visible_aliases(["user", "org"])
+                                .value_name("LOGIN")
+                                .help("Only entries for this user, org or search query"),
+                        )
+                        .arg(
+                            Arg::new("older-than")
+                                .long("older-than")
+                                .value_name("DAYS")
+                                .value_parser(clap::value_parser!(i64))
+                                .help("Only entries older than this many days"),
+                        )
+                        .arg(
+                            Arg::new("table")
+                                .long("table")
+                                .value_name("TABLE")
+                                .help("Only this table"),
+                        )
+                        .arg(
+                            Arg::new("expired")
+                                .long("expired")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with_all(["subject", "older-than", "table"])
+                                .help("Entries older than their `cache_ttl_days` setting"),
+                        )
+                        .arg(
+                            Arg::new("all")
+                                .long("all")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with_all(["subject", "older-than", "table", "expired"])
+                                .help("Everything except user data"),
+                        )
+                        .group(
+                            clap::ArgGroup::new("filter")
+                                .args(["subject", "older-than", "table", "expired", "all"])
+                                .multiple(true)
+                                .required(true),
+                        ),
+                )
+                .subcommand(Command::new("vacuum").about("Reclaim space left by deleted entries"))
+                .subcommand(
+                    Command::new("export")
+                        .about("Write the cache to a portable .json.gz archive")
+                        .arg(Arg::new("path").required(true)),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Load an archive written by `cache export`")
+                        .arg(Arg::new("path").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the layered configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("check")
+                        .about("Validate configured paths, URLs and credentials before launch"),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the effective configuration")
+                        .arg(
+                            Arg::new("effective")
+                                .long("effective")
+                                .action(clap::ArgAction::SetTrue)
+                                .help("Show where each value came from"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("secrets")
+                .about("Manage GitHub tokens in the encrypted vault")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a token to the vault (creates the vault if needed)")
+                        .arg(Arg::new("name").required(true)),
+                )
+                .subcommand(Command::new("list").about("List the credentials in the vault"))
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove a token from the vault")
+                        .arg(Arg::new("name").required(true)),
+                )
+                .subcommand(
+                    Command::new("rotate")
+                        .about("Replace a stored token, or change the vault passphrase if no name is given")
+                        .arg(Arg::new("name")),
+                ),
+        )
+        .subcommand(
+            Command::new("snapshots")
+                .about("List and diff stored Org Research / Repo Explorer snapshots")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list").arg(
+                        Arg::new("subject")
+                            .long("subject")
+                            .value_name("SUBJECT")
+                            .help("Only list snapshots for this user or search query"),
+                    ),
+                )
+                .subcommand(
+                    Command::new("diff")
+                        .arg(
+                            Arg::new("old")
+                                .required(true)
+                                .value_parser(clap::value_parser!(i64)),
+                        )
+                        .arg(
+                            Arg::new("new")
+                                .required(true)
+                                .value_parser(clap::value_parser!(i64)),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("unicode")
+                .about("Manage the Unicode Character Database files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("update")
+                        .about("Install UnicodeData.txt and Blocks.txt from a directory into the data dir")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .value_name("DIR")
+                                .required(true)
+                                .help("Directory containing the new UCD files"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Refresh the watchlist and print a digest of changes")
+                .arg(
+                    Arg::new("once")
+                        .long("once")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Refresh once and exit (for cron)"),
+                ),
+        )
         .get_matches();
 
+    let LoadedConfig {
+        config,
+        provenance,
+        warnings,
+    } = Config::load(&LoadOptions {
+        config_path: matches.get_one::<String>("config").map(PathBuf::from),
+        profile: matches.get_one::<String>("profile").cloned(),
+    })?;
+
+    if !matches!(matches.subcommand_name(), Some("config")) {
+        for warning in &warnings {
+            eprintln!("Config warning: {}", warning);
+        }
+    }
+
+    if let Some(("config", sub)) = matches.subcommand() {
+        match sub.subcommand() {
+            Some(("show", args)) if args.get_flag("effective") => {
+                for line in config.effective_lines(&provenance)? {
+                    println!("{}", line);
+                }
+            }
+            Some(("show", _)) => print!("{}", toml::to_string(&config)?),
+            Some(("check", _)) => {
+                let mut errors = 0;
+                for warning in &warnings {
+                    println!("[warn]  {}", warning);
+                }
+                for result in config.check() {
+                    let label = match result.level {
+                        CheckLevel::Ok => "[ok]   ",
+                        CheckLevel::Warning => "[warn] ",
+                        CheckLevel::Error => {
+                            errors += 1;
+                            "[error]"
+                        }
+                    };
+                    println!("{} {}", label, result.message);
+                }
+                if errors > 0 {
+                    return Err(format!("Configuration has {} error(s)", errors).into());
+                }
+            }
+            _ => unreachable!("subcommand_required"),
+        }
+        return Ok(());
+    }
+
+    if let Some(("unicode", sub)) = matches.subcommand() {
+        if let Some(("update", args)) = sub.subcommand() {
+            let from = args.get_one::<String>("from").expect("required");
+            for path in unicode_data::install(Path::new(from))? {
+                println!("Installed {}", path.display());
+            }
+            // Re-import from the new files on the next launch.
+            db::open(&config.cache_db_path)?.execute("DELETE FROM unicode_chars", [])?;
+            if !config.unicode_data_path.is_empty() {
+                println!(
+                    "Note: unicode_data_path = {:?} in the config takes precedence over the installed files.",
+                    config.unicode_data_path
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(("cache", sub)) = matches.subcommand() {
+        let conn = &mut db::open(&config.cache_db_path)?;
+        match sub.subcommand() {
+            Some(("stats", _)) => {
+                println!(
+                    "{:<18} {:>8} {:>10}  {:<23}  {:<23}  TTL",
+                    "TABLE", "ROWS", "SIZE", "OLDEST", "NEWEST"
+                );
+                for s in cache::stats(conn)? {
+                    let ttl = match s.table.ttl_days(&config.cache_ttl_days) {
+                        Some(0) => "keep".to_string(),
+                        Some(days) => format!("{}d", days),
+                        None if s.table.is_user_data() => "user data".to_string(),
+                        None => "-".to_string(),
+                    };
+                    println!(
+                        "{:<18} {:>8} {:>10}  {:<23}  {:<23}  {}",
+                        s.table.name,
+                        s.rows,
+                        s.bytes.map(cache::format_bytes).unwrap_or_default(),
+                        s.oldest
+                            .map(snapshots::format_timestamp)
+                            .unwrap_or_default(),
+                        s.newest
+                            .map(snapshots::format_timestamp)
+                            .unwrap_or_default(),
+                        ttl
+                    );
+                }
+                println!(
+                    "{}: {}",
+                    config.cache_db_path,
+                    cache::format_bytes(cache::database_size(conn)?)
+                );
+            }
+            Some(("list", args)) => {
+                let table = cache::table(args.get_one::<String>("table").expect("required"))?;
+                let limit = *args.get_one::<usize>("limit").expect("default");
+                for entry in cache::entries(conn, table, limit)? {
+                    println!(
+                        "{:>5}  {}",
+                        entry.at.map(cache::format_age).unwrap_or_default(),
+                        entry.label
+                    );
+                }
+            }
+            Some(("purge", args)) => {
+                let deleted = if args.get_flag("expired") {
+                    cache::purge_expired(conn, &config.cache_ttl_days)?
+                } else {
+                    cache::purge(
+                        conn,
+                        &cache::PurgeFilter {
+                            table: args.get_one::<String>("table").cloned(),
+                            subject: args.get_one::<String>("subject").cloned(),
+                            older_than_secs: args
+                                .get_one::<i64>("older-than")
+                                .map(|days| days * 86_400),
+                        },
+                    )?
+                };
+                if deleted.is_empty() {
+                    println!("Nothing to purge.");
+                }
+                for (table, count) in deleted {
+                    println!("Purged {} from {}", count, table);
+                }
+            }
+            Some(("vacuum", _)) => {
+                let before = cache::database_size(conn)?;
+                cache::vacuum(conn)?;
+                println!(
+                    "{} -> {}",
+                    cache::format_bytes(before),
+                    cache::format_bytes(cache::database_size(conn)?)
+                );
+            }
+            Some(("export", args)) => {
+                let path = args.get_one::<String>("path").expect("required");
+                let rows = cache::export(conn, Path::new(path))?;
+                println!("Exported {} rows to {}", rows, path);
+            }
+            Some(("import", args)) => {
+                let path = args.get_one::<String>("path").expect("required");
+                let rows = cache::import(conn, Path::new(path))?;
+                println!("Imported {} rows from {}", rows, path);
+            }
+            _ => unreachable!("subcommand_required"),
+        }
+        return Ok(());
+    }
+
+    if let Some(("snapshots", sub)) = matches.subcommand() {
+        let conn = db::open(&config.cache_db_path)?;
+        match sub.subcommand() {
+            Some(("list", args)) => {
+                let subject = args.get_one::<String>("subject").map(|s| s.as_str());
+                for meta in snapshots::list(&conn, None, subject)? {
+                    println!(
+                        "{:>6}  {:<5}  {}  {}",
+                        meta.id,
+                        meta.kind.as_str(),
+                        snapshots::format_timestamp(meta.taken_at),
+                        meta.subject
+                    );
+                }
+            }
+            Some(("diff", args)) => {
+                let old = *args.get_one::<i64>("old").expect("required");
+                let new = *args.get_one::<i64>("new").expect("required");
+                for line in snapshots::diff_by_id(&conn, old, new)?.lines() {
+                    println!("{}", line);
+                }
+            }
+            _ => unreachable!("subcommand_required"),
+        }
+        return Ok(());
+    }
+
+    if let Some(("secrets", sub)) = matches.subcommand() {
+        let path = vault::default_path();
+        let exists = path.exists();
+        let passphrase = if exists {
+            vault::prompt_secret("Vault passphrase: ")?
+        } else if matches!(sub.subcommand_name(), Some("add")) {
+            println!("Creating a new vault at {}", path.display());
+            vault::prompt_new_passphrase()?
+        } else {
+            return Err(format!("No vault at {}", path.display()).into());
+        };
+        let mut vault = if exists {
+            Vault::open(&path, &passphrase)?
+        } else {
+            Vault::default()
+        };
+
+        match sub.subcommand() {
+            Some(("add", args)) => {
+                let name = args.get_one::<String>("name").expect("required");
+                let token = vault::prompt_secret(&format!("Token for '{}': ", name))?;
+                vault.insert(name, token, false)?;
+                vault.save(&path, &passphrase)?;
+                println!("Added '{}'.", name);
+            }
+            Some(("list", _)) => {
+                for entry in vault.entries() {
+                    let token = entry.token.expose_secret();
+                    println!(
+                        "{:<20}  {:<40}  {}",
+                        entry.name,
+                        TokenKind::from_token(token).label(),
+                        token_history::fingerprint(token)
+                    );
+                }
+            }
+            Some(("remove", args)) => {
+                let name = args.get_one::<String>("name").expect("required");
+                if !vault.remove(name) {
+                    return Err(format!("'{}' is not in the vault", name).into());
+                }
+                vault.save(&path, &passphrase)?;
+                println!("Removed '{}'.", name);
+            }
+            Some(("rotate", args)) => match args.get_one::<String>("name") {
+                Some(name) => {
+                    let token = vault::prompt_secret(&format!("New token for '{}': ", name))?;
+                    vault.insert(name, token, true)?;
+                    vault.save(&path, &passphrase)?;
+                    println!("Rotated '{}'.", name);
+                }
+                None => {
+                    let new_passphrase = vault::prompt_new_passphrase()?;
+                    vault.save(&path, &new_passphrase)?;
+                    println!("Vault passphrase changed.");
+                }
+            },
+            _ => unreachable!("subcommand_required"),
+        }
+        return Ok(());
+    }
+
     let env_path = matches.get_one::<String>("env").map(|s| s.as_str());
     let allow_cwd = matches.get_flag("allow-cwd-env");
-    let secrets = Secrets::load(env_path, allow_cwd)?;
-    if secrets.github_token.expose_secret().is_empty() {
-        let config_dir = dirs::config_dir()
-            .map(|p| p.join("dev-toolbox").join(".env"))
-            .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "~/.config/dev-toolbox/.env".to_string());
-
-        eprintln!("Error: GITHUB_TOKEN not found.");
-        eprintln!("\nTo use this tool, please:");
-        eprintln!("1. Set the GITHUB_TOKEN environment variable.");
-        eprintln!("2. OR create a .env file in the current directory.");
-        eprintln!("3. OR create a .env file at: {}", config_dir);
-        eprintln!("\nYou can generate a token at: https://github.com/settings/tokens");
-        return Err("GitHub token missing".into());
+    let (secrets, credential_warnings) = Secrets::load(
+        env_path,
+        allow_cwd,
+        &config.credentials,
+        &config.token_sources,
+        &secrets::github_host(&config.github_api_base_url),
+    )?;
+    for warning in &credential_warnings {
+        eprintln!("Config warning: {}", warning);
+    }
+    let vault_path = vault::default_path();
+    if !matches.get_flag("no-vault") && vault_path.exists() {
+        // `watch` runs unattended, so it never prompts; without a passphrase
+        // file it uses the credentials from the environment only.
+        let passphrase = match vault::passphrase_from_env()? {
+            Some(passphrase) => Some(passphrase),
+            None if matches!(matches.subcommand(), Some(("watch", _))) => None,
+            None => Some(vault::prompt_secret("Vault passphrase: ")?),
+        };
+        if let Some(passphrase) = passphrase {
+            secrets.add_vault(&Vault::open(&vault_path, &passphrase)?);
+        }
+    }
+    secrets.set_tool_defaults(&config.tool_credentials)?;
+    if let Some(("watch", args)) = matches.subcommand() {
+        if secrets.token_for("Watchlist").expose_secret().is_empty() {
+            let config_dir = dirs::config_dir()
+                .map(|p| p.join("dev-toolbox").join(".env"))
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| "~/.config/dev-toolbox/.env".to_string());
+
+            eprintln!("Error: GITHUB_TOKEN not found.");
+            eprintln!("\nTo use this tool, please:");
+            eprintln!("1. Set the GITHUB_TOKEN environment variable.");
+            eprintln!("2. OR create a .env file in the current directory.");
+            eprintln!("3. OR create a .env file at: {}", config_dir);
+            eprintln!(
+                "4. OR log in with the GitHub CLI and set token_sources = [\"gh\"] in config.toml"
+            );
+            eprintln!("5. OR store it in the encrypted vault: dev-toolbox secrets add default");
+            eprintln!("\nYou can generate a token at: https://github.com/settings/tokens");
+            return Err("GitHub token missing".into());
+        }
+        let db = Database::new(&config.cache_db_path)?;
+        let client = reqwest::Client::builder()
+            .user_agent("Dev-Toolbox/1.0")
+            .https_only(true)
+            .build()?;
+        loop {
+            let notifications = watchlist::refresh_all(
+                &db,
+                &client,
+                secrets.token_for("Watchlist").expose_secret(),
+            )
+            .await?;
+            if notifications.is_empty() {
+                println!("No watchlist changes.");
+            }
+            for notification in &notifications {
+                println!("{}", notification);
+            }
+            if args.get_flag("once") || config.watch_interval_secs == 0 {
+                return Ok(());
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(config.watch_interval_secs)).await;
+        }
     }
 
+    // Opening the database may migrate it, so it happens before the terminal
+    // is taken over and an error can still be printed normally.
+    let mut conn = db::open(&config.cache_db_path)?;
+    cache::purge_expired(&mut conn, &config.cache_ttl_days)?;
+    let db = Database::from_connection(conn);
+
     crossterm::terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
     crossterm::execute!(
         stdout,
         crossterm::terminal::EnterAlternateScreen,
-        crossterm::event::EnableMouseCapture
+        crossterm::event::EnableMouseCapture,
+        crossterm::event::EnableFocusChange,
+        crossterm::event::EnableBracketedPaste
     )?;
 
     let backend = CrosstermBackend::new(stdout);
 
     let mut terminal = Terminal::new(backend)?;
 
-    let db = Arc::new(Mutex::new(Database::new(&config.cache_db_path)?));
-
     let mut app = App::new(db, secrets, config)?;
+    if let Some(warning) = warnings.first().or(credential_warnings.first()) {
+        app.set_message(format!(
+            "Config warning: {} (run `dev-toolbox config check` for details)",
+            warning
+        ));
+    }
 
     app.run(&mut terminal).await?;
 
@@ -71,7 +573,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     crossterm::execute!(
         terminal.backend_mut(),
         crossterm::terminal::LeaveAlternateScreen,
-        crossterm::event::DisableMouseCapture
+        crossterm::event::DisableMouseCapture,
+        crossterm::event::DisableFocusChange,
+        crossterm::event::DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
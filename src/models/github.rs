@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Organization {
     pub login: String,
     pub url: String,
@@ -10,17 +10,17 @@ pub struct Organization {
     pub website_url: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SearchResponse {
     pub items: Vec<Organization>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct User {
     pub login: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OrgResult {
     pub login: String,
     pub website_url: Option<String>,
@@ -29,7 +29,7 @@ pub struct OrgResult {
     pub shared_members: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Repository {
     pub name: String,
     pub stargazers_count: u32,
@@ -37,17 +37,26 @@ pub struct Repository {
     pub created_at: String,
     pub updated_at: String,
     pub pushed_at: String,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub visibility: Option<String>,
+    #[serde(default)]
     pub releases: Vec<Release>,
+    /// Whether `releases` was fetched. Only some repositories have their
+    /// releases fetched, and an empty list says nothing about the others.
+    #[serde(default)]
+    pub releases_fetched: bool,
     pub description: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Release {
     pub tag_name: String,
     pub assets: Vec<Asset>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Asset {
     pub name: String,
 }
@@ -1,15 +1,105 @@
-use secrecy::{ExposeSecretMut, SecretBox};
+use secrecy::{ExposeSecret, ExposeSecretMut, SecretBox};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
+use std::sync::{Arc, RwLock};
 
-use zeroize::Zeroize;
+use crate::vault::Vault;
 
-#[derive(Clone)]
+use zeroize::{Zeroize, Zeroizing};
+
+/// Name of the credential loaded from `GITHUB_TOKEN`.
+pub const DEFAULT_CREDENTIAL: &str = "default";
+
+/// Name of a token entered in the TUI while running without one.
+pub const SESSION_CREDENTIAL: &str = "session";
+
+/// Prefix of environment variables picked up as named credentials, e.g.
+/// `GITHUB_TOKEN_AUDIT` becomes the `audit` credential.
+const NAMED_TOKEN_PREFIX: &str = "GITHUB_TOKEN_";
+
+/// Where a `[credentials.<name>]` entry in `config.toml` reads its token from.
+/// Exactly one of `env` or `file` must be set.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CredentialSource {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// External tools a token can be imported from when `GITHUB_TOKEN` is not
+/// set, tried in the order listed in `token_sources`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenSource {
+    /// The `gh` CLI login: `hosts.yml`, then `gh auth token` for tokens kept
+    /// in the system keyring.
+    Gh,
+    /// `git credential fill` for the GitHub host.
+    GitCredential,
+}
+
+pub struct Credential {
+    pub name: String,
+    /// Human-readable description of where the token came from.
+    pub source: String,
+    token: SecretBox<str>,
+}
+
+impl Drop for Credential {
+    fn drop(&mut self) {
+        self.token.expose_secret_mut().zeroize();
+    }
+}
+
+#[derive(Default)]
+struct CredentialStore {
+    credentials: Vec<Credential>,
+    active: String,
+    tool_defaults: HashMap<String, String>,
+    tool_overrides: HashMap<String, String>,
+    /// `[credentials.*]` entries skipped because their variable is unset.
+    unavailable: Vec<String>,
+}
+
+impl CredentialStore {
+    fn get(&self, name: &str) -> Option<&Credential> {
+        self.credentials.iter().find(|c| c.name == name)
+    }
+
+    /// Resolves the credential for a tool: a selection made in the switcher
+    /// this session, then the tool's default from `config.toml`, then the
+    /// active credential.
+    fn resolve(&self, tool: Option<&str>) -> Option<&Credential> {
+        let preferred = tool.and_then(|tool| {
+            self.tool_overrides
+                .get(tool)
+                .or_else(|| self.tool_defaults.get(tool))
+        });
+        preferred
+            .and_then(|name| self.get(name))
+            .or_else(|| self.get(&self.active))
+    }
+}
+
+/// Shared handle to the loaded GitHub credentials. Clones refer to the same
+/// store, so a switch made in the TUI is seen by every tool.
+#[derive(Clone, Default)]
 pub struct Secrets {
-    pub github_token: SecretBox<str>,
+    store: Arc<RwLock<CredentialStore>>,
 }
 
 impl Secrets {
-    pub fn load(env_path: Option<&str>, allow_cwd: bool) -> Result<Self, Box<dyn Error>> {
+    /// Loads the credentials and returns warnings for `[credentials.*]`
+    /// entries that were skipped because their variable is unset.
+    pub fn load(
+        env_path: Option<&str>,
+        allow_cwd: bool,
+        sources: &BTreeMap<String, CredentialSource>,
+        imports: &[TokenSource],
+        host: &str,
+    ) -> Result<(Self, Vec<String>), Box<dyn Error>> {
         // 1. Try OS-specific config directory first (canonical location)
         if let Some(mut config_dir) = dirs::config_dir() {
             config_dir.push("dev-toolbox");
@@ -32,16 +122,336 @@ impl Secrets {
         }
 
         // 4. Load from environment (populated by the above or set manually)
-        let github_token = std::env::var("GITHUB_TOKEN")
-            .map(|s| SecretBox::new(s.into_boxed_str()))
-            .unwrap_or_else(|_| SecretBox::new(String::new().into_boxed_str()));
-        Ok(Secrets { github_token })
+        let mut credentials = Vec::new();
+        if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+            credentials.push(Credential::new(DEFAULT_CREDENTIAL, "GITHUB_TOKEN", token));
+        }
+
+        // 5. Optional fallbacks: tokens from the gh CLI or git credential helpers
+        if credentials.is_empty() {
+            for import in imports {
+                if let Some((source, token)) = import_token(*import, host) {
+                    credentials.push(Credential::new(DEFAULT_CREDENTIAL, &source, token));
+                    break;
+                }
+            }
+        }
+
+        let mut named: Vec<(String, String)> = std::env::vars()
+            .filter(|(key, _)| key.starts_with(NAMED_TOKEN_PREFIX))
+            .collect();
+        named.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, token) in named {
+            let name = key[NAMED_TOKEN_PREFIX.len()..].to_lowercase();
+            if !name.is_empty() {
+                credentials.push(Credential::new(&name, &key, token));
+            }
+        }
+
+        // 6. Explicit `[credentials.*]` entries from config.toml. An unset
+        // variable only disables that credential, as the same config is often
+        // shared between machines that do not all have every token.
+        let mut warnings = Vec::new();
+        let mut unavailable = Vec::new();
+        for (name, source) in sources {
+            let credential = match (&source.env, &source.file) {
+                (Some(var), None) => match std::env::var(var) {
+                    Ok(token) => Credential::new(name, var, token),
+                    Err(_) => {
+                        warnings.push(format!("Credential '{}' skipped: {} is not set", name, var));
+                        unavailable.push(name.clone());
+                        continue;
+                    }
+                },
+                (None, Some(path)) => {
+                    let mut content = std::fs::read_to_string(path).map_err(|e| {
+                        format!("Credential '{}': cannot read {}: {}", name, path, e)
+                    })?;
+                    let token = content.trim().to_string();
+                    content.zeroize();
+                    Credential::new(name, &format!("file {}", path), token)
+                }
+                _ => {
+                    return Err(format!(
+                        "Credential '{}' must set exactly one of `env` or `file`",
+                        name
+                    )
+                    .into())
+                }
+            };
+            credentials.retain(|c| c.name != *name);
+            credentials.push(credential);
+        }
+
+        let active = credentials
+            .iter()
+            .find(|c| c.name == DEFAULT_CREDENTIAL)
+            .or(credentials.first())
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| DEFAULT_CREDENTIAL.to_string());
+
+        let secrets = Secrets {
+            store: Arc::new(RwLock::new(CredentialStore {
+                credentials,
+                active,
+                unavailable,
+                ..Default::default()
+            })),
+        };
+        Ok((secrets, warnings))
+    }
+
+    /// Sets per-tool default credentials from the `[tool_credentials]` table.
+    /// Returns an error naming any credential that is not configured; tools
+    /// whose credential was skipped at load use the active one instead.
+    pub fn set_tool_defaults(
+        &self,
+        defaults: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut store = self.store.write().unwrap();
+        let mut tool_defaults = HashMap::new();
+        for (tool, name) in defaults {
+            if store.get(name).is_none() {
+                if store.unavailable.contains(name) {
+                    continue;
+                }
+                return Err(format!(
+                    "tool_credentials: '{}' uses unknown credential '{}'",
+                    tool, name
+                )
+                .into());
+            }
+            tool_defaults.insert(tool.clone(), name.clone());
+        }
+        store.tool_defaults = tool_defaults;
+        Ok(())
+    }
+
+    /// Adds the tokens of an unlocked vault as credentials. Vault entries
+    /// replace credentials of the same name loaded from the environment.
+    pub fn add_vault(&self, vault: &Vault) {
+        let mut store = self.store.write().unwrap();
+        for entry in vault.entries() {
+            store.credentials.retain(|c| c.name != entry.name);
+            store.credentials.push(Credential {
+                name: entry.name.clone(),
+                source: "vault".to_string(),
+                token: entry.token.clone(),
+            });
+        }
+        if store.get(&store.active).is_none() {
+            store.active = store
+                .credentials
+                .iter()
+                .find(|c| c.name == DEFAULT_CREDENTIAL)
+                .or(store.credentials.first())
+                .map(|c| c.name.clone())
+                .unwrap_or_else(|| DEFAULT_CREDENTIAL.to_string());
+        }
+    }
+
+    /// Whether a tool resolves to a non-empty token.
+    pub fn has_token_for(&self, tool: &str) -> bool {
+        let store = self.store.read().unwrap();
+        store
+            .resolve(Some(tool))
+            .is_some_and(|c| !c.token.expose_secret().is_empty())
+    }
+
+    /// Uses a token entered in the TUI as the active credential for the rest
+    /// of the session. It is kept in memory only and never written to disk.
+    pub fn set_session_token(&self, token: SecretBox<str>) {
+        let mut store = self.store.write().unwrap();
+        store.credentials.retain(|c| c.name != SESSION_CREDENTIAL);
+        store.credentials.push(Credential {
+            name: SESSION_CREDENTIAL.to_string(),
+            source: "entered at runtime, not saved".to_string(),
+            token,
+        });
+        store.active = SESSION_CREDENTIAL.to_string();
+        store.tool_overrides.clear();
+    }
+
+    /// The token of the active credential, or an empty secret if none is loaded.
+    pub fn github_token(&self) -> SecretBox<str> {
+        self.resolve_token(None)
+    }
+
+    /// The token a specific tool should use.
+    pub fn token_for(&self, tool: &str) -> SecretBox<str> {
+        self.resolve_token(Some(tool))
+    }
+
+    fn resolve_token(&self, tool: Option<&str>) -> SecretBox<str> {
+        let store = self.store.read().unwrap();
+        match store.resolve(tool) {
+            Some(credential) => credential.token.clone(),
+            None => SecretBox::new(String::new().into_boxed_str()),
+        }
+    }
+
+    /// Name and source of the credential a tool resolves to.
+    pub fn credential_for(&self, tool: &str) -> Option<(String, String)> {
+        let store = self.store.read().unwrap();
+        store
+            .resolve(Some(tool))
+            .map(|c| (c.name.clone(), c.source.clone()))
+    }
+
+    /// Names and sources of all loaded credentials, in load order.
+    pub fn list(&self) -> Vec<(String, String)> {
+        let store = self.store.read().unwrap();
+        store
+            .credentials
+            .iter()
+            .map(|c| (c.name.clone(), c.source.clone()))
+            .collect()
+    }
+
+    /// Uses `name` for one tool for the rest of the session.
+    pub fn select_for_tool(&self, tool: &str, name: &str) {
+        let mut store = self.store.write().unwrap();
+        if store.get(name).is_some() {
+            store
+                .tool_overrides
+                .insert(tool.to_string(), name.to_string());
+        }
+    }
+
+    /// Makes `name` the active credential and drops per-tool selections made
+    /// in this session. Defaults from `[tool_credentials]` still apply.
+    pub fn set_active(&self, name: &str) {
+        let mut store = self.store.write().unwrap();
+        if store.get(name).is_some() {
+            store.active = name.to_string();
+            store.tool_overrides.clear();
+        }
     }
 }
 
-impl Drop for Secrets {
-    fn drop(&mut self) {
-        self.github_token.expose_secret_mut().zeroize();
+/// The git host for an API base URL, e.g. `github.com` for
+/// `https://api.github.com`.
+pub fn github_host(api_base_url: &str) -> String {
+    let host = api_base_url
+        .split_once("://")
+        .map_or(api_base_url, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    host.strip_prefix("api.").unwrap_or(host).to_string()
+}
+
+fn import_token(source: TokenSource, host: &str) -> Option<(String, String)> {
+    match source {
+        TokenSource::Gh => {
+            let path = gh_hosts_path()?;
+            let content = Zeroizing::new(std::fs::read_to_string(&path).unwrap_or_default());
+            if let Some(token) = parse_gh_hosts(&content, host) {
+                return Some((format!("gh {}", path.display()), token));
+            }
+            let output = std::process::Command::new("gh")
+                .args(["auth", "token", "--hostname", host])
+                .stderr(std::process::Stdio::null())
+                .output()
+                .ok()?;
+            let stdout = Zeroizing::new(output.stdout);
+            let token = std::str::from_utf8(&stdout).ok()?.trim();
+            (output.status.success() && !token.is_empty())
+                .then(|| ("gh auth token".to_string(), token.to_string()))
+        }
+        TokenSource::GitCredential => {
+            git_credential_fill(host).map(|token| ("git credential fill".to_string(), token))
+        }
+    }
+}
+
+fn gh_hosts_path() -> Option<std::path::PathBuf> {
+    if let Ok(dir) = std::env::var("GH_CONFIG_DIR") {
+        return Some(std::path::Path::new(&dir).join("hosts.yml"));
+    }
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        return Some(std::path::Path::new(&dir).join("gh").join("hosts.yml"));
+    }
+    if cfg!(windows) {
+        dirs::config_dir().map(|d| d.join("GitHub CLI").join("hosts.yml"))
+    } else {
+        dirs::home_dir().map(|d| d.join(".config").join("gh").join("hosts.yml"))
+    }
+}
+
+/// Reads `oauth_token` for `host` from the gh CLI's `hosts.yml`. Only the
+/// subset of YAML that gh writes is understood: a top-level key per host
+/// with indented `key: value` pairs.
+fn parse_gh_hosts(content: &str, host: &str) -> Option<String> {
+    let mut in_host = false;
+    let mut field_indent = None;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            in_host = line.trim_end().trim_end_matches(':').trim_matches('"') == host;
+            field_indent = None;
+            continue;
+        }
+        if !in_host {
+            continue;
+        }
+        // Only direct children of the host; per-user blocks under `users:`
+        // are nested deeper.
+        let expected = *field_indent.get_or_insert(indent);
+        if indent != expected {
+            continue;
+        }
+        if let Some(value) = line.trim().strip_prefix("oauth_token:") {
+            let token = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            return (!token.is_empty()).then(|| token.to_string());
+        }
+    }
+    None
+}
+
+/// Asks the configured git credential helpers for the host's password
+/// without letting git prompt on the terminal.
+fn git_credential_fill(host: &str) -> Option<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={}\n\n", host).as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = Zeroizing::new(output.stdout);
+    std::str::from_utf8(&stdout)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+}
+
+impl Credential {
+    fn new(name: &str, source: &str, token: String) -> Self {
+        Credential {
+            name: name.to_string(),
+            source: source.to_string(),
+            token: SecretBox::new(token.into_boxed_str()),
+        }
     }
 }
 
@@ -63,12 +473,11 @@ mod tests {
 
         env::remove_var("GITHUB_TOKEN");
 
-        let secrets = Secrets::load(None, false).unwrap();
+        let (secrets, _) = Secrets::load(None, false, &BTreeMap::new(), &[], "github.com").unwrap();
 
         env::set_current_dir(original_dir).unwrap();
 
-        use secrecy::ExposeSecret;
-        assert_ne!(secrets.github_token.expose_secret(), "malicious_token");
+        assert_ne!(secrets.github_token().expose_secret(), "malicious_token");
     }
 
     #[test]
@@ -82,11 +491,119 @@ mod tests {
         env::set_current_dir(temp_dir.path()).unwrap();
         env::remove_var("GITHUB_TOKEN");
 
-        let secrets = Secrets::load(None, true).unwrap();
+        let (secrets, _) = Secrets::load(None, true, &BTreeMap::new(), &[], "github.com").unwrap();
 
         env::set_current_dir(original_dir).unwrap();
 
-        use secrecy::ExposeSecret;
-        assert_eq!(secrets.github_token.expose_secret(), "test_token_cwd");
+        assert_eq!(secrets.github_token().expose_secret(), "test_token_cwd");
+        env::remove_var("GITHUB_TOKEN");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_named_credentials_and_tool_selection() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let token_file = temp_dir.path().join("enterprise.token");
+        fs::write(&token_file, "ghp_enterprise\n").unwrap();
+
+        env::set_var("GITHUB_TOKEN", "ghp_personal");
+        env::set_var("GITHUB_TOKEN_AUDIT", "github_pat_audit");
+
+        let mut sources = BTreeMap::new();
+        sources.insert(
+            "enterprise".to_string(),
+            CredentialSource {
+                env: None,
+                file: Some(token_file.to_string_lossy().to_string()),
+            },
+        );
+        env::remove_var("DEV_TOOLBOX_TEST_BOT_TOKEN");
+        sources.insert(
+            "bot".to_string(),
+            CredentialSource {
+                env: Some("DEV_TOOLBOX_TEST_BOT_TOKEN".to_string()),
+                file: None,
+            },
+        );
+        let (secrets, warnings) = Secrets::load(None, false, &sources, &[], "github.com").unwrap();
+        env::remove_var("GITHUB_TOKEN");
+        env::remove_var("GITHUB_TOKEN_AUDIT");
+        assert_eq!(
+            warnings,
+            vec!["Credential 'bot' skipped: DEV_TOOLBOX_TEST_BOT_TOKEN is not set"]
+        );
+
+        let names: Vec<String> = secrets
+            .list()
+            .into_iter()
+            .map(|(n, _)| n)
+            .filter(|n| ["default", "audit", "enterprise", "bot"].contains(&n.as_str()))
+            .collect();
+        assert_eq!(names, vec!["default", "audit", "enterprise"]);
+
+        let mut defaults = BTreeMap::new();
+        defaults.insert("Token Inspector".to_string(), "audit".to_string());
+        defaults.insert("Watchlist".to_string(), "bot".to_string());
+        secrets.set_tool_defaults(&defaults).unwrap();
+        assert_eq!(
+            secrets.token_for("Watchlist").expose_secret(),
+            "ghp_personal"
+        );
+        assert_eq!(secrets.github_token().expose_secret(), "ghp_personal");
+        assert_eq!(
+            secrets.token_for("Token Inspector").expose_secret(),
+            "github_pat_audit"
+        );
+
+        secrets.select_for_tool("Repo Explorer", "enterprise");
+        assert_eq!(
+            secrets.token_for("Repo Explorer").expose_secret(),
+            "ghp_enterprise"
+        );
+
+        secrets.set_active("audit");
+        assert_eq!(
+            secrets.token_for("Repo Explorer").expose_secret(),
+            "github_pat_audit"
+        );
+
+        defaults.insert("Org Research".to_string(), "missing".to_string());
+        assert!(secrets.set_tool_defaults(&defaults).is_err());
+    }
+
+    #[test]
+    fn test_session_token_without_configured_credentials() {
+        let secrets = Secrets::default();
+        assert!(!secrets.has_token_for("Repo Explorer"));
+
+        secrets.set_session_token(SecretBox::new("ghp_session".into()));
+        assert!(secrets.has_token_for("Repo Explorer"));
+        assert_eq!(
+            secrets.credential_for("Repo Explorer").unwrap().0,
+            SESSION_CREDENTIAL
+        );
+    }
+
+    #[test]
+    fn test_parse_gh_hosts_and_github_host() {
+        let hosts = "github.com:\n    users:\n        octocat:\n            oauth_token: gho_nested\n    oauth_token: gho_direct\n    user: octocat\nghe.example.com:\n    oauth_token: \"gho_enterprise\"\n";
+        assert_eq!(
+            parse_gh_hosts(hosts, "github.com").as_deref(),
+            Some("gho_direct")
+        );
+        assert_eq!(
+            parse_gh_hosts(hosts, "ghe.example.com").as_deref(),
+            Some("gho_enterprise")
+        );
+        assert_eq!(
+            parse_gh_hosts("github.com:\n    user: octocat\n", "github.com"),
+            None
+        );
+
+        assert_eq!(github_host("https://api.github.com"), "github.com");
+        assert_eq!(
+            github_host("https://github.com/enterprise/api/v3"),
+            "github.com"
+        );
     }
 }
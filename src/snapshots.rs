@@ -0,0 +1,498 @@
+use crate::models::github::{Organization, Repository, User};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotKind {
+    Repos,
+    Orgs,
+}
+
+impl SnapshotKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotKind::Repos => "repos",
+            SnapshotKind::Orgs => "orgs",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "repos" => Some(SnapshotKind::Repos),
+            "orgs" => Some(SnapshotKind::Orgs),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotMeta {
+    pub id: i64,
+    pub kind: SnapshotKind,
+    pub subject: String,
+    pub taken_at: i64,
+}
+
+/// Payload stored for an Org Research fetch: the search hits plus the public
+/// members of the parent org, if one was given.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct OrgSnapshot {
+    pub orgs: Vec<Organization>,
+    #[serde(default)]
+    pub members: Vec<User>,
+}
+
+pub fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+pub fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+pub fn save<T: Serialize>(
+    conn: &Connection,
+    kind: SnapshotKind,
+    subject: &str,
+    data: &T,
+) -> Result<i64, Box<dyn Error>> {
+    conn.execute(
+        "INSERT INTO snapshots (kind, subject, taken_at, data) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            kind.as_str(),
+            subject,
+            now_unix(),
+            serde_json::to_string(data)?
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Lists snapshots, newest first, optionally filtered by kind and subject.
+pub fn list(
+    conn: &Connection,
+    kind: Option<SnapshotKind>,
+    subject: Option<&str>,
+) -> Result<Vec<SnapshotMeta>, Box<dyn Error>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, kind, subject, taken_at FROM snapshots
+         WHERE (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR subject = ?2)
+         ORDER BY taken_at DESC, id DESC",
+    )?;
+    let rows = stmt.query_map(params![kind.map(|k| k.as_str()), subject], |row| {
+        let kind: String = row.get(1)?;
+        Ok((row.get(0)?, kind, row.get(2)?, row.get(3)?))
+    })?;
+
+    let mut metas = Vec::new();
+    for row in rows {
+        let (id, kind, subject, taken_at) = row?;
+        if let Some(kind) = SnapshotKind::parse(&kind) {
+            metas.push(SnapshotMeta {
+                id,
+                kind,
+                subject,
+                taken_at,
+            });
+        }
+    }
+    Ok(metas)
+}
+
+pub fn load<T: DeserializeOwned>(
+    conn: &Connection,
+    id: i64,
+) -> Result<(SnapshotMeta, T), Box<dyn Error>> {
+    let row: Option<(String, String, i64, String)> = conn
+        .query_row(
+            "SELECT kind, subject, taken_at, data FROM snapshots WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+    let (kind, subject, taken_at, data) = row.ok_or(format!("Snapshot {} not found", id))?;
+    let kind = SnapshotKind::parse(&kind).ok_or(format!("Unknown snapshot kind: {}", kind))?;
+    Ok((
+        SnapshotMeta {
+            id,
+            kind,
+            subject,
+            taken_at,
+        },
+        serde_json::from_str(&data)?,
+    ))
+}
+
+/// Loads the payload of the most recent snapshot for a subject, if any.
+pub fn latest<T: DeserializeOwned>(
+    conn: &Connection,
+    kind: SnapshotKind,
+    subject: &str,
+) -> Result<Option<T>, Box<dyn Error>> {
+    match list(conn, Some(kind), Some(subject))?.first() {
+        Some(meta) => Ok(Some(load(conn, meta.id)?.1)),
+        None => Ok(None),
+    }
+}
+
+/// The changes between two snapshots of the same kind.
+#[derive(Debug, PartialEq)]
+pub enum SnapshotDiff {
+    Repos(RepoDiff),
+    Orgs(OrgDiff),
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            SnapshotDiff::Repos(diff) => diff.is_empty(),
+            SnapshotDiff::Orgs(diff) => diff.is_empty(),
+        }
+    }
+
+    /// The number of changes, one per line of `lines`.
+    pub fn len(&self) -> usize {
+        if self.is_empty() {
+            0
+        } else {
+            self.lines().len()
+        }
+    }
+
+    /// A human-readable summary, one change per line, or "No changes".
+    pub fn lines(&self) -> Vec<String> {
+        match self {
+            SnapshotDiff::Repos(diff) => diff.lines(),
+            SnapshotDiff::Orgs(diff) => diff.lines(),
+        }
+    }
+}
+
+/// Diffs two stored snapshots of the same kind.
+pub fn diff_by_id(
+    conn: &Connection,
+    old_id: i64,
+    new_id: i64,
+) -> Result<SnapshotDiff, Box<dyn Error>> {
+    let (old_meta, old_data): (_, serde_json::Value) = load(conn, old_id)?;
+    let (new_meta, new_data): (_, serde_json::Value) = load(conn, new_id)?;
+    if old_meta.kind != new_meta.kind {
+        return Err(format!(
+            "Cannot diff a {} snapshot against a {} snapshot",
+            old_meta.kind.as_str(),
+            new_meta.kind.as_str()
+        )
+        .into());
+    }
+
+    let diff = match old_meta.kind {
+        SnapshotKind::Repos => {
+            let old: Vec<Repository> = serde_json::from_value(old_data)?;
+            let new: Vec<Repository> = serde_json::from_value(new_data)?;
+            SnapshotDiff::Repos(diff_repos(&old, &new))
+        }
+        SnapshotKind::Orgs => {
+            let old: OrgSnapshot = serde_json::from_value(old_data)?;
+            let new: OrgSnapshot = serde_json::from_value(new_data)?;
+            SnapshotDiff::Orgs(diff_orgs(&old, &new))
+        }
+    };
+    Ok(diff)
+}
+
+/// Diffs the two most recent snapshots for a subject. Returns `None` when
+/// fewer than two snapshots exist.
+pub fn diff_latest(
+    conn: &Connection,
+    kind: SnapshotKind,
+    subject: &str,
+) -> Result<Option<SnapshotDiff>, Box<dyn Error>> {
+    let metas = list(conn, Some(kind), Some(subject))?;
+    match metas.as_slice() {
+        [newest, previous, ..] => Ok(Some(diff_by_id(conn, previous.id, newest.id)?)),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct RepoDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub archived: Vec<String>,
+    pub unarchived: Vec<String>,
+    pub visibility_changes: Vec<(String, String, String)>,
+    pub star_changes: Vec<(String, u32, u32)>,
+    pub new_releases: Vec<(String, String)>,
+}
+
+impl RepoDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == RepoDiff::default()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        lines.extend(self.added.iter().map(|r| format!("+ repo {}", r)));
+        lines.extend(self.removed.iter().map(|r| format!("- repo {}", r)));
+        lines.extend(self.archived.iter().map(|r| format!("~ {} archived", r)));
+        lines.extend(
+            self.unarchived
+                .iter()
+                .map(|r| format!("~ {} unarchived", r)),
+        );
+        lines.extend(
+            self.visibility_changes
+                .iter()
+                .map(|(r, old, new)| format!("! {} visibility {} -> {}", r, old, new)),
+        );
+        lines.extend(
+            self.star_changes
+                .iter()
+                .map(|(r, old, new)| format!("* {} stars {} -> {}", r, old, new)),
+        );
+        lines.extend(
+            self.new_releases
+                .iter()
+                .map(|(r, tag)| format!("+ release {} {}", r, tag)),
+        );
+        if lines.is_empty() {
+            lines.push("No changes".to_string());
+        }
+        lines
+    }
+}
+
+pub fn diff_repos(old: &[Repository], new: &[Repository]) -> RepoDiff {
+    let old_by_name: BTreeMap<&str, &Repository> =
+        old.iter().map(|r| (r.name.as_str(), r)).collect();
+    let new_by_name: BTreeMap<&str, &Repository> =
+        new.iter().map(|r| (r.name.as_str(), r)).collect();
+
+    let mut diff = RepoDiff::default();
+    for (name, repo) in &new_by_name {
+        let Some(prev) = old_by_name.get(name) else {
+            diff.added.push(name.to_string());
+            continue;
+        };
+        if repo.archived && !prev.archived {
+            diff.archived.push(name.to_string());
+        } else if !repo.archived && prev.archived {
+            diff.unarchived.push(name.to_string());
+        }
+        if let (Some(old), Some(new)) = (&prev.visibility, &repo.visibility) {
+            if old != new {
+                diff.visibility_changes
+                    .push((name.to_string(), old.clone(), new.clone()));
+            }
+        }
+        if repo.stargazers_count != prev.stargazers_count {
+            diff.star_changes.push((
+                name.to_string(),
+                prev.stargazers_count,
+                repo.stargazers_count,
+            ));
+        }
+        if !(prev.releases_fetched && repo.releases_fetched) {
+            continue;
+        }
+        let known_tags: BTreeSet<&str> =
+            prev.releases.iter().map(|r| r.tag_name.as_str()).collect();
+        for release in &repo.releases {
+            if !known_tags.contains(release.tag_name.as_str()) {
+                diff.new_releases
+                    .push((name.to_string(), release.tag_name.clone()));
+            }
+        }
+    }
+    diff.removed = old_by_name
+        .keys()
+        .filter(|name| !new_by_name.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+    diff
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct OrgDiff {
+    pub orgs_added: Vec<String>,
+    pub orgs_removed: Vec<String>,
+    pub members_joined: Vec<String>,
+    pub members_left: Vec<String>,
+}
+
+impl OrgDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == OrgDiff::default()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        lines.extend(self.orgs_added.iter().map(|o| format!("+ org {}", o)));
+        lines.extend(self.orgs_removed.iter().map(|o| format!("- org {}", o)));
+        lines.extend(
+            self.members_joined
+                .iter()
+                .map(|m| format!("+ member {}", m)),
+        );
+        lines.extend(self.members_left.iter().map(|m| format!("- member {}", m)));
+        if lines.is_empty() {
+            lines.push("No changes".to_string());
+        }
+        lines
+    }
+}
+
+pub fn diff_orgs(old: &OrgSnapshot, new: &OrgSnapshot) -> OrgDiff {
+    let (orgs_added, orgs_removed) = set_diff(
+        old.orgs.iter().map(|o| o.login.as_str()),
+        new.orgs.iter().map(|o| o.login.as_str()),
+    );
+    let (members_joined, members_left) = set_diff(
+        old.members.iter().map(|m| m.login.as_str()),
+        new.members.iter().map(|m| m.login.as_str()),
+    );
+    OrgDiff {
+        orgs_added,
+        orgs_removed,
+        members_joined,
+        members_left,
+    }
+}
+
+fn set_diff<'a>(
+    old: impl Iterator<Item = &'a str>,
+    new: impl Iterator<Item = &'a str>,
+) -> (Vec<String>, Vec<String>) {
+    let old: BTreeSet<&str> = old.collect();
+    let new: BTreeSet<&str> = new.collect();
+    (
+        new.difference(&old).map(|s| s.to_string()).collect(),
+        old.difference(&new).map(|s| s.to_string()).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::github::Release;
+
+    fn repo(name: &str, stars: u32, archived: bool, tags: &[&str]) -> Repository {
+        Repository {
+            name: name.to_string(),
+            stargazers_count: stars,
+            language: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            pushed_at: String::new(),
+            archived,
+            visibility: None,
+            releases: tags
+                .iter()
+                .map(|t| Release {
+                    tag_name: t.to_string(),
+                    assets: vec![],
+                })
+                .collect(),
+            releases_fetched: true,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_repos() {
+        let old = vec![
+            repo("kept", 1, false, &["v1"]),
+            repo("gone", 0, false, &[]),
+            repo("old-tool", 5, false, &[]),
+        ];
+        let new = vec![
+            repo("kept", 3, false, &["v1", "v2"]),
+            repo("old-tool", 5, true, &[]),
+            repo("fresh", 0, false, &[]),
+        ];
+
+        let diff = diff_repos(&old, &new);
+        assert_eq!(diff.added, vec!["fresh"]);
+        assert_eq!(diff.removed, vec!["gone"]);
+        assert_eq!(diff.archived, vec!["old-tool"]);
+        assert_eq!(diff.star_changes, vec![("kept".to_string(), 1, 3)]);
+        assert_eq!(
+            diff.new_releases,
+            vec![("kept".to_string(), "v2".to_string())]
+        );
+        assert!(diff_repos(&new, &new).is_empty());
+
+        // Releases seen for the first time are not new.
+        let unfetched = Repository {
+            releases: Vec::new(),
+            releases_fetched: false,
+            ..repo("kept", 3, false, &[])
+        };
+        assert!(diff_repos(&[unfetched], &new[..1]).new_releases.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_history_and_org_diff() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                taken_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            )",
+        )
+        .unwrap();
+
+        let org = |login: &str| Organization {
+            login: login.to_string(),
+            url: String::new(),
+            email: None,
+            website_url: None,
+        };
+        let member = |login: &str| User {
+            login: login.to_string(),
+        };
+
+        let first = OrgSnapshot {
+            orgs: vec![org("acme")],
+            members: vec![member("alice")],
+        };
+        let second = OrgSnapshot {
+            orgs: vec![org("acme"), org("acme-labs")],
+            members: vec![member("bob")],
+        };
+        assert!(diff_latest(&conn, SnapshotKind::Orgs, "acme")
+            .unwrap()
+            .is_none());
+        save(&conn, SnapshotKind::Orgs, "acme", &first).unwrap();
+        save(&conn, SnapshotKind::Orgs, "acme", &second).unwrap();
+
+        let diff = diff_latest(&conn, SnapshotKind::Orgs, "acme")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            diff.lines(),
+            vec!["+ org acme-labs", "+ member bob", "- member alice"]
+        );
+        assert_eq!(diff.len(), 3);
+
+        save(&conn, SnapshotKind::Orgs, "acme", &second).unwrap();
+        let unchanged = diff_latest(&conn, SnapshotKind::Orgs, "acme")
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged.len(), 0);
+        assert_eq!(unchanged.lines(), vec!["No changes"]);
+        assert_eq!(list(&conn, None, None).unwrap().len(), 3);
+    }
+}
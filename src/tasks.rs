@@ -0,0 +1,287 @@
+use std::error::Error;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::AbortHandle;
+
+pub type TaskId = u64;
+
+/// Sent to the app when a task finishes or is cancelled, so it can let the
+/// tool that started it pick up the result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TaskFinished {
+    pub id: TaskId,
+    pub tool: &'static str,
+}
+
+/// A running task as shown in the status bar.
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub tool: &'static str,
+    pub label: String,
+    pub elapsed: Duration,
+    pub progress: Option<String>,
+}
+
+struct Entry {
+    id: TaskId,
+    tool: &'static str,
+    label: String,
+    started: Instant,
+    progress: Option<String>,
+    abort: AbortHandle,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Spawns background work for tools and keeps track of it while it runs.
+///
+/// Tools hold a clone and keep the returned [`Task`] until the app reports it
+/// finished through the channel from [`Tasks::new`]. Meanwhile the event loop
+/// keeps drawing, and `Esc` cancels the selected tool's tasks.
+#[derive(Clone)]
+pub struct Tasks {
+    running: Arc<Mutex<Vec<Entry>>>,
+    finished: mpsc::UnboundedSender<TaskFinished>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Tasks {
+    /// Creates the spawner and the receiver the app reads completions from.
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<TaskFinished>) {
+        let (finished, events) = mpsc::unbounded_channel();
+        let tasks = Tasks {
+            running: Arc::new(Mutex::new(Vec::new())),
+            finished,
+            next_id: Arc::new(AtomicU64::new(1)),
+        };
+        (tasks, events)
+    }
+
+    /// Runs the future built by `f` on the runtime. `label` is shown while it
+    /// runs; `f` receives a [`Progress`] handle to add detail to it.
+    pub fn spawn<T, F, Fut>(&self, tool: &'static str, label: impl Into<String>, f: F) -> Task<T>
+    where
+        F: FnOnce(Progress) -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn Error>>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let future = f(Progress {
+            id,
+            running: Arc::clone(&self.running),
+        });
+        let (reply, result) = oneshot::channel();
+        let running = Arc::clone(&self.running);
+        let finished = self.finished.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        // Registered before the task can run, so one that finishes at once
+        // is never left behind in the list.
+        let mut list = self.running.lock().unwrap();
+        let handle = tokio::spawn(async move {
+            let output = future.await.map_err(|e| e.to_string());
+            running.lock().unwrap().retain(|e| e.id != id);
+            let _ = reply.send(output);
+            let _ = finished.send(TaskFinished { id, tool });
+        });
+        list.push(Entry {
+            id,
+            tool,
+            label: label.into(),
+            started: Instant::now(),
+            progress: None,
+            abort: handle.abort_handle(),
+            cancelled: Arc::clone(&cancelled),
+        });
+
+        Task {
+            id,
+            result,
+            cancelled,
+            tasks: self.clone(),
+        }
+    }
+
+    /// Running tasks, oldest first.
+    pub fn running(&self) -> Vec<TaskStatus> {
+        self.running
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| TaskStatus {
+                tool: e.tool,
+                label: e.label.clone(),
+                elapsed: e.started.elapsed(),
+                progress: e.progress.clone(),
+            })
+            .collect()
+    }
+
+    pub fn is_running(&self, tool: &str) -> bool {
+        self.running.lock().unwrap().iter().any(|e| e.tool == tool)
+    }
+
+    fn status(&self, id: TaskId) -> Option<TaskStatus> {
+        self.running
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| TaskStatus {
+                tool: e.tool,
+                label: e.label.clone(),
+                elapsed: e.started.elapsed(),
+                progress: e.progress.clone(),
+            })
+    }
+
+    /// Aborts every task of `tool` and reports each as finished. Returns the
+    /// labels of the cancelled tasks.
+    pub fn cancel(&self, tool: &str) -> Vec<String> {
+        let mut cancelled = Vec::new();
+        self.running.lock().unwrap().retain(|e| {
+            if e.tool != tool {
+                return true;
+            }
+            e.cancelled.store(true, Ordering::Relaxed);
+            e.abort.abort();
+            cancelled.push((e.id, e.tool, e.label.clone()));
+            false
+        });
+        cancelled
+            .into_iter()
+            .map(|(id, tool, label)| {
+                let _ = self.finished.send(TaskFinished { id, tool });
+                label
+            })
+            .collect()
+    }
+
+    fn abort(&self, id: TaskId) {
+        self.running.lock().unwrap().retain(|e| {
+            if e.id == id {
+                e.abort.abort();
+            }
+            e.id != id
+        });
+    }
+}
+
+/// Lets a running task describe what it is doing, e.g. `3/10 repositories`.
+pub struct Progress {
+    id: TaskId,
+    running: Arc<Mutex<Vec<Entry>>>,
+}
+
+impl Progress {
+    pub fn set(&self, message: impl Into<String>) {
+        if let Some(entry) = self
+            .running
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|e| e.id == self.id)
+        {
+            entry.progress = Some(message.into());
+        }
+    }
+}
+
+/// The result of a spawned task. Dropping it aborts the task, so replacing a
+/// tool's task with a new one cancels the old.
+pub struct Task<T> {
+    id: TaskId,
+    result: oneshot::Receiver<Result<T, String>>,
+    cancelled: Arc<AtomicBool>,
+    tasks: Tasks,
+}
+
+impl<T> Task<T> {
+    pub fn id(&self) -> TaskId {
+        self.id
+    }
+
+    /// The label, elapsed time and progress while the task runs.
+    pub fn status(&self) -> Option<TaskStatus> {
+        self.tasks.status(self.id)
+    }
+
+    /// Takes the result out of `slot` once the task has finished or was
+    /// cancelled, leaving `None`. Returns `None` while it is still running.
+    pub fn take_finished(slot: &mut Option<Task<T>>) -> Option<Result<T, String>> {
+        let task = slot.as_mut()?;
+        let result = match task.result.try_recv() {
+            Ok(result) => result,
+            Err(_) if task.cancelled.load(Ordering::Relaxed) => Err("Cancelled".to_string()),
+            Err(oneshot::error::TryRecvError::Empty) => return None,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                Err("The task stopped unexpectedly".to_string())
+            }
+        };
+        *slot = None;
+        Some(result)
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.tasks.abort(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_task_reports_result_and_progress() {
+        let (tasks, mut events) = Tasks::new();
+        let (go, wait) = oneshot::channel::<()>();
+        let mut slot = Some(tasks.spawn("Test", "Working", |progress| async move {
+            progress.set("halfway");
+            wait.await?;
+            Ok(42)
+        }));
+
+        tokio::task::yield_now().await;
+        assert!(tasks.is_running("Test"));
+        assert_eq!(Task::take_finished(&mut slot), None);
+        let status = slot.as_ref().unwrap().status().unwrap();
+        assert_eq!(status.label, "Working");
+
+        go.send(()).unwrap();
+        let done = events.recv().await.unwrap();
+        assert_eq!(done.tool, "Test");
+        assert_eq!(Task::take_finished(&mut slot), Some(Ok(42)));
+        assert!(slot.is_none());
+        assert!(tasks.running().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_aborts_and_reports() {
+        let (tasks, mut events) = Tasks::new();
+        let mut slot = Some(tasks.spawn("Test", "Sleeping", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        }));
+
+        assert_eq!(tasks.cancel("Other"), Vec::<String>::new());
+        assert_eq!(tasks.cancel("Test"), vec!["Sleeping".to_string()]);
+        assert_eq!(events.recv().await.unwrap().tool, "Test");
+        assert_eq!(
+            Task::take_finished(&mut slot),
+            Some(Err("Cancelled".to_string()))
+        );
+        assert!(!tasks.is_running("Test"));
+
+        // Dropping a task aborts it too.
+        let task = tasks.spawn("Test", "Sleeping", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+        drop(task);
+        assert!(tasks.running().is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::error::Error;
+
+/// One Token Inspector run as stored in `token_inspections`. The token itself
+/// is never stored; `fingerprint` is a truncated SHA-256 so rotations can be
+/// told apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inspection {
+    pub inspected_at: i64,
+    pub owner: String,
+    pub kind: String,
+    pub scopes: String,
+    pub expires_at: Option<i64>,
+    pub fingerprint: String,
+}
+
+pub fn fingerprint(token: &str) -> String {
+    hex::encode(&Sha256::digest(token.as_bytes())[..8])
+}
+
+/// Records an inspection and returns the previous one for the same owner.
+pub fn record(
+    conn: &Connection,
+    inspection: &Inspection,
+) -> Result<Option<Inspection>, Box<dyn Error>> {
+    let previous = recent(conn, Some(&inspection.owner), 1)?.into_iter().next();
+    conn.execute(
+        "INSERT INTO token_inspections (inspected_at, owner, kind, scopes, expires_at, fingerprint)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            inspection.inspected_at,
+            inspection.owner,
+            inspection.kind,
+            inspection.scopes,
+            inspection.expires_at,
+            inspection.fingerprint
+        ],
+    )?;
+    Ok(previous)
+}
+
+/// Returns the most recent inspections, newest first.
+pub fn recent(
+    conn: &Connection,
+    owner: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Inspection>, Box<dyn Error>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT inspected_at, owner, kind, scopes, expires_at, fingerprint FROM token_inspections
+         WHERE ?1 IS NULL OR owner = ?1
+         ORDER BY inspected_at DESC, id DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![owner, limit as i64], |row| {
+        Ok(Inspection {
+            inspected_at: row.get(0)?,
+            owner: row.get(1)?,
+            kind: row.get(2)?,
+            scopes: row.get(3)?,
+            expires_at: row.get(4)?,
+            fingerprint: row.get(5)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn scope_set(scopes: &str) -> BTreeSet<&str> {
+    scopes
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Compares a new inspection against the previous one for the same owner and
+/// returns a warning if the token was rotated to one with broader access.
+pub fn rotation_warning(previous: &Inspection, current: &Inspection) -> Option<String> {
+    if previous.fingerprint == current.fingerprint {
+        return None;
+    }
+
+    let old = scope_set(&previous.scopes);
+    let gained: Vec<&str> = scope_set(&current.scopes)
+        .into_iter()
+        .filter(|s| !old.contains(s))
+        .collect();
+    let kind_changed = previous.kind != current.kind;
+
+    match (gained.is_empty(), kind_changed) {
+        (true, false) => None,
+        (true, true) => Some(format!(
+            "Token was rotated from a {} to a {}.",
+            previous.kind, current.kind
+        )),
+        (false, _) => Some(format!(
+            "Token was rotated to one with broader access: gained {}.",
+            gained.join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspection(fingerprint: &str, kind: &str, scopes: &str) -> Inspection {
+        Inspection {
+            inspected_at: 0,
+            owner: "octocat".into(),
+            kind: kind.into(),
+            scopes: scopes.into(),
+            expires_at: None,
+            fingerprint: fingerprint.into(),
+        }
+    }
+
+    #[test]
+    fn test_rotation_warning() {
+        let old = inspection("aaaa", "Classic personal access token", "read:org");
+        let same_token = inspection("aaaa", "Classic personal access token", "read:org, repo");
+        let broader = inspection("bbbb", "Classic personal access token", "read:org, repo");
+        let narrower = inspection("bbbb", "Classic personal access token", "");
+
+        assert_eq!(rotation_warning(&old, &same_token), None);
+        assert_eq!(rotation_warning(&old, &narrower), None);
+        assert!(rotation_warning(&old, &broader)
+            .unwrap()
+            .contains("gained repo"));
+    }
+
+    #[test]
+    fn test_record_returns_previous_inspection() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE token_inspections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                inspected_at INTEGER NOT NULL,
+                owner TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                scopes TEXT NOT NULL,
+                expires_at INTEGER,
+                fingerprint TEXT NOT NULL
+            )",
+        )
+        .unwrap();
+
+        let first = inspection(&fingerprint("ghp_one"), "classic", "read:org");
+        let second = inspection(&fingerprint("ghp_two"), "classic", "repo");
+        assert_eq!(record(&conn, &first).unwrap(), None);
+        assert_eq!(record(&conn, &second).unwrap(), Some(first));
+        assert_eq!(recent(&conn, None, 10).unwrap().len(), 2);
+        assert_ne!(fingerprint("ghp_one"), fingerprint("ghp_two"));
+    }
+}
@@ -0,0 +1,492 @@
+use crate::cache::{self, CacheEntry, PurgeFilter, TableStats};
+use crate::config::{CacheTtl, Config};
+use crate::db::Database;
+use crate::keymap::Action;
+use crate::tasks::{Task, Tasks};
+use crate::tools::Command;
+use crate::ui::components::{self, TextInput};
+use crate::ui::focus::{self, Focus};
+use crate::ui::list::ScrollList;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use std::error::Error;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Entries listed for the selected table.
+const ENTRY_LIMIT: usize = 200;
+const EXPORT_PATH: &str = "cache_export.json.gz";
+/// How long a purge waits for its confirming key press.
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+
+const COMMANDS: &[Command] =
+    &[Command::new("purge", "Purge...").with_prompt("User/org, or an age such as 30d")];
+
+#[derive(Default)]
+struct CacheView {
+    stats: Vec<TableStats>,
+    db_size: i64,
+    entries: Vec<CacheEntry>,
+    loaded: bool,
+}
+
+pub struct CacheTool {
+    /// A user/org login, or an age such as `30d`, to purge with Enter.
+    input: TextInput,
+    selected: usize,
+    view: Arc<Mutex<CacheView>>,
+    entry_list: ScrollList,
+    /// A purge waiting for the same key to be pressed again, and since when.
+    confirm: Option<(PurgeFilter, Instant)>,
+    ttl: CacheTtl,
+    db: Database,
+    tasks: Tasks,
+    /// A running vacuum or export.
+    job: Option<Task<String>>,
+    /// Statistics being reloaded in the background.
+    loading: Option<Task<CacheView>>,
+    focus: Focus<Field>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Input,
+    Tables,
+    Entries,
+}
+
+impl CacheTool {
+    const NAME: &'static str = "Cache";
+
+    pub fn new(db: Database, config: &Config, tasks: &Tasks) -> Self {
+        let mut tool = CacheTool {
+            input: TextInput::new(),
+            selected: 0,
+            view: Arc::new(Mutex::new(CacheView::default())),
+            entry_list: ScrollList::new(),
+            confirm: None,
+            ttl: config.cache_ttl_days.clone(),
+            db,
+            tasks: tasks.clone(),
+            job: None,
+            loading: None,
+            focus: Focus::new(&[Field::Input, Field::Tables, Field::Entries]),
+        };
+
+        tool.reload();
+        tool
+    }
+
+    fn entry_rows(&self) -> Vec<Line<'static>> {
+        self.view
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|e| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:>5} ", e.at.map(cache::format_age).unwrap_or_default()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(e.label.clone()),
+                ])
+            })
+            .collect()
+    }
+
+    /// Reloads the statistics in the background; they arrive through
+    /// `on_task_finished`.
+    fn reload(&mut self) {
+        let db = self.db.clone();
+        let selected = self.selected;
+        self.loading = Some(self.tasks.spawn(
+            Self::NAME,
+            "Loading cache statistics",
+            move |_| async move { Self::query(&db, selected).await },
+        ));
+    }
+
+    async fn query(db: &Database, selected: usize) -> Result<CacheView, Box<dyn Error>> {
+        db.call(move |conn| {
+            Ok(CacheView {
+                stats: cache::stats(conn)?,
+                db_size: cache::database_size(conn)?,
+                entries: cache::entries(conn, &cache::TABLES[selected], ENTRY_LIMIT)?,
+                loaded: true,
+            })
+        })
+        .await
+    }
+
+    async fn load(
+        db: &Database,
+        view: &Mutex<CacheView>,
+        selected: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let loaded = Self::query(db, selected).await?;
+        *view.lock().unwrap() = loaded;
+        Ok(())
+    }
+
+    /// Reloads the statistics now, dropping a background reload that would
+    /// otherwise overwrite them with older ones.
+    async fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
+        self.loading = None;
+        Self::load(&self.db, &self.view, self.selected).await
+    }
+
+    /// Parses the input as an age (`30d`) or a user/org login.
+    fn input_filter(&self) -> Result<PurgeFilter, Box<dyn Error>> {
+        let input = self.input.value().trim();
+        if input.is_empty() {
+            return Err("Type a user/org or an age such as 30d first".into());
+        }
+        let filter = match input.strip_suffix('d').map(str::parse::<i64>) {
+            Some(Ok(days)) => PurgeFilter {
+                older_than_secs: Some(days * 86_400),
+                ..Default::default()
+            },
+            _ => PurgeFilter {
+                subject: Some(input.to_string()),
+                ..Default::default()
+            },
+        };
+        Ok(filter)
+    }
+
+    fn describe(filter: &PurgeFilter) -> String {
+        match (&filter.table, &filter.subject, filter.older_than_secs) {
+            (Some(table), _, _) => format!("all of {}", table),
+            (_, Some(subject), _) => format!("everything cached for '{}'", subject),
+            (_, _, Some(age)) => format!("entries older than {} days", age / 86_400),
+            _ => "the cache".to_string(),
+        }
+    }
+
+    /// Purges on the second identical request, so a stray key press cannot
+    /// wipe the cache.
+    async fn purge_confirmed(
+        &mut self,
+        filter: PurgeFilter,
+        key: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        if self.confirm.as_ref().map(|(pending, _)| pending) != Some(&filter) {
+            let prompt = format!("Press {} again to purge {}", key, Self::describe(&filter));
+            self.confirm = Some((filter, Instant::now()));
+            return Ok(prompt);
+        }
+        self.confirm = None;
+        let deleted = self
+            .db
+            .call(move |conn| cache::purge(conn, &filter))
+            .await?;
+        self.refresh().await?;
+        Ok(summarize("Purged", &deleted))
+    }
+}
+
+fn summarize(verb: &str, deleted: &[(&str, usize)]) -> String {
+    if deleted.is_empty() {
+        return "Nothing to purge".into();
+    }
+    let parts: Vec<String> = deleted
+        .iter()
+        .map(|(table, count)| format!("{} from {}", count, table))
+        .collect();
+    format!("{} {}", verb, parts.join(", "))
+}
+
+impl super::Tool for CacheTool {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn primary_result(&self) -> Option<String> {
+        let view = self.view.lock().unwrap();
+        if view.stats.is_empty() {
+            return None;
+        }
+        Some(
+            view.stats
+                .iter()
+                .map(|s| format!("{}\t{}", s.table.name, s.rows))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    fn render(&self, f: &mut Frame, area: Rect) {
+        if let Some(status) = self.job.as_ref().and_then(Task::status) {
+            components::render_task(f, area, &status);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        self.input.render(
+            f,
+            chunks[0],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Purge (user/org login, or an age such as 30d)",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Input),
+        );
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(chunks[1]);
+
+        let view = self.view.lock().unwrap();
+        let mut table_lines = vec![Line::from(Span::styled(
+            format!(
+                "{:<18} {:>8} {:>10} {:>7} {:>7} {:>5}",
+                "Table", "Rows", "Size", "Oldest", "Newest", "TTL"
+            ),
+            Style::default().bold(),
+        ))];
+        for (i, s) in view.stats.iter().enumerate() {
+            let ttl = match s.table.ttl_days(&self.ttl) {
+                Some(0) => "-".to_string(),
+                Some(days) => format!("{}d", days),
+                None if s.table.is_user_data() => "user".to_string(),
+                None => "-".to_string(),
+            };
+            let style = match (i == self.selected, self.focus.is(Field::Tables)) {
+                (true, true) => Style::default().bg(Color::Blue).fg(Color::White).bold(),
+                (true, false) => Style::default().bg(Color::DarkGray),
+                (false, _) => Style::default(),
+            };
+            table_lines.push(Line::from(Span::styled(
+                format!(
+                    "{:<18} {:>8} {:>10} {:>7} {:>7} {:>5}",
+                    s.table.name,
+                    s.rows,
+                    s.bytes.map(cache::format_bytes).unwrap_or_default(),
+                    s.oldest.map(cache::format_age).unwrap_or_default(),
+                    s.newest.map(cache::format_age).unwrap_or_default(),
+                    ttl
+                ),
+                style,
+            )));
+        }
+        if !view.loaded {
+            table_lines.push(Line::from("Loading..."));
+        }
+        let title = format!("cache.db ({})", cache::format_bytes(view.db_size));
+        let tables = Paragraph::new(table_lines).block(focus::border(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    title,
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Tables),
+        ));
+        f.render_widget(tables, columns[0]);
+
+        let loaded = view.loaded;
+        drop(view);
+        let mut entry_lines = self.entry_rows();
+        if entry_lines.is_empty() && loaded {
+            entry_lines.push(Line::from("No entries."));
+        }
+        let entries_title = match cache::TABLES.get(self.selected) {
+            Some(table) => format!("Entries: {}", table.name),
+            None => "Entries".to_string(),
+        };
+        self.entry_list.render(
+            f,
+            columns[1],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    entries_title,
+                    Style::default().fg(Color::Cyan),
+                ))),
+            entry_lines,
+            self.focus.is(Field::Entries),
+        );
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        self.entry_list.selected_text(&self.entry_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.entry_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.focus.cycle(backward);
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.focus.is(Field::Input).then_some(&mut self.input)
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            self.confirm = None;
+            match action {
+                Action::Refresh => {
+                    self.refresh().await?;
+                    Ok("Cache statistics refreshed".into())
+                }
+                Action::PurgeExpired => {
+                    let ttl = self.ttl.clone();
+                    let deleted = self
+                        .db
+                        .call(move |conn| cache::purge_expired(conn, &ttl))
+                        .await?;
+                    self.refresh().await?;
+                    Ok(summarize("Expired", &deleted))
+                }
+                Action::Vacuum => {
+                    let db = self.db.clone();
+                    let view = Arc::clone(&self.view);
+                    let selected = self.selected;
+                    self.job = Some(self.tasks.spawn(
+                        Self::NAME,
+                        "Vacuuming cache.db",
+                        move |_| async move {
+                            let before = view.lock().unwrap().db_size;
+                            db.call(|conn| cache::vacuum(conn)).await?;
+                            Self::load(&db, &view, selected).await?;
+                            let after = view.lock().unwrap().db_size;
+                            Ok(format!(
+                                "Vacuumed: {} -> {}",
+                                cache::format_bytes(before),
+                                cache::format_bytes(after)
+                            ))
+                        },
+                    ));
+                    Ok("Vacuuming cache.db (cancelling only stops waiting)".into())
+                }
+                Action::Export => {
+                    let db = self.db.clone();
+                    self.job = Some(self.tasks.spawn(
+                        Self::NAME,
+                        "Exporting the cache",
+                        move |_| async move {
+                            let rows = db
+                                .call(|conn| cache::export(conn, Path::new(EXPORT_PATH)))
+                                .await?;
+                            Ok(format!("Exported {} rows to {}", rows, EXPORT_PATH))
+                        },
+                    ));
+                    Ok(format!(
+                        "Exporting to {} (cancelling only stops waiting)",
+                        EXPORT_PATH
+                    ))
+                }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    /// Fills in the purge input and asks for the usual confirming `Enter`.
+    fn run_command(&mut self, _id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            self.input.set_value(&argument);
+            self.focus.set(Field::Input);
+            let filter = self.input_filter()?;
+            self.purge_confirmed(filter, "Enter").await
+        })
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match key.code {
+                KeyCode::Enter => {
+                    let filter = self.input_filter()?;
+                    let result = self.purge_confirmed(filter, "Enter").await;
+                    if self.confirm.is_none() {
+                        self.input.clear();
+                    }
+                    return result;
+                }
+                KeyCode::Delete => {
+                    let table = cache::TABLES[self.selected];
+                    if table.is_user_data() {
+                        self.confirm = None;
+                        return Ok(format!("{} holds user data and is not purged", table.name));
+                    }
+                    let filter = PurgeFilter {
+                        table: Some(table.name.to_string()),
+                        ..Default::default()
+                    };
+                    return self.purge_confirmed(filter, "Delete").await;
+                }
+                _ => {}
+            }
+            self.confirm = None;
+
+            match key.code {
+                _ if self.focus.is(Field::Entries) && self.entry_list.handle_focused_key(&key) => {
+                    Ok(String::new())
+                }
+                _ if self.entry_list.handle_key(&key) => Ok(String::new()),
+                KeyCode::Up => {
+                    self.selected = self.selected.saturating_sub(1);
+                    self.entry_list.reset();
+                    self.refresh().await?;
+                    Ok(String::new())
+                }
+                KeyCode::Down => {
+                    self.selected = (self.selected + 1).min(cache::TABLES.len() - 1);
+                    self.entry_list.reset();
+                    self.refresh().await?;
+                    Ok(String::new())
+                }
+                _ if self.focus.is(Field::Input) && self.input.handle_key(&key) => {
+                    Ok("Input updated".into())
+                }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn on_tick(&mut self) -> Option<String> {
+        let (_, since) = self.confirm.as_ref()?;
+        if since.elapsed() < CONFIRM_TIMEOUT {
+            return None;
+        }
+        self.confirm = None;
+        Some("Purge not confirmed".into())
+    }
+
+    /// The statistics may be stale after other tools fetched data.
+    fn on_focus(&mut self) -> Option<String> {
+        self.reload();
+        None
+    }
+
+    fn on_blur(&mut self) {
+        self.confirm = None;
+    }
+
+    fn on_task_finished(&mut self) -> Option<String> {
+        match Task::take_finished(&mut self.loading) {
+            Some(Ok(view)) => *self.view.lock().unwrap() = view,
+            Some(Err(e)) => return Some(format!("Could not load cache statistics: {}", e)),
+            None => {}
+        }
+        Some(Task::take_finished(&mut self.job)?.unwrap_or_else(|e| e))
+    }
+}
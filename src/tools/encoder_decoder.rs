@@ -1,4 +1,9 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::keymap::Action;
+use crate::tools::Command;
+use crate::ui::editor::Editor;
+use crate::ui::focus::Focus;
+use crate::ui::list::ScrollList;
+use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
@@ -34,12 +39,29 @@ impl Format {
 }
 
 pub struct EncoderDecoderTool {
-    input: String,
+    input: Editor,
     format: Format,
     is_encode: bool,
     result: Option<Result<String, String>>,
+    result_list: ScrollList,
+    focus: Focus<Field>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Input,
+    Result,
+}
+
+const COMMANDS: &[Command] = &[
+    Command::new("encode", "Encode"),
+    Command::new("decode", "Decode"),
+    Command::new("base64", "Switch to Base64"),
+    Command::new("hex", "Switch to Hex"),
+    Command::new("url", "Switch to URL"),
+    Command::new("input", "Convert Text...").with_prompt("Text"),
+];
+
 impl Default for EncoderDecoderTool {
     fn default() -> Self {
         Self::new()
@@ -49,10 +71,12 @@ impl Default for EncoderDecoderTool {
 impl EncoderDecoderTool {
     pub fn new() -> Self {
         EncoderDecoderTool {
-            input: String::new(),
+            input: Editor::new(),
             format: Format::Base64,
             is_encode: true,
             result: None,
+            result_list: ScrollList::new(),
+            focus: Focus::new(&[Field::Input, Field::Result]),
         }
     }
 
@@ -61,24 +85,38 @@ impl EncoderDecoderTool {
             self.result = None;
             return;
         }
+        let input = self.input.value();
+        // Encoded Base64 and hex are often wrapped across lines.
+        let encoded: String = input.split_whitespace().collect();
 
         let res = match (self.format, self.is_encode) {
-            (Format::Base64, true) => Ok(b64.encode(&self.input)),
+            (Format::Base64, true) => Ok(b64.encode(input)),
             (Format::Base64, false) => b64
-                .decode(&self.input)
+                .decode(&encoded)
                 .map_err(|e| e.to_string())
                 .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string())),
-            (Format::Hex, true) => Ok(hex::encode(&self.input)),
-            (Format::Hex, false) => hex::decode(&self.input)
+            (Format::Hex, true) => Ok(hex::encode(input)),
+            (Format::Hex, false) => hex::decode(&encoded)
                 .map_err(|e| e.to_string())
                 .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string())),
-            (Format::Url, true) => Ok(urlencoding::encode(&self.input).into_owned()),
-            (Format::Url, false) => urlencoding::decode(&self.input)
+            (Format::Url, true) => Ok(urlencoding::encode(input).into_owned()),
+            (Format::Url, false) => urlencoding::decode(input)
                 .map_err(|e| e.to_string())
                 .map(|s| s.into_owned()),
         };
         self.result = Some(res);
     }
+
+    fn result_rows(&self) -> Vec<Line<'_>> {
+        match &self.result {
+            Some(Ok(res)) => res.lines().map(Line::from).collect(),
+            Some(Err(err)) => vec![Line::styled(
+                format!("Error: {}", err),
+                Style::default().fg(Color::Red),
+            )],
+            None => Vec::new(),
+        }
+    }
 }
 
 impl super::Tool for EncoderDecoderTool {
@@ -86,12 +124,16 @@ impl super::Tool for EncoderDecoderTool {
         "Encoder/Decoder"
     }
 
+    fn primary_result(&self) -> Option<String> {
+        self.result.as_ref()?.as_ref().ok().cloned()
+    }
+
     fn render(&self, f: &mut Frame, area: Rect) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),
-                Constraint::Length(3),
+                Constraint::Percentage(40),
                 Constraint::Min(0),
             ])
             .split(area);
@@ -107,47 +149,59 @@ impl super::Tool for EncoderDecoderTool {
                 self.format.name(),
                 Style::default().fg(Color::Yellow).bold(),
             ),
-            Span::raw(" | [Ctrl+M] Toggle Mode | [Ctrl+T] Toggle Format"),
         ]);
         let config_para = Paragraph::new(config_line).block(Block::default().borders(Borders::ALL));
         f.render_widget(config_para, chunks[0]);
 
-        let input_para = Paragraph::new(self.input.as_str()).block(
+        self.input.render(
+            f,
+            chunks[1],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Input",
                     Style::default().fg(Color::Green),
                 ))),
+            self.focus.is(Field::Input),
         );
-        f.render_widget(input_para, chunks[1]);
 
-        let result_content = match &self.result {
-            Some(Ok(res)) => res.clone(),
-            Some(Err(err)) => format!("Error: {}", err),
-            None => String::new(),
-        };
-
-        let result_style = match &self.result {
-            Some(Err(_)) => Style::default().fg(Color::Red),
-            _ => Style::default(),
-        };
-
-        let result_para = Paragraph::new(result_content).style(result_style).block(
+        self.result_list.render(
+            f,
+            chunks[2],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Result",
                     Style::default().fg(Color::Cyan),
                 ))),
+            self.result_rows(),
+            self.focus.is(Field::Result),
         );
-        f.render_widget(result_para, chunks[2]);
     }
 
-    fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
+    fn selected_row(&self) -> Option<String> {
+        self.result_list.selected_text(&self.result_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.result_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.focus.cycle(backward);
+    }
+
+    fn handle_paste(&mut self, text: &str) -> Option<String> {
+        self.focus.set(Field::Input);
+        self.input.insert_str(text);
+        self.process();
+        Some(format!("Pasted {} characters", text.chars().count()))
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
         Box::pin(async move {
-            match key.code {
-                KeyCode::Char('m') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match action {
+                Action::ToggleMode => {
                     self.is_encode = !self.is_encode;
                     self.process();
                     Ok(format!(
@@ -155,21 +209,57 @@ impl super::Tool for EncoderDecoderTool {
                         if self.is_encode { "Encode" } else { "Decode" }
                     ))
                 }
-                KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Action::ToggleFormat => {
                     self.format = self.format.next();
                     self.process();
                     Ok(format!("Format toggled to {}", self.format.name()))
                 }
-                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.input.push(c);
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match id {
+                "encode" | "decode" => self.is_encode = id == "encode",
+                "base64" => self.format = Format::Base64,
+                "hex" => self.format = Format::Hex,
+                "url" => self.format = Format::Url,
+                _ => {
+                    self.input.set_value(&argument);
+                    self.result_list.reset();
+                }
+            }
+            self.process();
+            Ok(format!(
+                "{} as {}",
+                if self.is_encode {
+                    "Encoding"
+                } else {
+                    "Decoding"
+                },
+                self.format.name()
+            ))
+        })
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match key.code {
+                _ if self.focus.is(Field::Input) && self.input.handle_key(&key) => {
+                    self.result_list.reset();
                     self.process();
                     Ok("Input updated".into())
                 }
-                KeyCode::Backspace => {
-                    self.input.pop();
-                    self.process();
-                    Ok("Removed character".into())
+                _ if self.focus.is(Field::Result) && self.result_list.handle_focused_key(&key) => {
+                    Ok(String::new())
                 }
+                _ if self.result_list.handle_key(&key) => Ok(String::new()),
                 _ => Ok(String::new()),
             }
         })
@@ -1,10 +1,16 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::keymap::Action;
+use crate::tasks::{Task, Tasks};
+use crate::tools::Command;
+use crate::ui::components::{self, TextInput};
+use crate::ui::editor::Editor;
+use crate::ui::focus::{self, Focus};
+use crate::ui::list::ScrollList;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
 use reqwest::{Client, Method};
-use std::error::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum HttpMethod {
@@ -43,44 +49,94 @@ impl HttpMethod {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Method,
+    Url,
+    Body,
+    Response,
+}
+
+const COMMANDS: &[Command] = &[
+    Command::new("send", "Send Request"),
+    Command::new("request_url", "Request URL...").with_prompt("URL"),
+    Command::new("get", "Set Method to GET"),
+    Command::new("post", "Set Method to POST"),
+    Command::new("put", "Set Method to PUT"),
+    Command::new("delete", "Set Method to DELETE"),
+];
+
 pub struct HttpRequestInspectorTool {
     client: Client,
-    url: String,
+    url: TextInput,
     method: HttpMethod,
-    loading: bool,
+    body: Editor,
+    focus: Focus<Field>,
+    tasks: Tasks,
+    request: Option<Task<(Option<u16>, String)>>,
     response: Option<String>,
     status: Option<u16>,
+    response_list: ScrollList,
 }
 
 impl HttpRequestInspectorTool {
-    pub fn new(client: &Client) -> Self {
+    pub fn new(client: &Client, tasks: &Tasks) -> Self {
         HttpRequestInspectorTool {
             client: client.clone(),
-            url: "https://".to_string(),
+            url: {
+                let mut url = TextInput::new().with_history();
+                url.set_value("https://");
+                url
+            },
             method: HttpMethod::Get,
-            loading: false,
+            body: Editor::new().with_json_highlighting(),
+            focus: {
+                let mut focus =
+                    Focus::new(&[Field::Method, Field::Url, Field::Body, Field::Response]);
+                focus.set(Field::Url);
+                focus
+            },
+            tasks: tasks.clone(),
+            request: None,
             response: None,
             status: None,
+            response_list: ScrollList::new(),
         }
     }
 
-    async fn send_request(&mut self) -> Result<String, Box<dyn Error>> {
+    fn cycle_method(&mut self) -> String {
+        self.method = self.method.next();
+        format!("Method toggled to {}", self.method.as_str())
+    }
+
+    /// Sends the request in the background; the response arrives through
+    /// `on_task_finished`.
+    fn send_request(&mut self) -> String {
         if self.url.is_empty() {
-            return Ok("URL cannot be empty".into());
+            return "URL cannot be empty".into();
         }
+        self.url.commit();
 
-        self.loading = true;
-
-        let request = self
+        let mut request = self
             .client
-            .request(self.method.as_reqwest_method(), &self.url);
-        let resp = request.send().await;
-
-        self.loading = false;
-
-        match resp {
-            Ok(r) => {
-                self.status = Some(r.status().as_u16());
+            .request(self.method.as_reqwest_method(), self.url.value());
+        if !self.body.is_empty() {
+            let body = self.body.value().to_string();
+            if serde_json::from_str::<serde_json::Value>(&body).is_ok() {
+                request = request.header("Content-Type", "application/json");
+            }
+            request = request.body(body);
+        }
+        self.request = Some(self.tasks.spawn(
+            "HTTP Inspector",
+            "Sending Request",
+            |progress| async move {
+                let r = match request.send().await {
+                    Ok(r) => r,
+                    Err(e) => return Ok((None, format!("Error: {}", e))),
+                };
+                let status = r.status().as_u16();
+                progress.set(format!("Reading response body (status {})", status));
                 let headers = r
                     .headers()
                     .iter()
@@ -96,15 +152,39 @@ impl HttpRequestInspectorTool {
                     body = body.chars().take(10_000).collect::<String>();
                     body.push_str("\n...[truncated]");
                 }
-                self.response = Some(format!("Headers:\n{}\n\nBody:\n{}", headers, body));
-                Ok("Request completed".into())
-            }
-            Err(e) => {
-                self.status = None;
-                self.response = Some(format!("Error: {}", e));
-                Ok("Request failed".into())
+                Ok((
+                    Some(status),
+                    format!("Headers:\n{}\n\nBody:\n{}", headers, body),
+                ))
+            },
+        ));
+        "Sending request".into()
+    }
+
+    fn response_rows(&self) -> Vec<Line<'_>> {
+        let mut rows = vec![];
+        if let Some(status) = self.status {
+            let color = if (200..300).contains(&status) {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            rows.push(Line::from(vec![
+                Span::styled("Status: ", Style::default().bold()),
+                Span::styled(status.to_string(), Style::default().fg(color).bold()),
+            ]));
+            rows.push(Line::from(""));
+        }
+
+        if let Some(resp) = &self.response {
+            // Stored unredacted so the reveal toggle applies on the next frame.
+            for line in crate::redact::redact(resp).lines() {
+                rows.push(Line::from(line.to_string()));
             }
+        } else {
+            rows.push(Line::from("No request sent yet."));
         }
+        rows
     }
 }
 
@@ -113,83 +193,179 @@ impl super::Tool for HttpRequestInspectorTool {
         "HTTP Inspector"
     }
 
+    fn primary_result(&self) -> Option<String> {
+        let response = self.response.as_ref()?;
+        Some(
+            response
+                .split_once("\n\nBody:\n")
+                .map_or(response.as_str(), |(_, body)| body)
+                .to_string(),
+        )
+    }
+
     fn render(&self, f: &mut Frame, area: Rect) {
-        if self.loading {
-            let loading = Paragraph::new("Sending Request...")
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::ALL));
-            f.render_widget(loading, area);
+        if let Some(status) = self.request.as_ref().and_then(Task::status) {
+            components::render_task(f, area, &status);
             return;
         }
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(10),
+                Constraint::Min(0),
+            ])
             .split(area);
 
-        let input_line = Line::from(vec![
-            Span::raw("Method [Ctrl+M]: "),
-            Span::styled(
-                self.method.as_str(),
-                Style::default().fg(Color::Yellow).bold(),
-            ),
-            Span::raw(" | URL: "),
-            Span::styled(&self.url, Style::default().fg(Color::White)),
-        ]);
-
-        let input_para =
-            Paragraph::new(input_line).block(Block::default().borders(Borders::ALL).title(
-                Line::from(Span::styled("Request", Style::default().fg(Color::Green))),
-            ));
-        f.render_widget(input_para, chunks[0]);
-
-        let mut res_lines = vec![];
-        if let Some(status) = self.status {
-            let color = if (200..300).contains(&status) {
-                Color::Green
-            } else {
-                Color::Red
-            };
-            res_lines.push(Line::from(vec![
-                Span::styled("Status: ", Style::default().bold()),
-                Span::styled(status.to_string(), Style::default().fg(color).bold()),
-            ]));
-            res_lines.push(Line::from(""));
+        let request = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(18), Constraint::Min(0)])
+            .split(chunks[0]);
+
+        let method = Paragraph::new(Span::styled(
+            self.method.as_str(),
+            Style::default().fg(Color::Yellow).bold(),
+        ))
+        .block(focus::border(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Method",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Method),
+        ));
+        f.render_widget(method, request[0]);
+
+        self.url.render(
+            f,
+            request[1],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Request URL",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Url),
+        );
+
+        self.body.render(
+            f,
+            chunks[1],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Body [Ctrl+G: Find]",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Body),
+        );
+
+        self.response_list.render(
+            f,
+            chunks[2],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Response",
+                    Style::default().fg(Color::Cyan),
+                ))),
+            self.response_rows(),
+            self.focus.is(Field::Response),
+        );
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        self.response_list.selected_text(&self.response_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.response_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.focus.cycle(backward);
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.focus.is(Field::Url).then_some(&mut self.url)
+    }
+
+    fn handle_paste(&mut self, text: &str) -> Option<String> {
+        match self.focus.current() {
+            Field::Url => self.url.insert_str(text),
+            Field::Body => self.body.insert_str(text),
+            Field::Method | Field::Response => return None,
         }
+        Some(format!("Pasted {} characters", text.chars().count()))
+    }
 
-        if let Some(resp) = &self.response {
-            for line in resp.lines() {
-                res_lines.push(Line::from(line.to_string()));
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match action {
+                Action::CycleMethod => Ok(self.cycle_method()),
+                _ => Ok(String::new()),
             }
-        } else {
-            res_lines.push(Line::from("No request sent yet."));
-        }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
 
-        let result_para =
-            Paragraph::new(res_lines).block(Block::default().borders(Borders::ALL).title(
-                Line::from(Span::styled("Response", Style::default().fg(Color::Cyan))),
-            ));
-        f.render_widget(result_para, chunks[1]);
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            self.method = match id {
+                "get" => HttpMethod::Get,
+                "post" => HttpMethod::Post,
+                "put" => HttpMethod::Put,
+                "delete" => HttpMethod::Delete,
+                "request_url" => {
+                    self.url.set_value(&argument);
+                    return Ok(self.send_request());
+                }
+                _ => return Ok(self.send_request()),
+            };
+            Ok(format!("Method set to {}", self.method.as_str()))
+        })
     }
 
     fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
         Box::pin(async move {
             match key.code {
-                KeyCode::Char('m') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.method = self.method.next();
-                    Ok(format!("Method toggled to {}", self.method.as_str()))
+                KeyCode::Char(' ') if self.focus.is(Field::Method) => Ok(self.cycle_method()),
+                _ if self.focus.is(Field::Body) && self.body.handle_key(&key) => {
+                    Ok("Body updated".into())
                 }
-                KeyCode::Enter => self.send_request().await,
-                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.url.push(c);
-                    Ok("URL updated".into())
+                _ if self.focus.is(Field::Response)
+                    && self.response_list.handle_focused_key(&key) =>
+                {
+                    Ok(String::new())
                 }
-                KeyCode::Backspace => {
-                    self.url.pop();
-                    Ok("Removed character".into())
+                _ if self.response_list.handle_key(&key) => Ok(String::new()),
+                KeyCode::Enter => Ok(self.send_request()),
+                _ if self.focus.is(Field::Url) && self.url.handle_key(&key) => {
+                    Ok("URL updated".into())
                 }
                 _ => Ok(String::new()),
             }
         })
     }
+
+    fn on_task_finished(&mut self) -> Option<String> {
+        match Task::take_finished(&mut self.request)? {
+            Ok((status, response)) => {
+                self.status = status;
+                self.response = Some(response);
+                self.response_list.reset();
+                Some(if status.is_some() {
+                    "Request completed".into()
+                } else {
+                    "Request failed".into()
+                })
+            }
+            Err(e) => Some(e),
+        }
+    }
 }
@@ -1,18 +1,35 @@
+use crate::tools::Command;
+use crate::ui::editor::Editor;
+use crate::ui::focus::Focus;
+use crate::ui::list::ScrollList;
 use base64::{engine::general_purpose, Engine as _};
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders},
 };
 use serde_json::Value;
 use std::error::Error;
 
 pub struct JwtDecoderTool {
-    input: String,
+    input: Editor,
     header: Option<Value>,
     payload: Option<Value>,
+    result_list: ScrollList,
+    focus: Focus<Field>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Input,
+    Results,
+}
+
+const COMMANDS: &[Command] = &[
+    Command::new("decode", "Decode"),
+    Command::new("decode_token", "Decode Token...").with_prompt("JWT"),
+];
+
 impl Default for JwtDecoderTool {
     fn default() -> Self {
         Self::new()
@@ -22,14 +39,18 @@ impl Default for JwtDecoderTool {
 impl JwtDecoderTool {
     pub fn new() -> Self {
         JwtDecoderTool {
-            input: String::new(),
+            input: Editor::new(),
             header: None,
             payload: None,
+            result_list: ScrollList::new(),
+            focus: Focus::new(&[Field::Input, Field::Results]),
         }
     }
 
     fn decode_jwt(&mut self) -> Result<String, Box<dyn Error>> {
-        let parts: Vec<&str> = self.input.split('.').collect();
+        // Tokens pasted from logs or docs are often wrapped.
+        let token: String = self.input.value().split_whitespace().collect();
+        let parts: Vec<&str> = token.split('.').collect();
         if parts.len() != 3 {
             return Err("Invalid JWT format".into());
         }
@@ -42,31 +63,11 @@ impl JwtDecoderTool {
 
         self.header = Some(decode_part(parts[0])?);
         self.payload = Some(decode_part(parts[1])?);
+        self.result_list.reset();
         Ok("Decoded JWT".into())
     }
-}
-
-impl super::Tool for JwtDecoderTool {
-    fn name(&self) -> &'static str {
-        "JWT Decoder"
-    }
-
-    fn render(&self, f: &mut Frame, area: Rect) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0)])
-            .split(area);
-
-        let input = Paragraph::new(self.input.as_str()).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(Line::from(Span::styled(
-                    "JWT Input",
-                    Style::default().fg(Color::Green),
-                ))),
-        );
-        f.render_widget(input, chunks[0]);
 
+    fn result_rows(&self) -> Vec<Line<'_>> {
         let mut lines = vec![
             Line::from(Span::styled(
                 "⚠ WARNING: Signature NOT verified. This tool only decodes the payload.",
@@ -110,26 +111,91 @@ impl super::Tool for JwtDecoderTool {
                 .as_ref()
                 .map_or("None".to_string(), |v| v.to_string())
         )));
+        lines
+    }
+}
 
-        let results =
-            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(Line::from(
-                Span::styled("JWT Results", Style::default().fg(Color::Green)),
-            )));
-        f.render_widget(results, chunks[1]);
+impl super::Tool for JwtDecoderTool {
+    fn name(&self) -> &'static str {
+        "JWT Decoder"
+    }
+
+    fn primary_result(&self) -> Option<String> {
+        serde_json::to_string_pretty(self.payload.as_ref()?).ok()
+    }
+
+    fn render(&self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(6), Constraint::Min(0)])
+            .split(area);
+
+        self.input.render(
+            f,
+            chunks[0],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "JWT Input",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Input),
+        );
+
+        self.result_list.render(
+            f,
+            chunks[1],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "JWT Results",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.result_rows(),
+            self.focus.is(Field::Results),
+        );
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        self.result_list.selected_text(&self.result_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.result_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.focus.cycle(backward);
+    }
+
+    fn handle_paste(&mut self, text: &str) -> Option<String> {
+        self.focus.set(Field::Input);
+        self.input.insert_str(text);
+        Some(format!("Pasted {} characters", text.chars().count()))
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        if id == "decode_token" {
+            self.input.set_value(&argument);
+        }
+        Box::pin(async move { self.decode_jwt() })
     }
 
     fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
         Box::pin(async move {
             match key.code {
-                KeyCode::Char(c) => {
-                    self.input.push(c);
+                KeyCode::Enter if !self.input.is_searching() => self.decode_jwt(),
+                _ if self.focus.is(Field::Input) && self.input.handle_key(&key) => {
                     Ok("Input updated".into())
                 }
-                KeyCode::Backspace => {
-                    self.input.pop();
-                    Ok("Removed character".into())
+                _ if self.focus.is(Field::Results) && self.result_list.handle_focused_key(&key) => {
+                    Ok(String::new())
                 }
-                KeyCode::Enter => self.decode_jwt(),
+                _ if self.result_list.handle_key(&key) => Ok(String::new()),
                 _ => Ok(String::new()),
             }
         })
@@ -143,7 +209,7 @@ mod tests {
     #[test]
     fn test_decode_jwt_invalid_format() {
         let mut tool = JwtDecoderTool::new();
-        tool.input = "invalid.jwt".to_string();
+        tool.input.set_value("invalid.jwt");
         assert!(tool.decode_jwt().is_err());
     }
 
@@ -155,7 +221,7 @@ mod tests {
         let jwt = format!("{}.{}.signature", header, payload);
 
         let mut tool = JwtDecoderTool::new();
-        tool.input = jwt;
+        tool.input.set_value(&jwt);
         assert!(tool.decode_jwt().is_ok());
         assert_eq!(tool.header.unwrap()["alg"], "HS256");
         assert_eq!(tool.payload.unwrap()["name"], "John Doe");
@@ -1,9 +1,12 @@
-use crossterm::event::KeyEvent;
+use crate::keymap::Action;
+use crate::ui::components::TextInput;
+use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::prelude::*;
 use std::error::Error;
 use std::future::Future;
 use std::pin::Pin;
 
+pub mod cache;
 pub mod encoder_decoder;
 pub mod http_inspector;
 pub mod jwt_decoder;
@@ -11,7 +14,9 @@ pub mod org_research;
 pub mod repo_explorer;
 pub mod token_inspector;
 pub mod unicode_inspector;
+pub mod watchlist;
 
+pub use cache::CacheTool;
 pub use encoder_decoder::EncoderDecoderTool;
 pub use http_inspector::HttpRequestInspectorTool;
 pub use jwt_decoder::JwtDecoderTool;
@@ -19,14 +24,126 @@ pub use org_research::OrgResearchTool;
 pub use repo_explorer::RepoExplorerTool;
 pub use token_inspector::TokenInspectorTool;
 pub use unicode_inspector::UnicodeInspectorTool;
+pub use watchlist::WatchlistTool;
 
 pub type ToolFuture<'a> = Pin<Box<dyn Future<Output = Result<String, Box<dyn Error>>> + Send + 'a>>;
 
+/// An entry a tool adds to the command palette, on top of its keymap
+/// actions, e.g. "Switch to Hex".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Command {
+    /// Passed back to [`Tool::run_command`].
+    pub id: &'static str,
+    pub title: &'static str,
+    /// What the palette asks for before running the command, e.g. `"URL"`.
+    /// The answer is passed to `run_command`.
+    pub prompt: Option<&'static str>,
+}
+
+impl Command {
+    pub const fn new(id: &'static str, title: &'static str) -> Self {
+        Self {
+            id,
+            title,
+            prompt: None,
+        }
+    }
+
+    pub const fn with_prompt(mut self, prompt: &'static str) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+}
+
 pub trait Tool: Send + Sync {
     fn name(&self) -> &'static str;
     fn render(&self, f: &mut Frame, area: Rect);
     fn handle_input(&mut self, key: KeyEvent) -> ToolFuture<'_>;
 
+    /// A warning the app should surface in the global status bar, e.g. an
+    /// expiring token, regardless of which tool is selected.
+    fn status_warning(&self) -> Option<String> {
+        None
+    }
+
+    /// The tool's main output as plain text, copied by `Ctrl+C` in place of
+    /// the status message.
+    fn primary_result(&self) -> Option<String> {
+        None
+    }
+
+    /// Whether the tool talks to the GitHub API. Without a token the app
+    /// shows a "configure a token" panel in place of such tools.
+    fn requires_github_token(&self) -> bool {
+        false
+    }
+
+    /// The selected row of the tool's result list as plain text, copied by
+    /// `Ctrl+Y`.
+    fn selected_row(&self) -> Option<String> {
+        None
+    }
+
+    /// Mouse events other than clicks on the tab bar, e.g. the wheel over a
+    /// result list.
+    fn handle_mouse(&mut self, _mouse: &MouseEvent) {}
+
+    /// Runs a shortcut from the keymap. Only actions that list the tool in
+    /// the keymap are passed here; their keys never reach `handle_input`.
+    fn handle_action(&mut self, _action: Action) -> ToolFuture<'_> {
+        Box::pin(async { Ok(String::new()) })
+    }
+
+    /// Commands the tool lists in the command palette.
+    fn commands(&self) -> &'static [Command] {
+        &[]
+    }
+
+    /// Runs one of [`commands`](Self::commands) picked in the palette.
+    /// `argument` is the answer to its prompt, or empty if it has none.
+    fn run_command(&mut self, _id: &'static str, _argument: String) -> ToolFuture<'_> {
+        Box::pin(async { Ok(String::new()) })
+    }
+
+    /// Moves focus to the tool's next widget on `Tab`, or the previous one on
+    /// `Shift+Tab` when `backward`.
+    fn cycle_focus(&mut self, _backward: bool) {}
+
+    /// The text field that has focus, which receives pasted text and whose
+    /// selection `Ctrl+C` copies.
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        None
+    }
+
+    /// Inserts bracketed-paste text in one go, instead of one key event per
+    /// character. Returns the status message, or `None` if nothing took it.
+    fn handle_paste(&mut self, text: &str) -> Option<String> {
+        self.focused_input()?.insert_str(text);
+        Some(format!("Pasted {} characters", text.chars().count()))
+    }
+
+    /// Called on every tick of the event loop (`tick_rate_ms`) for every
+    /// tool, selected or not. Returns a status message to show, if any.
+    fn on_tick(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Called when the tool is selected or the terminal regains focus while
+    /// it is selected. Returns a status message to show, if any.
+    fn on_focus(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Called when another tool is selected or the terminal loses focus.
+    fn on_blur(&mut self) {}
+
+    /// Called when one of the tool's [`Task`](crate::tasks::Task)s finished
+    /// or was cancelled, so the tool can take its result. Returns the status
+    /// message to show, if any.
+    fn on_task_finished(&mut self) -> Option<String> {
+        None
+    }
+
     fn as_persistable(&self) -> Option<&dyn Persistable> {
         None
     }
@@ -1,97 +1,228 @@
-use crate::models::github::Organization;
+use crate::db::Database;
+use crate::keymap::Action;
+use crate::models::github::{Organization, User};
 use crate::secrets::Secrets;
+use crate::snapshots::{self, OrgSnapshot, SnapshotDiff, SnapshotKind};
+use crate::tasks::{Task, Tasks};
+use crate::tools::Command;
+use crate::ui::components::{self, TextInput};
+use crate::ui::focus::{self, Focus};
+use crate::ui::list::ScrollList;
 use reqwest::Client;
 use std::error::Error;
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
-use serde_json;
 
 pub struct OrgResearchTool {
     input: InputState,
     results: Vec<Organization>,
+    members: Vec<User>,
+    db: Database,
     client: Client,
     secrets: Secrets,
-    loading: bool,
+    tasks: Tasks,
+    fetch: Option<Task<OrgFetch>>,
     scope_warning: Option<String>,
+    changes: Option<SnapshotDiff>,
+    show_changes: bool,
+    result_list: ScrollList,
 }
 
+/// What a background search hands back to the tool.
+struct OrgFetch {
+    orgs: Vec<Organization>,
+    members: Vec<User>,
+    scope_warning: Option<String>,
+    changes: Option<SnapshotDiff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    ParentOrg,
+    SearchTerm,
+    AllowNoParent,
+    Results,
+}
+
+const COMMANDS: &[Command] = &[
+    Command::new("search", "Search Organizations...").with_prompt("Search term"),
+    Command::new("parent", "Search in Organization...").with_prompt("Parent organization"),
+];
+
 struct InputState {
-    parent_org: String,
-    search_term: String,
-    current_field: usize,
+    parent_org: TextInput,
+    search_term: TextInput,
+    focus: Focus<Field>,
     allow_no_parent: bool,
 }
 
+impl InputState {
+    fn field(&mut self) -> Option<&mut TextInput> {
+        match self.focus.current() {
+            Field::ParentOrg => Some(&mut self.parent_org),
+            Field::SearchTerm => Some(&mut self.search_term),
+            Field::AllowNoParent | Field::Results => None,
+        }
+    }
+}
+
 use crate::models::github::SearchResponse;
 use secrecy::ExposeSecret;
 
 impl OrgResearchTool {
-    pub fn new(client: &Client, secrets: &Secrets) -> Result<Self, Box<dyn Error>> {
+    const NAME: &'static str = "Org Research";
+
+    pub fn new(
+        db: Database,
+        client: &Client,
+        secrets: &Secrets,
+        tasks: &Tasks,
+    ) -> Result<Self, Box<dyn Error>> {
         Ok(OrgResearchTool {
             input: InputState {
-                parent_org: String::new(),
-                search_term: String::new(),
-                current_field: 0,
+                parent_org: TextInput::new(),
+                search_term: TextInput::new(),
+                focus: Focus::new(&[
+                    Field::ParentOrg,
+                    Field::SearchTerm,
+                    Field::AllowNoParent,
+                    Field::Results,
+                ]),
                 allow_no_parent: false,
             },
             results: Vec::new(),
+            members: Vec::new(),
+            db,
             client: client.clone(),
             secrets: secrets.clone(),
-            loading: false,
+            tasks: tasks.clone(),
+            fetch: None,
             scope_warning: None,
+            changes: None,
+            show_changes: false,
+            result_list: ScrollList::new(),
         })
     }
 
-    async fn fetch_orgs(&mut self) -> Result<String, Box<dyn Error>> {
-        self.loading = true;
-        let query = if self.input.parent_org.is_empty() {
-            self.input.search_term.clone()
+    fn toggle_allow_no_parent(&mut self) -> String {
+        self.input.allow_no_parent = !self.input.allow_no_parent;
+        format!("Allow No Parent: {}", self.input.allow_no_parent)
+    }
+
+    fn showing_changes(&self) -> bool {
+        self.show_changes && self.changes.is_some()
+    }
+
+    /// The organizations, or the changes since the last snapshot while
+    /// those are shown.
+    fn result_rows(&self) -> Vec<Line<'_>> {
+        match (&self.changes, self.show_changes) {
+            (Some(changes), true) => changes.lines().into_iter().map(Line::from).collect(),
+            _ => self
+                .results
+                .iter()
+                .map(|org| Line::from(org.login.as_str()))
+                .collect(),
+        }
+    }
+
+    /// Starts the search in the background; the result arrives through
+    /// `on_task_finished`.
+    fn fetch_orgs(&mut self) -> String {
+        let client = self.client.clone();
+        let token = self.secrets.token_for(Self::NAME);
+        let db = self.db.clone();
+        let parent_org = self.input.parent_org.value().to_string();
+        let search_term = self.input.search_term.value();
+        let query = if parent_org.is_empty() {
+            search_term.to_string()
         } else {
-            format!("org:{} {}", self.input.parent_org, self.input.search_term)
+            format!("org:{} {}", parent_org, search_term)
         };
 
-        let url = format!("https://api.github.com/search/users?q={}+type:org", query);
-        let resp = self
-            .client
-            .get(&url)
-            .header(
-                "Authorization",
-                format!("token {}", self.secrets.github_token.expose_secret()),
-            )
-            .send()
-            .await?;
+        self.fetch = Some(self.tasks.spawn(
+            Self::NAME,
+            "Searching GitHub Organizations",
+            move |progress| async move {
+                let url = format!("https://api.github.com/search/users?q={}+type:org", query);
+                let resp = client
+                    .get(&url)
+                    .header("Authorization", format!("token {}", token.expose_secret()))
+                    .send()
+                    .await?;
 
-        if !resp.status().is_success() {
-            self.loading = false;
-            return Err(format!("GitHub API error: {}", resp.status()).into());
-        }
+                if !resp.status().is_success() {
+                    return Err(format!("GitHub API error: {}", resp.status()).into());
+                }
+
+                // Check scopes
+                let scope_warning = crate::github::check_token_scopes(resp.headers());
 
-        // Check scopes
-        self.scope_warning = crate::github::check_token_scopes(resp.headers());
+                let search_results: SearchResponse = resp.json().await?;
+                let orgs = search_results.items;
 
-        let search_results: SearchResponse = resp.json().await?;
-        self.results = search_results.items;
-        self.loading = false;
-        Ok(format!("Found {} organizations", self.results.len()))
+                let mut members = Vec::new();
+                if !parent_org.is_empty() {
+                    progress.set(format!("Fetching public members of {}", parent_org));
+                    members = crate::github::fetch_public_members(
+                        &client,
+                        token.expose_secret(),
+                        &parent_org,
+                    )
+                    .await?;
+                }
+
+                let snapshot = OrgSnapshot {
+                    orgs: orgs.clone(),
+                    members: members.clone(),
+                };
+                let changes = db
+                    .call(move |conn| {
+                        snapshots::save(conn, SnapshotKind::Orgs, &query, &snapshot)?;
+                        snapshots::diff_latest(conn, SnapshotKind::Orgs, &query)
+                    })
+                    .await?;
+                Ok(OrgFetch {
+                    orgs,
+                    members,
+                    scope_warning,
+                    changes,
+                })
+            },
+        ));
+        "Searching organizations".into()
     }
 }
 
 impl super::Tool for OrgResearchTool {
     fn name(&self) -> &'static str {
-        "Org Research"
+        Self::NAME
+    }
+
+    fn primary_result(&self) -> Option<String> {
+        if self.results.is_empty() {
+            return None;
+        }
+        Some(
+            self.results
+                .iter()
+                .map(|o| o.login.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    fn requires_github_token(&self) -> bool {
+        true
     }
 
     fn render(&self, f: &mut Frame, area: Rect) {
-        if self.loading {
-            let area = f.area();
-            let loading = Paragraph::new("Searching GitHub Organizations...")
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::ALL));
-            f.render_widget(loading, area);
+        if let Some(status) = self.fetch.as_ref().and_then(Task::status) {
+            components::render_task(f, area, &status);
             return;
         }
 
@@ -116,35 +247,44 @@ impl super::Tool for OrgResearchTool {
             .constraints(constraints)
             .split(area);
 
-        let parent_input = Paragraph::new(self.input.parent_org.as_str()).block(
+        self.input.parent_org.render(
+            f,
+            chunks[0],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Parent Org",
                     Style::default().fg(Color::Green),
                 ))),
+            self.input.focus.is(Field::ParentOrg),
         );
-        f.render_widget(parent_input, chunks[0]);
 
-        let search_input = Paragraph::new(self.input.search_term.as_str()).block(
+        self.input.search_term.render(
+            f,
+            chunks[1],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Search Term",
                     Style::default().fg(Color::Green),
                 ))),
+            self.input.focus.is(Field::SearchTerm),
         );
-        f.render_widget(search_input, chunks[1]);
-
-        let allow_toggle =
-            Paragraph::new(if self.input.allow_no_parent {
-                "Yes"
-            } else {
-                "No"
-            })
-            .block(Block::default().borders(Borders::ALL).title(Line::from(
-                Span::styled("Allow No Parent", Style::default().fg(Color::Green)),
-            )));
+
+        let allow_toggle = Paragraph::new(if self.input.allow_no_parent {
+            "[x] Yes (Space to toggle)"
+        } else {
+            "[ ] No (Space to toggle)"
+        })
+        .block(focus::border(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Allow No Parent",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.input.focus.is(Field::AllowNoParent),
+        ));
         f.render_widget(allow_toggle, chunks[2]);
 
         if let (Some(warning), Some(idx)) = (&self.scope_warning, warning_idx) {
@@ -158,63 +298,131 @@ impl super::Tool for OrgResearchTool {
             f.render_widget(warning_para, chunks[idx]);
         }
 
-        let results =
-            Paragraph::new(
-                self.results
-                    .iter()
-                    .map(|org| Line::from(org.login.clone()))
-                    .collect::<Vec<_>>(),
+        let title = if self.showing_changes() {
+            Span::styled(
+                "Changes Since Last Snapshot",
+                Style::default().fg(Color::Cyan),
             )
-            .block(Block::default().borders(Borders::ALL).title(Line::from(
-                Span::styled("Org Results", Style::default().fg(Color::Green)),
-            )));
-        f.render_widget(results, chunks[results_idx]);
+        } else {
+            Span::styled("Org Results", Style::default().fg(Color::Green))
+        };
+        self.result_list.render(
+            f,
+            chunks[results_idx],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(title)),
+            self.result_rows(),
+            self.input.focus.is(Field::Results),
+        );
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        self.result_list.selected_text(&self.result_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.result_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.input.focus.cycle(backward);
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.input.field()
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match action {
+                Action::ToggleOption => Ok(self.toggle_allow_no_parent()),
+                Action::ShowChanges => {
+                    if self.changes.is_none() {
+                        return Ok("No earlier snapshot to compare against".into());
+                    }
+                    self.show_changes = !self.show_changes;
+                    self.result_list.reset();
+                    Ok(if self.show_changes {
+                        "Showing changes since last snapshot".into()
+                    } else {
+                        "Showing organizations".into()
+                    })
+                }
+                Action::Export => {
+                    crate::redact::export_json("org_results.json", &self.results)?;
+                    Ok("Exported to org_results.json".into())
+                }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match id {
+                "parent" => self.input.parent_org.set_value(&argument),
+                _ => self.input.search_term.set_value(&argument),
+            }
+            self.show_changes = false;
+            Ok(self.fetch_orgs())
+        })
     }
 
     fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
         Box::pin(async move {
             match key.code {
-                KeyCode::Up => {
-                    self.input.current_field = self.input.current_field.saturating_sub(1);
-                    Ok("Switched field".into())
+                _ if self.input.focus.is(Field::Results)
+                    && self.result_list.handle_focused_key(&key) =>
+                {
+                    Ok(String::new())
                 }
-                KeyCode::Down => {
-                    self.input.current_field = (self.input.current_field + 1).min(2);
-                    Ok("Switched field".into())
+                _ if self.result_list.handle_key(&key) => Ok(String::new()),
+                KeyCode::Char(' ') if self.input.focus.is(Field::AllowNoParent) => {
+                    Ok(self.toggle_allow_no_parent())
                 }
-                KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.input.allow_no_parent = !self.input.allow_no_parent;
-                    Ok(format!("Allow No Parent: {}", self.input.allow_no_parent))
+                KeyCode::Enter => {
+                    self.show_changes = false;
+                    Ok(self.fetch_orgs())
                 }
-                KeyCode::Enter => self.fetch_orgs().await,
-                KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    serde_json::to_writer(
-                        std::fs::File::create("org_results.json")?,
-                        &self.results,
-                    )?;
-                    Ok("Exported to org_results.json".into())
-                }
-                KeyCode::Char(c) => {
-                    match self.input.current_field {
-                        0 => self.input.parent_org.push(c),
-                        1 => self.input.search_term.push(c),
-                        _ => {}
-                    }
+                _ if self
+                    .input
+                    .field()
+                    .is_some_and(|field| field.handle_key(&key)) =>
+                {
                     Ok("Input updated".into())
                 }
-                KeyCode::Backspace => match self.input.current_field {
-                    0 => {
-                        self.input.parent_org.pop();
-                        Ok("Removed character".into())
-                    }
-                    1 => {
-                        self.input.search_term.pop();
-                        Ok("Removed character".into())
-                    }
-                    _ => Ok("No action".into()),
-                },
                 _ => Ok(String::new()),
             }
         })
     }
+
+    fn on_task_finished(&mut self) -> Option<String> {
+        let fetched = match Task::take_finished(&mut self.fetch)? {
+            Ok(fetched) => fetched,
+            Err(e) => return Some(e),
+        };
+        self.scope_warning = fetched.scope_warning;
+        self.results = fetched.orgs;
+        self.members = fetched.members;
+        self.changes = fetched.changes;
+        self.result_list.reset();
+        Some(match &self.changes {
+            Some(changes) if changes.is_empty() => format!(
+                "Found {} organizations (no changes since last snapshot)",
+                self.results.len()
+            ),
+            Some(changes) => format!(
+                "Found {} organizations ({} changes since last snapshot, {} to view)",
+                self.results.len(),
+                changes.len(),
+                Action::ShowChanges.label()
+            ),
+            None => format!("Found {} organizations", self.results.len()),
+        })
+    }
 }
@@ -1,7 +1,14 @@
 use crate::db::Database;
+use crate::keymap::Action;
 use crate::models::github::Repository;
 use crate::secrets::Secrets;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::snapshots::{self, SnapshotDiff, SnapshotKind};
+use crate::tasks::{Task, Tasks};
+use crate::tools::Command;
+use crate::ui::components::{self, TextInput};
+use crate::ui::focus::Focus;
+use crate::ui::list::ScrollList;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
@@ -10,98 +17,168 @@ use reqwest::Client;
 use rusqlite::params;
 use serde_json;
 use std::error::Error;
-use std::sync::{Arc, Mutex};
 
 pub struct RepoExplorerTool {
-    input: String,
+    input: TextInput,
     results: Vec<Repository>,
-    db: Arc<Mutex<Database>>,
+    db: Database,
     client: Client,
     secrets: Secrets,
-    loading: bool,
+    tasks: Tasks,
+    fetch: Option<Task<RepoFetch>>,
     scope_warning: Option<String>,
+    changes: Option<SnapshotDiff>,
+    show_changes: bool,
+    result_list: ScrollList,
+    focus: Focus<Field>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Input,
+    Results,
+}
+
+const COMMANDS: &[Command] = &[
+    Command::new("fetch", "Fetch Repositories...").with_prompt("GitHub user or organization"),
+    Command::new("export_csv", "Export CSV"),
+];
+
+const CSV_HEADER: [&str; 9] = [
+    "name",
+    "stars",
+    "language",
+    "archived",
+    "visibility",
+    "created_at",
+    "updated_at",
+    "pushed_at",
+    "description",
+];
+
+fn csv_row(repo: &Repository) -> Vec<String> {
+    vec![
+        repo.name.clone(),
+        repo.stargazers_count.to_string(),
+        repo.language.clone().unwrap_or_default(),
+        repo.archived.to_string(),
+        repo.visibility.clone().unwrap_or_default(),
+        repo.created_at.clone(),
+        repo.updated_at.clone(),
+        repo.pushed_at.clone(),
+        repo.description.clone().unwrap_or_default(),
+    ]
+}
+
+/// What a background fetch hands back to the tool.
+struct RepoFetch {
+    repos: Vec<Repository>,
+    scope_warning: Option<String>,
+    changes: Option<SnapshotDiff>,
 }
 
 use secrecy::ExposeSecret;
 
 impl RepoExplorerTool {
+    const NAME: &'static str = "Repo Explorer";
+
     pub fn new(
-        db: Arc<Mutex<Database>>,
+        db: Database,
         client: &Client,
         secrets: &Secrets,
+        tasks: &Tasks,
     ) -> Result<Self, Box<dyn Error>> {
         Ok(RepoExplorerTool {
-            input: String::new(),
+            input: TextInput::new().with_history(),
             results: Vec::new(),
             db,
             client: client.clone(),
             secrets: secrets.clone(),
-            loading: false,
+            tasks: tasks.clone(),
+            fetch: None,
             scope_warning: None,
+            changes: None,
+            show_changes: false,
+            result_list: ScrollList::new(),
+            focus: Focus::new(&[Field::Input, Field::Results]),
         })
     }
 
-    async fn fetch_repos(&mut self) -> Result<String, Box<dyn Error>> {
-        self.loading = true;
-        let url = format!("https://api.github.com/users/{}/repos", self.input);
-        let resp = self
-            .client
-            .get(&url)
-            .header(
-                "Authorization",
-                format!("token {}", self.secrets.github_token.expose_secret()),
-            )
-            .send()
-            .await?;
-
-        if !resp.status().is_success() {
-            self.loading = false;
-            return Err(format!("GitHub API error: {}", resp.status()).into());
-        }
+    fn showing_changes(&self) -> bool {
+        self.show_changes && self.changes.is_some()
+    }
 
-        // Check scopes
-        self.scope_warning = crate::github::check_token_scopes(resp.headers());
-
-        let mut repos: Vec<Repository> = resp.json().await?;
-
-        // Fetch releases for each repo (limited to first 5 for performance)
-        for repo in repos.iter_mut().take(5) {
-            let release_url = format!(
-                "https://api.github.com/repos/{}/{}/releases",
-                self.input, repo.name
-            );
-            let release_resp = self
-                .client
-                .get(&release_url)
-                .header(
-                    "Authorization",
-                    format!("token {}", self.secrets.github_token.expose_secret()),
-                )
-                .send()
-                .await?;
-            if release_resp.status().is_success() {
-                repo.releases = release_resp.json().await?;
-            }
+    /// The repositories, or the changes since the last snapshot while those
+    /// are shown.
+    fn result_rows(&self) -> Vec<Line<'_>> {
+        match (&self.changes, self.show_changes) {
+            (Some(changes), true) => changes.lines().into_iter().map(Line::from).collect(),
+            _ => self
+                .results
+                .iter()
+                .map(|repo| Line::from(repo.name.as_str()))
+                .collect(),
         }
+    }
 
-        self.results = repos;
-        self.loading = false;
-        Ok(format!("Fetched {} repositories", self.results.len()))
+    /// Starts fetching in the background; the result arrives through
+    /// `on_task_finished`.
+    fn fetch_repos(&mut self) -> String {
+        let client = self.client.clone();
+        let token = self.secrets.token_for(Self::NAME);
+        self.input.commit();
+        let owner = self.input.value().to_string();
+        let db = self.db.clone();
+        self.fetch = Some(self.tasks.spawn(
+            Self::NAME,
+            "Fetching Repositories",
+            move |progress| async move {
+                let (repos, scope_warning) =
+                    crate::github::fetch_repos(&client, token.expose_secret(), &owner).await?;
+                progress.set(format!("Saving a snapshot of {} repositories", repos.len()));
+                let snapshot = repos.clone();
+                let changes = db
+                    .call(move |conn| {
+                        snapshots::save(conn, SnapshotKind::Repos, &owner, &snapshot)?;
+                        snapshots::diff_latest(conn, SnapshotKind::Repos, &owner)
+                    })
+                    .await?;
+                Ok(RepoFetch {
+                    repos,
+                    scope_warning,
+                    changes,
+                })
+            },
+        ));
+        "Fetching repositories".into()
     }
 }
 
 impl super::Tool for RepoExplorerTool {
     fn name(&self) -> &'static str {
-        "Repo Explorer"
+        Self::NAME
+    }
+
+    fn primary_result(&self) -> Option<String> {
+        if self.results.is_empty() {
+            return None;
+        }
+        Some(
+            self.results
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    fn requires_github_token(&self) -> bool {
+        true
     }
 
     fn render(&self, f: &mut Frame, area: Rect) {
-        if self.loading {
-            let area = f.area();
-            let loading = Paragraph::new("Fetching Repositories...")
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::ALL));
-            f.render_widget(loading, area);
+        if let Some(status) = self.fetch.as_ref().and_then(Task::status) {
+            components::render_task(f, area, &status);
             return;
         }
 
@@ -122,15 +199,17 @@ impl super::Tool for RepoExplorerTool {
             .constraints(constraints)
             .split(area);
 
-        let input = Paragraph::new(self.input.as_str()).block(
+        self.input.render(
+            f,
+            chunks[0],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Repo Input",
                     Style::default().fg(Color::Green),
                 ))),
+            self.focus.is(Field::Input),
         );
-        f.render_widget(input, chunks[0]);
 
         if let (Some(warning), Some(idx)) = (&self.scope_warning, warning_idx) {
             let warning_para = Paragraph::new(warning.as_str())
@@ -143,43 +222,128 @@ impl super::Tool for RepoExplorerTool {
             f.render_widget(warning_para, chunks[idx]);
         }
 
-        let results =
-            Paragraph::new(
-                self.results
-                    .iter()
-                    .map(|repo| Line::from(repo.name.clone()))
-                    .collect::<Vec<_>>(),
+        let title = if self.showing_changes() {
+            Span::styled(
+                "Changes Since Last Snapshot",
+                Style::default().fg(Color::Cyan),
             )
-            .block(Block::default().borders(Borders::ALL).title(Line::from(
-                Span::styled("Repo Results", Style::default().fg(Color::Green)),
-            )));
-        f.render_widget(results, chunks[results_idx]);
+        } else {
+            Span::styled("Repo Results", Style::default().fg(Color::Green))
+        };
+        self.result_list.render(
+            f,
+            chunks[results_idx],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(title)),
+            self.result_rows(),
+            self.focus.is(Field::Results),
+        );
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        self.result_list.selected_text(&self.result_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.result_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.focus.cycle(backward);
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.focus.is(Field::Input).then_some(&mut self.input)
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match action {
+                Action::ShowChanges => {
+                    if self.changes.is_none() {
+                        return Ok("No earlier snapshot to compare against".into());
+                    }
+                    self.show_changes = !self.show_changes;
+                    self.result_list.reset();
+                    Ok(if self.show_changes {
+                        "Showing changes since last snapshot".into()
+                    } else {
+                        "Showing repositories".into()
+                    })
+                }
+                Action::Export => {
+                    crate::redact::export_json("repo_results.json", &self.results)?;
+                    Ok("Exported to repo_results.json".into())
+                }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            if id == "fetch" {
+                self.input.set_value(&argument);
+                self.show_changes = false;
+                return Ok(self.fetch_repos());
+            }
+            crate::redact::export_csv(
+                "repo_results.csv",
+                &CSV_HEADER,
+                &self.results.iter().map(csv_row).collect::<Vec<_>>(),
+            )?;
+            Ok("Exported to repo_results.csv".into())
+        })
     }
 
     fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
         Box::pin(async move {
             match key.code {
-                KeyCode::Enter => self.fetch_repos().await,
-                KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    serde_json::to_writer(
-                        std::fs::File::create("repo_results.json")?,
-                        &self.results,
-                    )?;
-                    Ok("Exported to repo_results.json".into())
+                _ if self.focus.is(Field::Results) && self.result_list.handle_focused_key(&key) => {
+                    Ok(String::new())
                 }
-                KeyCode::Char(c) => {
-                    self.input.push(c);
-                    Ok("Input updated".into())
+                _ if self.result_list.handle_key(&key) => Ok(String::new()),
+                KeyCode::Enter => {
+                    self.show_changes = false;
+                    Ok(self.fetch_repos())
                 }
-                KeyCode::Backspace => {
-                    self.input.pop();
-                    Ok("Removed character".into())
+                _ if self.focus.is(Field::Input) && self.input.handle_key(&key) => {
+                    Ok("Input updated".into())
                 }
                 _ => Ok(String::new()),
             }
         })
     }
 
+    fn on_task_finished(&mut self) -> Option<String> {
+        let fetched = match Task::take_finished(&mut self.fetch)? {
+            Ok(fetched) => fetched,
+            Err(e) => return Some(e),
+        };
+        self.scope_warning = fetched.scope_warning;
+        self.results = fetched.repos;
+        self.changes = fetched.changes;
+        self.result_list.reset();
+        Some(match &self.changes {
+            Some(changes) if changes.is_empty() => format!(
+                "Fetched {} repositories (no changes since last snapshot)",
+                self.results.len()
+            ),
+            Some(changes) => format!(
+                "Fetched {} repositories ({} changes since last snapshot, {} to view)",
+                self.results.len(),
+                changes.len(),
+                Action::ShowChanges.label()
+            ),
+            None => format!("Fetched {} repositories", self.results.len()),
+        })
+    }
+
     fn as_persistable(&self) -> Option<&dyn super::Persistable> {
         Some(self)
     }
@@ -187,13 +351,25 @@ impl super::Tool for RepoExplorerTool {
 
 impl super::Persistable for RepoExplorerTool {
     fn save_cache(&self) -> Result<(), Box<dyn Error>> {
-        let mut db = self.db.lock().unwrap();
-        for repo in &self.results {
-            db.conn().execute(
-                "INSERT OR REPLACE INTO repos (username, name, data) VALUES (?1, ?2, ?3)",
-                params![&self.input, &repo.name, serde_json::to_string(&repo)?],
-            )?;
-        }
-        Ok(())
+        let username = self.input.value().to_string();
+        let rows = self
+            .results
+            .iter()
+            .map(|repo| Ok((repo.name.clone(), serde_json::to_string(repo)?)))
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        self.db.call_blocking(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR REPLACE INTO repos (username, name, data, fetched_at) VALUES (?1, ?2, ?3, ?4)",
+                )?;
+                let now = snapshots::now_unix();
+                for (name, data) in &rows {
+                    stmt.execute(params![&username, name, data, now])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
     }
 }
@@ -1,190 +1,523 @@
+use crate::config::Config;
+use crate::db::Database;
+use crate::github::TokenKind;
+use crate::keymap::Action;
 use crate::secrets::Secrets;
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::snapshots::{format_timestamp, now_unix};
+use crate::tasks::{Task, Tasks};
+use crate::token_history::{self, Inspection};
+use crate::ui::components;
+use crate::ui::list::ScrollList;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, LineGauge, Paragraph, Sparkline},
 };
 use reqwest::Client;
+use secrecy::ExposeSecret;
+use std::collections::VecDeque;
 use std::error::Error;
+use std::time::{Duration, Instant};
 
 use std::sync::{Arc, Mutex};
 
 pub struct TokenInspectorTool {
+    db: Database,
     client: Client,
     secrets: Secrets,
-    loading: Arc<Mutex<bool>>,
+    expiry_warning_days: u32,
+    tasks: Tasks,
+    inspection: Option<Task<String>>,
+    /// False while the inspection started on launch runs, whose success
+    /// message would otherwise replace the welcome message.
+    announce: bool,
     results: Arc<Mutex<Option<TokenInfo>>>,
+    history: Arc<Mutex<Vec<Inspection>>>,
+    rate_samples: Arc<Mutex<VecDeque<u64>>>,
+    /// When the rate limits were last sampled, and the sample in flight.
+    last_sample: Instant,
+    sampler: Option<tokio::task::JoinHandle<()>>,
     error: Arc<Mutex<Option<String>>>,
+    info_list: ScrollList,
 }
 
 #[derive(Debug, Default, Clone)]
 struct TokenInfo {
+    kind: TokenKind,
     owner_login: String,
     owner_name: Option<String>,
     scopes: String,
     scope_warning: Option<String>,
-    rate_limit_limit: u32,
-    rate_limit_remaining: u32,
-    rate_limit_reset: i64,
+    expires_at: Option<i64>,
+    repo_access: Option<RepoAccess>,
+    rotation_warning: Option<String>,
+    /// Why the inspection could not be recorded in the history, if it failed.
+    history_warning: Option<String>,
+    rate_limits: Vec<RateResource>,
+}
+
+/// One entry of `resources` in the `/rate_limit` response.
+#[derive(Debug, Clone, PartialEq)]
+struct RateResource {
+    name: String,
+    limit: u64,
+    used: u64,
+    remaining: u64,
+    reset: i64,
+}
+
+/// Repositories a fine-grained or GitHub App token can reach, and which read
+/// permissions were confirmed by probing the first few of them.
+#[derive(Debug, Default, Clone)]
+struct RepoAccess {
+    total: usize,
+    matrix: Vec<(String, Vec<ProbeResult>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProbeResult {
+    Granted,
+    Denied,
+    Unknown,
+}
+
+/// Read-only endpoints probed per repository: column label, the fine-grained
+/// permission they require, and a path relative to `/repos/{owner}/{repo}`.
+const PERMISSION_PROBES: &[(&str, &str, &str)] = &[
+    ("Meta", "metadata", ""),
+    ("Code", "contents", "/commits?per_page=1"),
+    ("Issu", "issues", "/issues?per_page=1"),
+    ("Pull", "pull_requests", "/pulls?per_page=1"),
+    ("Actn", "actions", "/actions/runs?per_page=1"),
+    ("Hook", "webhooks", "/hooks"),
+    ("Secr", "secrets", "/actions/secrets"),
+    ("Dpbt", "dependabot_alerts", "/dependabot/alerts?per_page=1"),
+];
+
+/// Maximum number of repositories probed for the permission matrix.
+const MAX_PROBED_REPOS: usize = 5;
+
+/// How often the remaining rate-limit budget is sampled for the sparkline.
+/// `/rate_limit` itself does not count against any limit.
+const RATE_SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of samples kept for the sparkline.
+const MAX_RATE_SAMPLES: usize = 240;
+
+/// Parses every entry of `resources` in a `/rate_limit` response, with
+/// `core` first and the rest in alphabetical order.
+fn parse_rate_limits(json: &serde_json::Value) -> Vec<RateResource> {
+    let mut resources: Vec<RateResource> = json["resources"]
+        .as_object()
+        .map(|resources| {
+            resources
+                .iter()
+                .map(|(name, r)| RateResource {
+                    name: name.clone(),
+                    limit: r["limit"].as_u64().unwrap_or(0),
+                    used: r["used"].as_u64().unwrap_or(0),
+                    remaining: r["remaining"].as_u64().unwrap_or(0),
+                    reset: r["reset"].as_i64().unwrap_or(0),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    resources.sort_by(|a, b| (a.name != "core", &a.name).cmp(&(b.name != "core", &b.name)));
+    resources
+}
+
+fn format_countdown(secs: i64) -> String {
+    match secs {
+        s if s <= 0 => "now".to_string(),
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}m {:02}s", s / 60, s % 60),
+    }
 }
 
 impl TokenInspectorTool {
-    pub fn new(client: &Client, secrets: &Secrets) -> Self {
-        let tool = TokenInspectorTool {
+    const NAME: &'static str = "Token Inspector";
+
+    pub fn new(
+        db: Database,
+        client: &Client,
+        secrets: &Secrets,
+        config: &Config,
+        tasks: &Tasks,
+    ) -> Self {
+        let mut tool = TokenInspectorTool {
+            db,
             client: client.clone(),
             secrets: secrets.clone(),
-            loading: Arc::new(Mutex::new(false)),
+            expiry_warning_days: config.token_expiry_warning_days,
+            tasks: tasks.clone(),
+            inspection: None,
+            announce: false,
             results: Arc::new(Mutex::new(None)),
+            history: Arc::new(Mutex::new(Vec::new())),
+            rate_samples: Arc::new(Mutex::new(VecDeque::new())),
+            last_sample: Instant::now(),
+            sampler: None,
             error: Arc::new(Mutex::new(None)),
+            info_list: ScrollList::new(),
         };
 
-        let mut tool_clone = tool.clone_state();
-        tokio::spawn(async move {
-            let _ = tool_clone.inspect_token().await;
-        });
-
+        tool.start_inspection();
         tool
     }
 
     fn clone_state(&self) -> Self {
         TokenInspectorTool {
+            db: self.db.clone(),
             client: self.client.clone(),
             secrets: self.secrets.clone(),
-            loading: Arc::clone(&self.loading),
+            expiry_warning_days: self.expiry_warning_days,
+            tasks: self.tasks.clone(),
+            inspection: None,
+            announce: false,
             results: Arc::clone(&self.results),
+            history: Arc::clone(&self.history),
+            rate_samples: Arc::clone(&self.rate_samples),
+            last_sample: self.last_sample,
+            sampler: None,
             error: Arc::clone(&self.error),
+            info_list: ScrollList::new(),
         }
     }
 
+    /// Inspects the token again on request, announcing the result.
+    fn reinspect(&mut self) -> String {
+        self.announce = true;
+        self.start_inspection();
+        "Inspecting token".into()
+    }
+
+    fn start_inspection(&mut self) {
+        let mut tool_clone = self.clone_state();
+        self.inspection = Some(
+            self.tasks
+                .spawn(Self::NAME, "Inspecting Token", |_| async move {
+                    tool_clone.inspect_token().await
+                }),
+        );
+    }
+
     async fn inspect_token(&mut self) -> Result<String, Box<dyn Error>> {
-        use secrecy::ExposeSecret;
-        let token = self.secrets.github_token.expose_secret();
+        let token = self.secrets.token_for(Self::NAME);
+        let token = token.expose_secret();
         if token.is_empty() {
             *self.error.lock().unwrap() =
                 Some("No GitHub token configured. Please check your .env file.".into());
             return Ok("Missing token".into());
         }
 
-        *self.loading.lock().unwrap() = true;
         *self.error.lock().unwrap() = None;
 
-        // Fetch User Info to get scopes and owner details
-        let user_url = "https://api.github.com/user";
-        let user_resp = self
-            .client
-            .get(user_url)
-            .header("Authorization", format!("Bearer {}", token))
-            .send()
-            .await?;
+        // Scoped so the non-`Send` error is gone before the next await.
+        let mut info = {
+            let result = self.fetch_token_info(token).await;
+            match result {
+                Ok(info) => info,
+                Err(e) => {
+                    *self.error.lock().unwrap() = Some(e.to_string());
+                    return Err(e);
+                }
+            }
+        };
+        // A failed history write should not throw away the inspection.
+        let status = match self.record_inspection(token, &info).await {
+            Ok(warning) => {
+                info.rotation_warning = warning;
+                "Token inspected successfully".to_string()
+            }
+            Err(e) => {
+                info.history_warning = Some(format!("Inspection not recorded: {}", e));
+                "Token inspected, but the history could not be updated".to_string()
+            }
+        };
+        self.push_rate_sample(&info.rate_limits);
+        *self.results.lock().unwrap() = Some(info);
+        Ok(status)
+    }
 
-        if !user_resp.status().is_success() {
-            *self.loading.lock().unwrap() = false;
-            let err_msg = format!("GitHub API error (User): {}", user_resp.status());
-            *self.error.lock().unwrap() = Some(err_msg.clone());
-            return Err(err_msg.into());
-        }
+    async fn fetch_token_info(&self, token: &str) -> Result<TokenInfo, Box<dyn Error>> {
+        let kind = TokenKind::from_token(token);
 
-        let scope_warning = crate::github::check_token_scopes(user_resp.headers());
+        // Installation tokens act as the app, not a user, so `/user` is
+        // off-limits; `/rate_limit` still reports the expiration header.
+        let (owner_login, owner_name, headers) = if kind == TokenKind::Installation {
+            let resp = self
+                .get(
+                    token,
+                    "https://api.github.com/installation/repositories?per_page=1",
+                )
+                .await?;
+            if !resp.status().is_success() {
+                return Err(format!("GitHub API error (Installation): {}", resp.status()).into());
+            }
+            (
+                "GitHub App installation".to_string(),
+                None,
+                resp.headers().clone(),
+            )
+        } else {
+            // Fetch User Info to get scopes and owner details
+            let user_resp = self.get(token, "https://api.github.com/user").await?;
+            if !user_resp.status().is_success() {
+                return Err(format!("GitHub API error (User): {}", user_resp.status()).into());
+            }
+            let headers = user_resp.headers().clone();
+            let user_json: serde_json::Value = user_resp.json().await?;
+            (
+                user_json["login"].as_str().unwrap_or("Unknown").to_string(),
+                user_json["name"].as_str().map(|s| s.to_string()),
+                headers,
+            )
+        };
 
-        let scopes = user_resp
-            .headers()
-            .get("x-oauth-scopes")
+        let scope_warning = crate::github::check_token_scopes(&headers);
+        let scopes = match headers.get("x-oauth-scopes").and_then(|h| h.to_str().ok()) {
+            Some(scopes) if !scopes.is_empty() => scopes.to_string(),
+            _ if !kind.reports_scopes() => "Per-repository permissions (see matrix)".to_string(),
+            _ => "No scopes returned".to_string(),
+        };
+        let expires_at = headers
+            .get("github-authentication-token-expiration")
             .and_then(|h| h.to_str().ok())
-            .unwrap_or("No scopes returned")
-            .to_string();
-
-        let user_json: serde_json::Value = user_resp.json().await?;
-        let owner_login = user_json["login"].as_str().unwrap_or("Unknown").to_string();
-        let owner_name = user_json["name"].as_str().map(|s| s.to_string());
-
-        // Fetch Rate Limits
-        let rate_url = "https://api.github.com/rate_limit";
-        let rate_resp = self
-            .client
-            .get(rate_url)
-            .header("Authorization", format!("Bearer {}", token))
-            .send()
-            .await?;
+            .and_then(crate::github::parse_token_expiration);
 
-        if !rate_resp.status().is_success() {
-            *self.loading.lock().unwrap() = false;
-            let err_msg = format!("GitHub API error (Rate Limit): {}", rate_resp.status());
-            *self.error.lock().unwrap() = Some(err_msg.clone());
-            return Err(err_msg.into());
-        }
+        let repo_access = if kind.reports_scopes() {
+            None
+        } else {
+            Some(self.probe_repo_access(token, kind).await?)
+        };
 
-        let rate_json: serde_json::Value = rate_resp.json().await?;
-        let core_limit = rate_json["resources"]["core"]["limit"]
-            .as_u64()
-            .unwrap_or(0) as u32;
-        let core_remaining = rate_json["resources"]["core"]["remaining"]
-            .as_u64()
-            .unwrap_or(0) as u32;
-        let core_reset = rate_json["resources"]["core"]["reset"]
-            .as_i64()
-            .unwrap_or(0);
+        let rate_limits = self.fetch_rate_limits(token).await?;
 
-        *self.results.lock().unwrap() = Some(TokenInfo {
+        Ok(TokenInfo {
+            kind,
             owner_login,
             owner_name,
             scopes,
             scope_warning,
-            rate_limit_limit: core_limit,
-            rate_limit_remaining: core_remaining,
-            rate_limit_reset: core_reset,
-        });
+            expires_at,
+            repo_access,
+            rotation_warning: None,
+            history_warning: None,
+            rate_limits,
+        })
+    }
 
-        *self.loading.lock().unwrap() = false;
-        Ok("Token inspected successfully".into())
+    async fn fetch_rate_limits(&self, token: &str) -> Result<Vec<RateResource>, Box<dyn Error>> {
+        let rate_resp = self.get(token, "https://api.github.com/rate_limit").await?;
+        if !rate_resp.status().is_success() {
+            return Err(format!("GitHub API error (Rate Limit): {}", rate_resp.status()).into());
+        }
+        let rate_json: serde_json::Value = rate_resp.json().await?;
+        Ok(parse_rate_limits(&rate_json))
     }
-}
 
-impl super::Tool for TokenInspectorTool {
-    fn name(&self) -> &'static str {
-        "Token Inspector"
+    /// Refreshes the rate limits shown for the last inspection and records
+    /// the remaining core budget for the sparkline.
+    async fn sample_rate_limits(&self) -> Result<(), Box<dyn Error>> {
+        let token = self.secrets.token_for(Self::NAME);
+        let rate_limits = self.fetch_rate_limits(token.expose_secret()).await?;
+        self.push_rate_sample(&rate_limits);
+        if let Some(info) = self.results.lock().unwrap().as_mut() {
+            info.rate_limits = rate_limits;
+        }
+        Ok(())
     }
 
-    fn render(&self, f: &mut Frame, area: Rect) {
-        let is_loading = *self.loading.lock().unwrap();
-        if is_loading {
-            let loading = Paragraph::new("Inspecting token...")
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::ALL));
-            f.render_widget(loading, area);
-            return;
+    fn push_rate_sample(&self, rate_limits: &[RateResource]) {
+        if let Some(core) = rate_limits.iter().find(|r| r.name == "core") {
+            let mut samples = self.rate_samples.lock().unwrap();
+            samples.push_back(core.remaining);
+            while samples.len() > MAX_RATE_SAMPLES {
+                samples.pop_front();
+            }
         }
+    }
 
-        let error_lock = self.error.lock().unwrap();
-        if let Some(err) = &*error_lock {
-            let error_para = Paragraph::new(err.as_str())
-                .style(Style::default().fg(Color::Red))
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::ALL).title("Error"));
-            f.render_widget(error_para, area);
-            return;
+    /// Stores the inspection in the history table and returns a warning if
+    /// the token was rotated to one with broader access.
+    async fn record_inspection(
+        &self,
+        token: &str,
+        info: &TokenInfo,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let scopes = match &info.repo_access {
+            Some(access) => PERMISSION_PROBES
+                .iter()
+                .enumerate()
+                .filter(|(col, _)| {
+                    access
+                        .matrix
+                        .iter()
+                        .any(|(_, results)| results[*col] == ProbeResult::Granted)
+                })
+                .map(|(_, (_, permission, _))| format!("{}:read", permission))
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => info.scopes.clone(),
+        };
+        let inspection = Inspection {
+            inspected_at: now_unix(),
+            owner: info.owner_login.clone(),
+            kind: info.kind.label().to_string(),
+            scopes,
+            expires_at: info.expires_at,
+            fingerprint: token_history::fingerprint(token),
+        };
+
+        let recorded = inspection.clone();
+        let (previous, history) = self
+            .db
+            .call(move |conn| {
+                let previous = token_history::record(conn, &recorded)?;
+                Ok((previous, token_history::recent(conn, None, 5)?))
+            })
+            .await?;
+        *self.history.lock().unwrap() = history;
+        Ok(previous.and_then(|prev| token_history::rotation_warning(&prev, &inspection)))
+    }
+
+    /// Days until the token expires, or `None` if it does not expire.
+    fn days_remaining(expires_at: Option<i64>) -> Option<i64> {
+        expires_at.map(|ts| (ts - now_unix()).div_euclid(86_400))
+    }
+
+    async fn get(&self, token: &str, url: &str) -> Result<reqwest::Response, reqwest::Error> {
+        self.client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+    }
+
+    /// Lists the repositories the token can reach and probes read access to
+    /// each permission on the first few of them.
+    async fn probe_repo_access(
+        &self,
+        token: &str,
+        kind: TokenKind,
+    ) -> Result<RepoAccess, Box<dyn Error>> {
+        // Installations report their total, so one page is enough to probe;
+        // for users, every page is followed to count them.
+        let (repos, total): (Vec<String>, usize) = if kind == TokenKind::Installation {
+            let resp = self
+                .get(
+                    token,
+                    "https://api.github.com/installation/repositories?per_page=100",
+                )
+                .await?;
+            let json: serde_json::Value = resp.json().await?;
+            let repos = json["repositories"]
+                .as_array()
+                .map(|repos| full_names(repos))
+                .unwrap_or_default();
+            let total = json["total_count"]
+                .as_u64()
+                .map_or(repos.len(), |total| total as usize);
+            (repos, total)
+        } else {
+            let (repos, _) = crate::github::get_all_pages::<serde_json::Value>(
+                &self.client,
+                token,
+                "https://api.github.com/user/repos",
+            )
+            .await?;
+            let repos = full_names(&repos);
+            let total = repos.len();
+            (repos, total)
+        };
+
+        let mut probes = tokio::task::JoinSet::new();
+        for (row, repo) in repos.iter().take(MAX_PROBED_REPOS).enumerate() {
+            for (col, (_, _, path)) in PERMISSION_PROBES.iter().enumerate() {
+                let client = self.client.clone();
+                let url = format!("https://api.github.com/repos/{}{}", repo, path);
+                let auth = format!("Bearer {}", token);
+                probes.spawn(async move {
+                    let result = match client.get(&url).header("Authorization", auth).send().await {
+                        Ok(resp) if resp.status().is_success() => ProbeResult::Granted,
+                        Ok(resp) if matches!(resp.status().as_u16(), 403 | 404) => {
+                            ProbeResult::Denied
+                        }
+                        _ => ProbeResult::Unknown,
+                    };
+                    (row, col, result)
+                });
+            }
         }
-        drop(error_lock);
 
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0)])
-            .split(area);
+        let mut matrix: Vec<(String, Vec<ProbeResult>)> = repos
+            .iter()
+            .take(MAX_PROBED_REPOS)
+            .map(|repo| {
+                (
+                    repo.clone(),
+                    vec![ProbeResult::Unknown; PERMISSION_PROBES.len()],
+                )
+            })
+            .collect();
+        while let Some(probe) = probes.join_next().await {
+            if let Ok((row, col, result)) = probe {
+                matrix[row].1[col] = result;
+            }
+        }
 
-        let cta = Paragraph::new("Press Enter to re-inspect the current GitHub Token.")
-            .style(Style::default().fg(Color::Yellow))
-            .block(Block::default().borders(Borders::ALL));
-        f.render_widget(cta, chunks[0]);
+        Ok(RepoAccess { total, matrix })
+    }
+}
 
+impl TokenInspectorTool {
+    /// The token details, repository access matrix and inspection history.
+    fn info_rows(&self) -> Vec<Line<'static>> {
         let mut lines = vec![];
-        let results_lock = self.results.lock().unwrap();
-        if let Some(info) = &*results_lock {
+        let results = self.results.lock().unwrap();
+        if let Some(info) = &*results {
+            if let Some((name, source)) = self.secrets.credential_for(Self::NAME) {
+                lines.push(Line::from(vec![
+                    Span::styled("Credential:  ", Style::default().bold()),
+                    Span::raw(format!("{} (from {})", name, source)),
+                ]));
+            }
+            lines.push(Line::from(vec![
+                Span::styled("Token Type:  ", Style::default().bold()),
+                Span::raw(info.kind.label()),
+            ]));
+            let expiry = match (info.expires_at, Self::days_remaining(info.expires_at)) {
+                (Some(ts), Some(days)) if days < 0 => Span::styled(
+                    format!("{} (expired)", format_timestamp(ts)),
+                    Style::default().fg(Color::Red).bold(),
+                ),
+                (Some(ts), Some(days)) => Span::styled(
+                    format!("{} ({} days remaining)", format_timestamp(ts), days),
+                    if days <= self.expiry_warning_days as i64 {
+                        Style::default().fg(Color::Yellow).bold()
+                    } else {
+                        Style::default().fg(Color::Green)
+                    },
+                ),
+                _ => Span::raw("No expiration reported"),
+            };
+            lines.push(Line::from(vec![
+                Span::styled("Expires:     ", Style::default().bold()),
+                expiry,
+            ]));
+            if let Some(warning) = &info.rotation_warning {
+                lines.push(Line::from(vec![
+                    Span::raw("             "),
+                    Span::styled(warning.clone(), Style::default().fg(Color::Red).bold()),
+                ]));
+            }
             lines.push(Line::from(vec![
                 Span::styled("Owner Login: ", Style::default().bold()),
-                Span::raw(&info.owner_login),
+                Span::raw(info.owner_login.clone()),
             ]));
             lines.push(Line::from(vec![
                 Span::styled("Owner Name:  ", Style::default().bold()),
-                Span::raw(info.owner_name.as_deref().unwrap_or("None")),
+                Span::raw(info.owner_name.clone().unwrap_or_else(|| "None".into())),
             ]));
             lines.push(Line::from(""));
             let scope_style = if info.scope_warning.is_some() {
@@ -195,58 +528,333 @@ impl super::Tool for TokenInspectorTool {
 
             lines.push(Line::from(vec![
                 Span::styled("Scopes:      ", Style::default().bold()),
-                Span::styled(&info.scopes, scope_style),
+                Span::styled(info.scopes.clone(), scope_style),
             ]));
             if let Some(warning) = &info.scope_warning {
                 lines.push(Line::from(vec![
                     Span::raw("             "),
-                    Span::styled(warning, Style::default().fg(Color::Red)),
+                    Span::styled(warning.clone(), Style::default().fg(Color::Red)),
+                ]));
+            }
+            if let Some(access) = &info.repo_access {
+                lines.push(Line::from(""));
+                lines.push(Line::from(vec![
+                    Span::styled("Repositories:", Style::default().bold()),
+                    Span::raw(format!(
+                        " {} accessible (read access probed on the first {})",
+                        access.total,
+                        access.matrix.len()
+                    )),
                 ]));
+                let mut header = vec![Span::raw(format!("  {:<32}", "Repository"))];
+                header.extend(PERMISSION_PROBES.iter().map(|(name, _, _)| {
+                    Span::styled(format!("{:<6}", name), Style::default().bold())
+                }));
+                lines.push(Line::from(header));
+                for (repo, results) in &access.matrix {
+                    let mut row = vec![Span::raw(format!("  {:<32}", repo))];
+                    row.extend(results.iter().map(|result| match result {
+                        ProbeResult::Granted => {
+                            Span::styled(format!("{:<6}", "yes"), Style::default().fg(Color::Green))
+                        }
+                        ProbeResult::Denied => Span::styled(
+                            format!("{:<6}", "no"),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        ProbeResult::Unknown => {
+                            Span::styled(format!("{:<6}", "?"), Style::default().fg(Color::Yellow))
+                        }
+                    }));
+                    lines.push(Line::from(row));
+                }
             }
+        } else {
+            lines.push(Line::from("No token info loaded yet."));
+        }
+
+        let history_warning = results
+            .as_ref()
+            .and_then(|info| info.history_warning.clone());
+        if let Some(warning) = history_warning {
             lines.push(Line::from(""));
-            lines.push(Line::from(vec![
-                Span::styled("Rate Limit:  ", Style::default().bold()),
-                Span::raw(format!(
-                    "{}/{}",
-                    info.rate_limit_remaining, info.rate_limit_limit
-                )),
-            ]));
+            lines.push(Line::from(Span::styled(
+                warning,
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        let history = self.history.lock().unwrap();
+        if !history.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Inspection History:",
+                Style::default().bold(),
+            )));
+            for entry in history.iter() {
+                lines.push(Line::from(format!(
+                    "  {}  {}  {}  [{}]  expires {}",
+                    format_timestamp(entry.inspected_at),
+                    entry.owner,
+                    entry.kind,
+                    entry.fingerprint,
+                    entry
+                        .expires_at
+                        .map(format_timestamp)
+                        .unwrap_or_else(|| "never".to_string())
+                )));
+            }
+        }
+        lines
+    }
 
-            use std::time::{SystemTime, UNIX_EPOCH};
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64;
-            let reset_in = info.rate_limit_reset - now;
-            let reset_str = if reset_in > 0 {
-                format!("{} minutes", reset_in / 60)
+    fn render_rate_limits(&self, f: &mut Frame, area: Rect, rate_limits: &[RateResource]) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(Span::styled(
+                "Rate Limits",
+                Style::default().fg(Color::Cyan),
+            )));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let mut constraints = vec![Constraint::Length(1); rate_limits.len()];
+        constraints.push(Constraint::Min(0));
+        constraints.push(Constraint::Length(5));
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(inner);
+
+        let now = now_unix();
+        for (resource, row) in rate_limits.iter().zip(rows.iter()) {
+            let ratio = if resource.limit > 0 {
+                resource.remaining as f64 / resource.limit as f64
             } else {
-                "Resetting now...".to_string()
+                0.0
             };
+            let color = match ratio {
+                r if r < 0.1 => Color::Red,
+                r if r < 0.5 => Color::Yellow,
+                _ => Color::Green,
+            };
+            let gauge = LineGauge::default()
+                .ratio(ratio.clamp(0.0, 1.0))
+                .filled_style(Style::default().fg(color))
+                .label(format!(
+                    "{:<22} {:>5}/{:<5} used {:<5} reset {}",
+                    resource.name,
+                    resource.remaining,
+                    resource.limit,
+                    resource.used,
+                    format_countdown(resource.reset - now)
+                ));
+            f.render_widget(gauge, *row);
+        }
 
-            lines.push(Line::from(vec![
-                Span::styled("Reset In:    ", Style::default().bold()),
-                Span::raw(reset_str),
-            ]));
-        } else {
-            lines.push(Line::from("No token info loaded yet."));
+        let samples = self.rate_samples.lock().unwrap();
+        let data: Vec<u64> = samples.iter().copied().collect();
+        let max = rate_limits
+            .iter()
+            .find(|r| r.name == "core")
+            .map(|r| r.limit)
+            .unwrap_or(0);
+        let sparkline = Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .title(format!("Core budget this session ({} samples)", data.len())),
+            )
+            .data(&data)
+            .max(max.max(1))
+            .style(Style::default().fg(Color::Cyan));
+        f.render_widget(sparkline, rows[rows.len() - 1]);
+    }
+}
+
+fn full_names(repos: &[serde_json::Value]) -> Vec<String> {
+    repos
+        .iter()
+        .filter_map(|r| r["full_name"].as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+impl super::Tool for TokenInspectorTool {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn primary_result(&self) -> Option<String> {
+        let results = self.results.lock().unwrap();
+        let info = results.as_ref()?;
+        Some(format!(
+            "{} ({}), scopes: {}",
+            info.owner_login,
+            info.kind.label(),
+            info.scopes
+        ))
+    }
+
+    fn requires_github_token(&self) -> bool {
+        true
+    }
+
+    /// Samples the rate limits every `RATE_SAMPLE_INTERVAL` once a token has
+    /// been inspected, one request at a time.
+    fn on_tick(&mut self) -> Option<String> {
+        if self.last_sample.elapsed() < RATE_SAMPLE_INTERVAL
+            || self.sampler.as_ref().is_some_and(|s| !s.is_finished())
+        {
+            return None;
+        }
+        self.last_sample = Instant::now();
+        if self
+            .secrets
+            .token_for(Self::NAME)
+            .expose_secret()
+            .is_empty()
+            || self.results.lock().unwrap().is_none()
+        {
+            return None;
         }
+        let sampler = self.clone_state();
+        self.sampler = Some(tokio::spawn(async move {
+            let _ = sampler.sample_rate_limits().await;
+        }));
+        None
+    }
 
-        let results =
-            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(Line::from(
-                Span::styled("Token Information", Style::default().fg(Color::Cyan)),
-            )));
-        f.render_widget(results, chunks[1]);
+    fn render(&self, f: &mut Frame, area: Rect) {
+        if let Some(status) = self.inspection.as_ref().and_then(Task::status) {
+            components::render_task(f, area, &status);
+            return;
+        }
+
+        let error_lock = self.error.lock().unwrap();
+        if let Some(err) = &*error_lock {
+            let error_para = Paragraph::new(err.as_str())
+                .style(Style::default().fg(Color::Red))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).title("Error"));
+            f.render_widget(error_para, area);
+            return;
+        }
+        drop(error_lock);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        let cta = Paragraph::new(format!(
+            "Press Enter to re-inspect the current GitHub Token. {} switches tokens.",
+            Action::Credentials.label()
+        ))
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().borders(Borders::ALL));
+        f.render_widget(cta, chunks[0]);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(chunks[1]);
+
+        self.info_list.render(
+            f,
+            columns[0],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Token Information",
+                    Style::default().fg(Color::Cyan),
+                ))),
+            self.info_rows(),
+            true,
+        );
+
+        let results = self.results.lock().unwrap();
+        let rate_limits = results
+            .as_ref()
+            .map(|info| info.rate_limits.as_slice())
+            .unwrap_or_default();
+        self.render_rate_limits(f, columns[1], rate_limits);
+    }
+
+    fn status_warning(&self) -> Option<String> {
+        let results = self.results.lock().unwrap();
+        let info = results.as_ref()?;
+        if let Some(warning) = &info.rotation_warning {
+            return Some(warning.clone());
+        }
+        match Self::days_remaining(info.expires_at)? {
+            days if days < 0 => Some("GitHub token has expired".to_string()),
+            days if days <= self.expiry_warning_days as i64 => {
+                Some(format!("GitHub token expires in {} days", days))
+            }
+            _ => None,
+        }
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        self.info_list.selected_text(&self.info_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.info_list.handle_mouse(mouse);
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match action {
+                Action::Refresh => Ok(self.reinspect()),
+                _ => Ok(String::new()),
+            }
+        })
     }
 
     fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
-        // We need to clone state for the async move block
-        let mut tool_clone = self.clone_state();
         Box::pin(async move {
             match key.code {
-                KeyCode::Enter => tool_clone.inspect_token().await,
+                _ if self.info_list.handle_focused_key(&key) => Ok(String::new()),
+                KeyCode::Enter => Ok(self.reinspect()),
                 _ => Ok(String::new()),
             }
         })
     }
+
+    fn on_task_finished(&mut self) -> Option<String> {
+        let result = Task::take_finished(&mut self.inspection)?;
+        self.info_list.reset();
+        if !std::mem::replace(&mut self.announce, true) {
+            return result.err();
+        }
+        Some(result.unwrap_or_else(|e| e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate_limits_reads_every_resource() {
+        let json = serde_json::json!({
+            "resources": {
+                "search": {"limit": 30, "used": 2, "remaining": 28, "reset": 100},
+                "core": {"limit": 5000, "used": 10, "remaining": 4990, "reset": 200},
+                "graphql": {"limit": 5000, "used": 0, "remaining": 5000, "reset": 300}
+            },
+            "rate": {"limit": 5000, "used": 10, "remaining": 4990, "reset": 200}
+        });
+        let resources = parse_rate_limits(&json);
+        let names: Vec<&str> = resources.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["core", "graphql", "search"]);
+        assert_eq!(resources[2].remaining, 28);
+        assert_eq!(resources[2].used, 2);
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        assert_eq!(format_countdown(-5), "now");
+        assert_eq!(format_countdown(125), "2m 05s");
+        assert_eq!(format_countdown(3720), "1h 02m");
+    }
 }
@@ -1,11 +1,16 @@
 use crate::db::Database;
+use crate::keymap::Action;
 use crate::models::unicode::UnicodeChar;
+use crate::tools::Command;
+use crate::ui::components::TextInput;
+use crate::ui::focus::{self, Focus};
+use crate::ui::list::ScrollList;
+use crate::unicode_data;
 use rusqlite::params;
 use std::error::Error;
-use std::fs;
 use std::sync::{Arc, Mutex};
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
@@ -17,31 +22,65 @@ use crate::tools::LoadState;
 pub struct UnicodeInspectorTool {
     input: InputState,
     results: Vec<UnicodeChar>,
+    result_list: ScrollList,
     sequential: bool,
-    db: Arc<Mutex<Database>>,
+    db: Database,
     load_state: Arc<Mutex<LoadState>>,
+    /// Where the imported data came from, shown in the input title.
+    data_source: Arc<Mutex<Option<String>>>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Text,
+    Codepoint,
+    Name,
+    Sequential,
+    Results,
+}
+
+const COMMANDS: &[Command] = &[
+    Command::new("text", "Analyze Text...").with_prompt("Text"),
+    Command::new("codepoint", "Look Up Codepoint...").with_prompt("Codepoint, e.g. U+1F600"),
+    Command::new("name", "Search by Name...").with_prompt("Character name"),
+];
+
 struct InputState {
-    text: String,
-    codepoint: String,
-    name: String,
-    current_field: usize,
+    text: TextInput,
+    codepoint: TextInput,
+    name: TextInput,
+    focus: Focus<Field>,
+}
+
+impl InputState {
+    fn field(&mut self) -> Option<&mut TextInput> {
+        match self.focus.current() {
+            Field::Text => Some(&mut self.text),
+            Field::Codepoint => Some(&mut self.codepoint),
+            Field::Name => Some(&mut self.name),
+            Field::Sequential | Field::Results => None,
+        }
+    }
 }
 
 use crate::config::Config;
 
+const BY_CODEPOINT: &str = "SELECT codepoint, name, block FROM unicode_chars WHERE codepoint = ?1";
+const BY_NAME: &str = "SELECT codepoint, name, block FROM unicode_chars WHERE name LIKE ?1";
+
 impl UnicodeInspectorTool {
-    pub fn new(db: Arc<Mutex<Database>>, config: &Config) -> Result<Self, Box<dyn Error>> {
+    pub fn new(db: Database, config: &Config) -> Result<Self, Box<dyn Error>> {
         let load_state = Arc::new(Mutex::new(LoadState::Loading));
-        let db_clone = Arc::clone(&db);
+        let db_clone = db.clone();
         let loading_clone = Arc::clone(&load_state);
+        let data_source = Arc::new(Mutex::new(None));
+        let source_clone = Arc::clone(&data_source);
         let unicode_data_path = config.unicode_data_path.clone();
-        let blocks_path = config.blocks_path.clone();
 
         tokio::spawn(async move {
-            match Self::import_data_if_needed(db_clone, unicode_data_path, blocks_path) {
-                Ok(_) => {
+            match Self::import_data_if_needed(&db_clone, unicode_data_path).await {
+                Ok(source) => {
+                    *source_clone.lock().unwrap() = Some(source);
                     let mut state = loading_clone.lock().unwrap();
                     *state = LoadState::Ready;
                 }
@@ -54,120 +93,149 @@ impl UnicodeInspectorTool {
 
         Ok(UnicodeInspectorTool {
             input: InputState {
-                text: String::new(),
-                codepoint: String::new(),
-                name: String::new(),
-                current_field: 0,
+                text: TextInput::new(),
+                codepoint: TextInput::new(),
+                name: TextInput::new(),
+                focus: Focus::new(&[
+                    Field::Text,
+                    Field::Codepoint,
+                    Field::Name,
+                    Field::Sequential,
+                    Field::Results,
+                ]),
             },
             results: Vec::new(),
+            result_list: ScrollList::new(),
             sequential: false,
             db,
             load_state,
+            data_source,
         })
     }
 
-    fn import_data_if_needed(
-        db: Arc<Mutex<Database>>,
+    /// Imports `UnicodeData.txt` into the cache on first use and returns a
+    /// description of where the data came from. The file is read and
+    /// decompressed on a blocking thread; only the insert runs on the
+    /// database thread.
+    async fn import_data_if_needed(
+        db: &Database,
         unicode_path: String,
-        _blocks_path: String,
-    ) -> Result<(), Box<dyn Error>> {
-        let mut locked_db = db.lock().unwrap();
-
+    ) -> Result<String, Box<dyn Error>> {
         // Only import if table is empty
-        let count: u32 = locked_db
-            .conn()
-            .query_row("SELECT COUNT(*) FROM unicode_chars", [], |r| r.get(0))
-            .unwrap_or(0);
+        let count: u32 = db
+            .call(|conn| {
+                Ok(conn
+                    .query_row("SELECT COUNT(*) FROM unicode_chars", [], |r| r.get(0))
+                    .unwrap_or(0))
+            })
+            .await?;
         if count > 0 {
-            return Ok(());
+            return Ok("cache".into());
         }
 
-        let tx = locked_db.conn().transaction()?;
-        if std::path::Path::new(&unicode_path).exists() {
-            let chars_data = fs::read_to_string(unicode_path)?;
-            for line in chars_data.lines() {
-                let fields: Vec<&str> = line.split(';').collect();
-                if fields.len() >= 3 {
-                    tx.execute(
-                        "INSERT OR REPLACE INTO unicode_chars (codepoint, name, block) VALUES (?1, ?2, ?3)",
-                        params![fields[0], fields[1], fields[2]],
-                    )?;
+        let (chars_data, source) = tokio::task::spawn_blocking(move || {
+            unicode_data::read(&unicode_path, unicode_data::UNICODE_DATA).map_err(|e| e.to_string())
+        })
+        .await??;
+        db.call(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR REPLACE INTO unicode_chars (codepoint, name, block) VALUES (?1, ?2, ?3)",
+                )?;
+                for line in chars_data.lines() {
+                    let fields: Vec<&str> = line.split(';').collect();
+                    if fields.len() >= 3 {
+                        stmt.execute(params![fields[0], fields[1], fields[2]])?;
+                    }
                 }
             }
-        }
-        tx.commit()?;
-        Ok(())
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+        Ok(source.to_string())
     }
 
-    fn analyze_text(&mut self) -> Result<String, Box<dyn Error>> {
-        self.results.clear();
-        let graphemes = self.input.text.graphemes(true).collect::<Vec<&str>>();
-        for g in graphemes {
-            let codepoints = g
-                .chars()
-                .map(|c| format!("{:04X}", c as u32))
-                .collect::<Vec<String>>();
-            if let Some(codepoint) = codepoints.first() {
-                let mut db = self.db.lock().unwrap();
-                let mut rows = db.conn().prepare(
-                    "SELECT codepoint, name, block FROM unicode_chars WHERE codepoint = ?1",
-                )?;
-                let chars = rows.query_map(params![codepoint], |row| {
-                    Ok(UnicodeChar {
-                        codepoint: row.get(0)?,
-                        name: row.get(1)?,
-                        block: row.get(2)?,
-                    })
-                })?;
-                for c in chars {
-                    self.results.push(c?);
+    /// Runs a `unicode_chars` query once per argument and collects the rows.
+    async fn query_chars(
+        &self,
+        sql: &'static str,
+        args: Vec<String>,
+    ) -> Result<Vec<UnicodeChar>, Box<dyn Error>> {
+        self.db
+            .call(move |conn| {
+                let mut stmt = conn.prepare_cached(sql)?;
+                let mut found = Vec::new();
+                for arg in &args {
+                    let chars = stmt.query_map(params![arg], |row| {
+                        Ok(UnicodeChar {
+                            codepoint: row.get(0)?,
+                            name: row.get(1)?,
+                            block: row.get(2)?,
+                        })
+                    })?;
+                    for c in chars {
+                        found.push(c?);
+                    }
                 }
-            }
-        }
+                Ok(found)
+            })
+            .await
+    }
+
+    fn toggle_sequential(&mut self) -> String {
+        self.sequential = !self.sequential;
+        format!("Sequential Mode: {}", self.sequential)
+    }
+
+    async fn analyze_text(&mut self) -> Result<String, Box<dyn Error>> {
+        // The first code point of each grapheme identifies it.
+        let codepoints = self
+            .input
+            .text
+            .value()
+            .graphemes(true)
+            .filter_map(|g| g.chars().next())
+            .map(|c| format!("{:04X}", c as u32))
+            .collect();
+        self.results = self.query_chars(BY_CODEPOINT, codepoints).await?;
+        self.result_list.reset();
         Ok(format!("Analyzed {} graphemes", self.results.len()))
     }
 
-    fn lookup_codepoint(&mut self) -> Result<String, Box<dyn Error>> {
-        self.results.clear();
-        let cp = self.input.codepoint.trim_start_matches("U+").to_uppercase();
-        let mut db = self.db.lock().unwrap();
-        let mut rows = db
-            .conn()
-            .prepare("SELECT codepoint, name, block FROM unicode_chars WHERE codepoint = ?1")?;
-        let chars = rows.query_map(
-            params![format!("{:04X}", u32::from_str_radix(&cp, 16)?)],
-            |row| {
-                Ok(UnicodeChar {
-                    codepoint: row.get(0)?,
-                    name: row.get(1)?,
-                    block: row.get(2)?,
-                })
-            },
-        )?;
-        for c in chars {
-            self.results.push(c?);
-        }
+    async fn lookup_codepoint(&mut self) -> Result<String, Box<dyn Error>> {
+        let cp = self
+            .input
+            .codepoint
+            .value()
+            .trim_start_matches("U+")
+            .to_uppercase();
+        let codepoint = format!("{:04X}", u32::from_str_radix(&cp, 16)?);
+        self.results = self.query_chars(BY_CODEPOINT, vec![codepoint]).await?;
+        self.result_list.reset();
         Ok(format!("Found {} characters", self.results.len()))
     }
 
-    fn lookup_name(&mut self) -> Result<String, Box<dyn Error>> {
-        self.results.clear();
-        let mut db = self.db.lock().unwrap();
-        let mut rows = db
-            .conn()
-            .prepare("SELECT codepoint, name, block FROM unicode_chars WHERE name LIKE ?1")?;
-        let chars = rows.query_map(params![format!("%{}%", self.input.name)], |row| {
-            Ok(UnicodeChar {
-                codepoint: row.get(0)?,
-                name: row.get(1)?,
-                block: row.get(2)?,
-            })
-        })?;
-        for c in chars {
-            self.results.push(c?);
-        }
+    async fn lookup_name(&mut self) -> Result<String, Box<dyn Error>> {
+        let pattern = format!("%{}%", self.input.name.value());
+        self.results = self.query_chars(BY_NAME, vec![pattern]).await?;
+        self.result_list.reset();
         Ok(format!("Found {} characters", self.results.len()))
     }
+
+    fn result_rows(&self) -> Vec<Line<'_>> {
+        self.results
+            .iter()
+            .map(|c| {
+                Line::from(vec![
+                    Span::raw(format!("U+{} ", c.codepoint)),
+                    Span::raw(c.name.clone()),
+                    Span::raw(format!(" ({})", c.block)),
+                ])
+            })
+            .collect()
+    }
 }
 
 impl super::Tool for UnicodeInspectorTool {
@@ -175,6 +243,19 @@ impl super::Tool for UnicodeInspectorTool {
         "Unicode Inspector"
     }
 
+    fn primary_result(&self) -> Option<String> {
+        if self.results.is_empty() {
+            return None;
+        }
+        Some(
+            self.results
+                .iter()
+                .map(|c| format!("{} {} ({})", c.codepoint, c.name, c.block))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
     fn render(&self, f: &mut Frame, area: Rect) {
         let state = self.load_state.lock().unwrap();
         match &*state {
@@ -209,121 +290,156 @@ impl super::Tool for UnicodeInspectorTool {
             ])
             .split(area);
 
-        let text_input = Paragraph::new(self.input.text.as_str()).block(
+        self.input.text.render(
+            f,
+            chunks[0],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
-                    "Unicode Input",
+                    match &*self.data_source.lock().unwrap() {
+                        Some(source) => format!("Unicode Input (data: {})", source),
+                        None => "Unicode Input".to_string(),
+                    },
                     Style::default().fg(Color::Green),
                 ))),
+            self.input.focus.is(Field::Text),
         );
-        f.render_widget(text_input, chunks[0]);
 
-        let codepoint_input = Paragraph::new(self.input.codepoint.as_str()).block(
+        self.input.codepoint.render(
+            f,
+            chunks[1],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Codepoint",
                     Style::default().fg(Color::Green),
                 ))),
+            self.input.focus.is(Field::Codepoint),
         );
-        f.render_widget(codepoint_input, chunks[1]);
 
-        let name_input = Paragraph::new(self.input.name.as_str()).block(
+        self.input.name.render(
+            f,
+            chunks[2],
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Name",
                     Style::default().fg(Color::Green),
                 ))),
+            self.input.focus.is(Field::Name),
         );
-        f.render_widget(name_input, chunks[2]);
 
-        let sequential_toggle = Paragraph::new(if self.sequential { "Yes" } else { "No" }).block(
+        let sequential_toggle = Paragraph::new(if self.sequential {
+            "[x] Yes (Space to toggle)"
+        } else {
+            "[ ] No (Space to toggle)"
+        })
+        .block(focus::border(
             Block::default()
                 .borders(Borders::ALL)
                 .title(Line::from(Span::styled(
                     "Sequential Mode",
                     Style::default().fg(Color::Green),
                 ))),
-        );
+            self.input.focus.is(Field::Sequential),
+        ));
         f.render_widget(sequential_toggle, chunks[3]);
 
-        let results =
-            Paragraph::new(
-                self.results
-                    .iter()
-                    .map(|c| {
-                        Line::from(vec![
-                            Span::raw(format!("U+{} ", c.codepoint)),
-                            Span::raw(c.name.clone()),
-                            Span::raw(format!(" ({})", c.block)),
-                        ])
-                    })
-                    .collect::<Vec<_>>(),
-            )
-            .block(Block::default().borders(Borders::ALL).title(Line::from(
-                Span::styled("Unicode Results", Style::default().fg(Color::Green)),
-            )));
-        f.render_widget(results, chunks[4]);
+        self.result_list.render(
+            f,
+            chunks[4],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Unicode Results",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.result_rows(),
+            self.input.focus.is(Field::Results),
+        );
     }
 
-    fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
+    fn selected_row(&self) -> Option<String> {
+        self.result_list.selected_text(&self.result_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        self.result_list.handle_mouse(mouse);
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.input.focus.cycle(backward);
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.input.field()
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
         Box::pin(async move {
-            match key.code {
-                KeyCode::Up => {
-                    self.input.current_field = self.input.current_field.saturating_sub(1);
-                    Ok("Switched field".into())
-                }
-                KeyCode::Down => {
-                    self.input.current_field = (self.input.current_field + 1).min(3);
-                    Ok("Switched field".into())
-                }
-                KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.sequential = !self.sequential;
-                    Ok(format!("Sequential Mode: {}", self.sequential))
-                }
-                KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match action {
+                Action::ToggleOption => Ok(self.toggle_sequential()),
+                Action::Lookup => {
                     if !self.input.codepoint.is_empty() {
-                        self.lookup_codepoint()
+                        self.lookup_codepoint().await
                     } else if !self.input.name.is_empty() {
-                        self.lookup_name()
+                        self.lookup_name().await
                     } else {
                         Ok("No lookup input".into())
                     }
                 }
-                KeyCode::Enter => self.analyze_text(),
-                KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    serde_json::to_writer(
-                        std::fs::File::create("unicode_results.json")?,
-                        &self.results,
-                    )?;
+                Action::Export => {
+                    crate::redact::export_json("unicode_results.json", &self.results)?;
                     Ok("Exported to unicode_results.json".into())
                 }
-                KeyCode::Char(c) => {
-                    match self.input.current_field {
-                        0 => self.input.text.push(c),
-                        1 => self.input.codepoint.push(c),
-                        2 => self.input.name.push(c),
-                        _ => {}
-                    }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match id {
+                "codepoint" => {
+                    self.input.codepoint.set_value(&argument);
+                    self.lookup_codepoint().await
+                }
+                "name" => {
+                    self.input.name.set_value(&argument);
+                    self.lookup_name().await
+                }
+                _ => {
+                    self.input.text.set_value(&argument);
+                    self.analyze_text().await
+                }
+            }
+        })
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match key.code {
+                _ if self.input.focus.is(Field::Results)
+                    && self.result_list.handle_focused_key(&key) =>
+                {
+                    Ok(String::new())
+                }
+                _ if self.result_list.handle_key(&key) => Ok(String::new()),
+                KeyCode::Char(' ') if self.input.focus.is(Field::Sequential) => {
+                    Ok(self.toggle_sequential())
+                }
+                KeyCode::Enter => self.analyze_text().await,
+                _ if self
+                    .input
+                    .field()
+                    .is_some_and(|field| field.handle_key(&key)) =>
+                {
                     Ok("Input updated".into())
                 }
-                KeyCode::Backspace => match self.input.current_field {
-                    0 => {
-                        self.input.text.pop();
-                        Ok("Removed character".into())
-                    }
-                    1 => {
-                        self.input.codepoint.pop();
-                        Ok("Removed character".into())
-                    }
-                    2 => {
-                        self.input.name.pop();
-                        Ok("Removed character".into())
-                    }
-                    _ => Ok("No action".into()),
-                },
                 _ => Ok(String::new()),
             }
         })
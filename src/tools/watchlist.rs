@@ -0,0 +1,472 @@
+use crate::config::Config;
+use crate::db::Database;
+use crate::keymap::Action;
+use crate::secrets::Secrets;
+use crate::snapshots;
+use crate::tasks::{Task, Tasks};
+use crate::tools::Command;
+use crate::ui::components::TextInput;
+use crate::ui::focus::Focus;
+use crate::ui::list::ScrollList;
+use crate::watchlist::{self, Notification, NotificationKind, WatchEntry};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders},
+};
+use reqwest::Client;
+use secrecy::ExposeSecret;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+pub struct WatchlistTool {
+    input: TextInput,
+    entries: Vec<WatchEntry>,
+    entry_list: ScrollList,
+    notifications: Vec<Notification>,
+    notification_list: ScrollList,
+    tasks: Tasks,
+    refresh: Option<Task<WatchRefresh>>,
+    /// When the running or last refresh started, for `watch_interval_secs`.
+    refresh_started: Instant,
+    interval: u64,
+    /// Whether the refresh was requested, so its result is always reported.
+    announce: bool,
+    last_refresh: Option<i64>,
+    db: Database,
+    client: Client,
+    secrets: Secrets,
+    focus: Focus<Field>,
+}
+
+/// What a background refresh hands back to the tool.
+struct WatchRefresh {
+    entries: Vec<WatchEntry>,
+    /// `None` if there was no token to refresh with.
+    notifications: Option<Vec<Notification>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Input,
+    Entries,
+    Notifications,
+}
+
+const FOCUS_ORDER: &[Field] = &[Field::Input, Field::Entries, Field::Notifications];
+
+const COMMANDS: &[Command] = &[
+    Command::new("watch", "Watch...").with_prompt("user:NAME, org:NAME or OWNER/REPO"),
+    Command::new("remove", "Stop Watching Selected Entry"),
+];
+
+impl WatchlistTool {
+    const NAME: &'static str = "Watchlist";
+
+    pub fn new(
+        db: Database,
+        client: &Client,
+        secrets: &Secrets,
+        config: &Config,
+        tasks: &Tasks,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut tool = WatchlistTool {
+            input: TextInput::new(),
+            entries: Vec::new(),
+            entry_list: ScrollList::new(),
+            notification_list: ScrollList::new(),
+            notifications: Vec::new(),
+            tasks: tasks.clone(),
+            refresh: None,
+            refresh_started: Instant::now(),
+            interval: config.watch_interval_secs,
+            announce: false,
+            last_refresh: None,
+            db,
+            client: client.clone(),
+            secrets: secrets.clone(),
+            focus: Focus::new(FOCUS_ORDER),
+        };
+
+        // Loads the entries, and refreshes them once if there is a token.
+        tool.start_refresh();
+        Ok(tool)
+    }
+
+    /// Starts a refresh in the background; the result arrives through
+    /// `on_task_finished`.
+    fn start_refresh(&mut self) {
+        let db = self.db.clone();
+        let client = self.client.clone();
+        let token = self.secrets.token_for(Self::NAME);
+        self.refresh_started = Instant::now();
+        self.refresh = Some(self.tasks.spawn(
+            Self::NAME,
+            "Refreshing Watchlist",
+            move |progress| async move {
+                let token = token.expose_secret();
+                let notifications = if token.is_empty() {
+                    None
+                } else {
+                    progress.set("Checking watched repositories and organizations");
+                    // A failure is shown as a notification rather than
+                    // replacing the panel.
+                    Some(
+                        watchlist::refresh_all(&db, &client, token)
+                            .await
+                            .unwrap_or_else(|e| {
+                                vec![Notification {
+                                    kind: NotificationKind::Error,
+                                    target: "watchlist".into(),
+                                    message: e.to_string(),
+                                }]
+                            }),
+                    )
+                };
+                let entries = db.call(|conn| watchlist::list(conn)).await?;
+                Ok(WatchRefresh {
+                    entries,
+                    notifications,
+                })
+            },
+        ));
+    }
+
+    async fn add_entry(&mut self) -> Result<String, Box<dyn Error>> {
+        let (kind, target) = watchlist::parse_target(self.input.value())?;
+        let added_target = target.clone();
+        let (added, entries) = self
+            .db
+            .call(move |conn| {
+                let added = watchlist::add(conn, kind, &added_target)?;
+                Ok((added, watchlist::list(conn)?))
+            })
+            .await?;
+        self.entries = entries;
+        self.input.clear();
+        Ok(if added {
+            format!(
+                "Watching {}:{} ({} to fetch it now)",
+                kind.as_str(),
+                target,
+                Action::Refresh.label()
+            )
+        } else {
+            format!("Already watching {}:{}", kind.as_str(), target)
+        })
+    }
+
+    fn notification_rows(&self) -> Vec<Line<'static>> {
+        self.notifications
+            .iter()
+            .map(|n| {
+                let color = match n.kind {
+                    NotificationKind::Release => Color::Green,
+                    NotificationKind::Repository => Color::Cyan,
+                    NotificationKind::Security => Color::Red,
+                    NotificationKind::Error => Color::Yellow,
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!("[{}] ", n.kind.as_str()),
+                        Style::default().fg(color).bold(),
+                    ),
+                    Span::raw(format!("{}: {}", n.target, n.message)),
+                ])
+            })
+            .collect()
+    }
+
+    async fn remove_selected(&mut self) -> Result<String, Box<dyn Error>> {
+        let selected = self.entry_list.selected();
+        let Some(entry) = self.entries.get(selected).cloned() else {
+            return Ok("Watchlist is empty".into());
+        };
+        let (kind, target) = (entry.kind, entry.target.clone());
+        let remaining = self
+            .db
+            .call(move |conn| {
+                watchlist::remove(conn, kind, &target)?;
+                watchlist::list(conn)
+            })
+            .await?;
+        self.entry_list
+            .select(selected.min(remaining.len().saturating_sub(1)));
+        self.entries = remaining;
+        Ok(format!("Stopped watching {}", entry.label()))
+    }
+}
+
+impl super::Tool for WatchlistTool {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn primary_result(&self) -> Option<String> {
+        if self.notifications.is_empty() {
+            return None;
+        }
+        Some(
+            self.notifications
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    fn requires_github_token(&self) -> bool {
+        true
+    }
+
+    fn render(&self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        self.input.render(
+            f,
+            chunks[0],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Add (user:NAME, org:NAME or OWNER/REPO)",
+                    Style::default().fg(Color::Green),
+                ))),
+            self.focus.is(Field::Input),
+        );
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+            .split(chunks[1]);
+
+        let mut entry_lines = self
+            .entries
+            .iter()
+            .map(|entry| Line::from(entry.label()))
+            .collect::<Vec<_>>();
+        if entry_lines.is_empty() {
+            entry_lines.push(Line::from("Nothing watched yet."));
+        }
+        self.entry_list.render(
+            f,
+            columns[0],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    "Watched",
+                    Style::default().fg(Color::Green),
+                ))),
+            entry_lines,
+            self.focus.is(Field::Entries),
+        );
+
+        let title = if self.refresh.is_some() {
+            "Notifications (refreshing...)".to_string()
+        } else {
+            match self.last_refresh {
+                Some(ts) => format!(
+                    "Notifications (last refresh {})",
+                    snapshots::format_timestamp(ts)
+                ),
+                None => "Notifications".to_string(),
+            }
+        };
+
+        let mut lines = self.notification_rows();
+        if lines.is_empty() {
+            lines.push(Line::from(
+                "No changes since the watchlist was last refreshed.",
+            ));
+        }
+        self.notification_list.render(
+            f,
+            columns[1],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(Span::styled(
+                    title,
+                    Style::default().fg(Color::Cyan),
+                ))),
+            lines,
+            self.focus.is(Field::Notifications),
+        );
+    }
+
+    fn selected_row(&self) -> Option<String> {
+        if self.focus.is(Field::Entries) {
+            return self
+                .entries
+                .get(self.entry_list.selected())
+                .map(WatchEntry::label);
+        }
+        self.notification_list
+            .selected_text(&self.notification_rows())
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseEvent) {
+        if !self.entry_list.handle_mouse(mouse) {
+            self.notification_list.handle_mouse(mouse);
+        }
+    }
+
+    fn cycle_focus(&mut self, backward: bool) {
+        self.focus.cycle(backward);
+    }
+
+    fn focused_input(&mut self) -> Option<&mut TextInput> {
+        self.focus.is(Field::Input).then_some(&mut self.input)
+    }
+
+    fn handle_action(&mut self, action: Action) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match action {
+                Action::Refresh => {
+                    self.announce = true;
+                    self.start_refresh();
+                    Ok("Refreshing watchlist".into())
+                }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    fn commands(&self) -> &'static [Command] {
+        COMMANDS
+    }
+
+    fn run_command(&mut self, id: &'static str, argument: String) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            if id == "remove" {
+                return self.remove_selected().await;
+            }
+            self.input.set_value(&argument);
+            self.add_entry().await
+        })
+    }
+
+    fn handle_input(&mut self, key: KeyEvent) -> crate::tools::ToolFuture<'_> {
+        Box::pin(async move {
+            match key.code {
+                KeyCode::Enter if self.focus.is(Field::Input) => self.add_entry().await,
+                KeyCode::Delete if self.focus.is(Field::Entries) => self.remove_selected().await,
+                _ if self.focus.is(Field::Entries) && self.entry_list.handle_focused_key(&key) => {
+                    Ok(String::new())
+                }
+                _ if self.focus.is(Field::Notifications)
+                    && self.notification_list.handle_focused_key(&key) =>
+                {
+                    Ok(String::new())
+                }
+                _ if self.notification_list.handle_key(&key) => Ok(String::new()),
+                KeyCode::Up => {
+                    self.entry_list.scroll(-1);
+                    Ok(String::new())
+                }
+                KeyCode::Down => {
+                    self.entry_list.scroll(1);
+                    Ok(String::new())
+                }
+                _ if self.focus.is(Field::Input) && self.input.handle_key(&key) => {
+                    Ok("Input updated".into())
+                }
+                _ => Ok(String::new()),
+            }
+        })
+    }
+
+    /// Refreshes every `watch_interval_secs` while there is a token; `0`
+    /// only refreshes at startup.
+    fn on_tick(&mut self) -> Option<String> {
+        if self.interval == 0
+            || self.refresh.is_some()
+            || self.refresh_started.elapsed() < Duration::from_secs(self.interval)
+            || !self.secrets.has_token_for(Self::NAME)
+        {
+            return None;
+        }
+        self.start_refresh();
+        None
+    }
+
+    /// Automatic refreshes only report changes; a requested one always
+    /// reports its outcome.
+    fn on_task_finished(&mut self) -> Option<String> {
+        let result = Task::take_finished(&mut self.refresh)?;
+        let announce = std::mem::take(&mut self.announce);
+        let refreshed = match result {
+            Ok(refreshed) => refreshed,
+            Err(e) => return announce.then_some(e),
+        };
+        self.entries = refreshed.entries;
+        self.entry_list.select(
+            self.entry_list
+                .selected()
+                .min(self.entries.len().saturating_sub(1)),
+        );
+        let Some(found) = refreshed.notifications else {
+            return announce.then(|| "No GitHub token to refresh the watchlist with".into());
+        };
+        self.last_refresh = Some(snapshots::now_unix());
+        let count = found.len();
+        // Newest notifications first; keep the panel bounded.
+        self.notifications.splice(0..0, found);
+        self.notifications.truncate(200);
+        match count {
+            0 if announce => Some("No watchlist changes".into()),
+            0 => None,
+            n => Some(format!("{} watchlist changes", n)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::Tool;
+
+    #[tokio::test]
+    async fn test_delete_only_removes_entries_when_the_list_has_focus() {
+        let db = Database::new(":memory:").unwrap();
+        db.call(|conn| watchlist::add(conn, watchlist::WatchKind::Org, "rust-lang"))
+            .await
+            .unwrap();
+        let (tasks, _events) = Tasks::new();
+        let mut tool = WatchlistTool::new(
+            db.clone(),
+            &Client::new(),
+            &Secrets::default(),
+            &Config::default(),
+            &tasks,
+        )
+        .unwrap();
+        // The startup refresh loads the entries; without a token it reports
+        // nothing.
+        while tool.refresh.is_some() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert_eq!(tool.on_task_finished(), None);
+        }
+        assert_eq!(tool.entries.len(), 1);
+
+        tool.input.set_value("user:octocat");
+        tool.add_entry().await.unwrap();
+
+        tool.input.set_value("user:octocatx");
+        tool.input.handle_key(&KeyEvent::from(KeyCode::Left));
+        tool.handle_input(KeyEvent::from(KeyCode::Delete))
+            .await
+            .unwrap();
+        assert_eq!(tool.input.value(), "user:octocat");
+        let watched = db.call(|conn| watchlist::list(conn)).await.unwrap();
+        assert_eq!(watched.len(), 2);
+
+        tool.cycle_focus(false);
+        tool.handle_input(KeyEvent::from(KeyCode::Delete))
+            .await
+            .unwrap();
+        let watched = db.call(|conn| watchlist::list(conn)).await.unwrap();
+        assert_eq!(watched.len(), 1);
+    }
+}
@@ -1,2 +1,485 @@
-// Placeholder for future TUI components
-// Currently empty as all rendering is handled directly in tools
+use crate::keymap::Action;
+use crate::tasks::TaskStatus;
+use crate::ui::focus;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Paragraph},
+};
+use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
+use zeroize::Zeroize;
+
+const SPINNER: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// A spinner frame that advances with the task's elapsed time, so it moves on
+/// every redraw tick without any state of its own.
+pub fn spinner(elapsed: Duration) -> &'static str {
+    SPINNER[(elapsed.as_millis() / 100) as usize % SPINNER.len()]
+}
+
+/// The loading screen a tool shows in place of its content while one of its
+/// tasks runs.
+pub fn render_task(f: &mut Frame, area: Rect, status: &TaskStatus) {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "{} {}... ({}s)",
+                spinner(status.elapsed),
+                status.label,
+                status.elapsed.as_secs()
+            ),
+            Style::default().fg(Color::Yellow).bold(),
+        )),
+    ];
+    if let Some(progress) = &status.progress {
+        lines.push(Line::from(progress.clone()));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("{} to stop", Action::CancelTask.label()),
+        Style::default().fg(Color::DarkGray),
+    )));
+    let loading = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(loading, area);
+}
+
+/// Entries kept in a text input's history.
+const HISTORY_LIMIT: usize = 50;
+
+/// A single-line text field with a cursor, selection, readline-style
+/// deletion, paste, history and horizontal scrolling.
+///
+/// Positions are counted in `char`s. Secret fields render as bullets, drop
+/// whitespace, never record history, and are zeroized when cleared or
+/// dropped; their buffer is allocated once so editing never leaves copies
+/// behind in freed memory.
+#[derive(Default)]
+pub struct TextInput {
+    value: String,
+    cursor: usize,
+    /// The other end of the selection, if any; the cursor is one end.
+    anchor: Option<usize>,
+    /// Byte limit for secret fields; `None` is unlimited.
+    capacity: Option<usize>,
+    secret: bool,
+    /// Whether Up/Down recall history. Off for fields where the tool uses
+    /// them for something else.
+    history_keys: bool,
+    history: Vec<String>,
+    /// Position while browsing history, and the text typed before it began.
+    history_pos: Option<usize>,
+    draft: String,
+}
+
+/// Masks the value of secret fields, which also keep no history to show.
+impl std::fmt::Debug for TextInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = if self.secret {
+            format!("<{} bytes hidden>", self.value.len())
+        } else {
+            format!("{:?}", self.value)
+        };
+        f.debug_struct("TextInput")
+            .field("value", &format_args!("{}", value))
+            .field("cursor", &self.cursor)
+            .field("anchor", &self.anchor)
+            .field("secret", &self.secret)
+            .field("history", &self.history)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets Up/Down recall values recorded with [`commit`](Self::commit).
+    pub fn with_history(mut self) -> Self {
+        self.history_keys = true;
+        self
+    }
+
+    /// A masked field for tokens and passwords, holding at most `capacity`
+    /// bytes.
+    pub fn secret(capacity: usize) -> Self {
+        let mut input = Self::default();
+        input.value.reserve_exact(capacity);
+        input.capacity = Some(capacity);
+        input.secret = true;
+        input
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Replaces the text and moves the cursor to its end.
+    pub fn set_value(&mut self, value: &str) {
+        self.clear();
+        self.insert_str(value);
+    }
+
+    pub fn clear(&mut self) {
+        if self.secret {
+            self.value.zeroize();
+        }
+        self.value.clear();
+        self.cursor = 0;
+        self.anchor = None;
+        self.history_pos = None;
+    }
+
+    /// The selected text, if any.
+    pub fn selected_text(&self) -> Option<&str> {
+        let (start, end) = self.selection()?;
+        Some(&self.value[self.byte(start)..self.byte(end)])
+    }
+
+    /// Records the current value in the history, e.g. when it is submitted.
+    pub fn commit(&mut self) {
+        self.history_pos = None;
+        let value = self.value.trim();
+        if self.secret || value.is_empty() || self.history.last().map(String::as_str) == Some(value)
+        {
+            return;
+        }
+        self.history.push(value.to_string());
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+    }
+
+    /// Inserts text at the cursor, replacing the selection. Line breaks and
+    /// tabs become spaces (and are dropped from secret fields), so a paste
+    /// with a trailing newline does not leave stray whitespace.
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        let text = text.trim_end_matches(['\r', '\n']);
+        for c in text.chars() {
+            let c = if c.is_whitespace() && c != ' ' {
+                ' '
+            } else {
+                c
+            };
+            if self.secret && c.is_whitespace() {
+                continue;
+            }
+            if let Some(capacity) = self.capacity {
+                if self.value.len() + c.len_utf8() > capacity {
+                    break;
+                }
+            }
+            let at = self.byte(self.cursor);
+            self.value.insert(at, c);
+            self.cursor += 1;
+        }
+    }
+
+    /// Applies an editing key. Returns whether the key was used, so tools
+    /// can fall through to their own shortcuts otherwise.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char('w') if ctrl => self.delete_to(self.word_start()),
+            KeyCode::Char('u') if ctrl => self.delete_to(0),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_str(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Backspace if ctrl || alt => self.delete_to(self.word_start()),
+            KeyCode::Backspace => {
+                if !self.delete_selection() {
+                    self.delete_to(self.cursor.saturating_sub(1));
+                }
+            }
+            KeyCode::Delete => {
+                if !self.delete_selection() {
+                    self.delete_to((self.cursor + 1).min(self.len()));
+                }
+            }
+            KeyCode::Left if ctrl => self.move_to(self.word_start(), shift),
+            KeyCode::Right if ctrl => self.move_to(self.word_end(), shift),
+            KeyCode::Left => self.move_to(self.cursor.saturating_sub(1), shift),
+            KeyCode::Right => self.move_to((self.cursor + 1).min(self.len()), shift),
+            KeyCode::Home => self.move_to(0, shift),
+            KeyCode::End => self.move_to(self.len(), shift),
+            KeyCode::Up if self.history_keys => return self.history_prev(),
+            KeyCode::Down if self.history_keys => return self.history_next(),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, block: Block, focused: bool) {
+        let block = focus::border(block, focused);
+        let inner = block.inner(area);
+        let shown: Vec<char> = if self.secret {
+            vec!['•'; self.len()]
+        } else {
+            self.value.chars().collect()
+        };
+        let offset = scroll_offset(&shown, self.cursor, inner.width as usize);
+        let selection = self.selection().filter(|_| focused);
+
+        let mut spans = Vec::new();
+        let mut run = String::new();
+        let mut run_selected = false;
+        for (i, c) in shown.iter().enumerate().skip(offset) {
+            let selected = selection.is_some_and(|(start, end)| (start..end).contains(&i));
+            if selected != run_selected && !run.is_empty() {
+                spans.push(Self::span(std::mem::take(&mut run), run_selected));
+            }
+            run_selected = selected;
+            run.push(*c);
+        }
+        if !run.is_empty() {
+            spans.push(Self::span(run, run_selected));
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)).block(block), area);
+
+        if focused && inner.width > 0 && inner.height > 0 {
+            let x: usize = shown[offset..self.cursor]
+                .iter()
+                .map(|c| c.width().unwrap_or(0))
+                .sum();
+            f.set_cursor_position((inner.x + x as u16, inner.y));
+        }
+    }
+
+    fn span(text: String, selected: bool) -> Span<'static> {
+        if selected {
+            Span::styled(text, Style::default().bg(Color::Blue).fg(Color::White))
+        } else {
+            Span::raw(text)
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    /// Byte offset of the char at `index`.
+    fn byte(&self, index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(index)
+            .map_or(self.value.len(), |(at, _)| at)
+    }
+
+    fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.anchor.filter(|&a| a != self.cursor)?;
+        Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+    }
+
+    fn move_to(&mut self, index: usize, extend: bool) {
+        if extend {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = index;
+    }
+
+    /// Deletes between the cursor and `index`.
+    fn delete_to(&mut self, index: usize) {
+        if self.delete_selection() {
+            return;
+        }
+        let (start, end) = (index.min(self.cursor), index.max(self.cursor));
+        let range = self.byte(start)..self.byte(end);
+        self.value.replace_range(range, "");
+        self.cursor = start;
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let Some((start, end)) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        let range = self.byte(start)..self.byte(end);
+        self.value.replace_range(range, "");
+        self.cursor = start;
+        self.anchor = None;
+        true
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    fn history_prev(&mut self) -> bool {
+        let pos = match self.history_pos {
+            _ if self.history.is_empty() => return false,
+            None => {
+                self.draft = self.value.clone();
+                self.history.len() - 1
+            }
+            Some(pos) => pos.saturating_sub(1),
+        };
+        self.recall(Some(pos));
+        true
+    }
+
+    fn history_next(&mut self) -> bool {
+        match self.history_pos {
+            None => return false,
+            Some(pos) if pos + 1 < self.history.len() => self.recall(Some(pos + 1)),
+            Some(_) => self.recall(None),
+        }
+        true
+    }
+
+    fn recall(&mut self, pos: Option<usize>) {
+        let value = match pos {
+            Some(pos) => self.history[pos].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+        self.value = value;
+        self.cursor = self.len();
+        self.anchor = None;
+        self.history_pos = pos;
+    }
+}
+
+impl Drop for TextInput {
+    fn drop(&mut self) {
+        if self.secret {
+            self.value.zeroize();
+        }
+    }
+}
+
+/// The first visible char, so that the cursor at `cursor` stays within
+/// `width` columns, leaving room for it after the last char.
+fn scroll_offset(chars: &[char], cursor: usize, width: usize) -> usize {
+    let mut used = 1;
+    let mut offset = cursor.min(chars.len());
+    while offset > 0 {
+        let w = chars[offset - 1].width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        input.insert_str(text);
+        input
+    }
+
+    #[test]
+    fn test_cursor_editing_and_word_deletion() {
+        let mut input = typed("hello big world");
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::CONTROL));
+        input.handle_key(&key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "hello world");
+        input.handle_key(&key(KeyCode::Home, KeyModifiers::NONE));
+        input.handle_key(&key(KeyCode::Delete, KeyModifiers::NONE));
+        input.handle_key(&key(KeyCode::Char('J'), KeyModifiers::SHIFT));
+        assert_eq!(input.value(), "Jello world");
+        input.handle_key(&key(KeyCode::End, KeyModifiers::NONE));
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::NONE));
+        input.handle_key(&key(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "d");
+        assert!(!input.handle_key(&key(KeyCode::Enter, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn test_selection_and_paste() {
+        let mut input = typed("ab€cd");
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::NONE));
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::SHIFT));
+        input.handle_key(&key(KeyCode::Left, KeyModifiers::SHIFT));
+        assert_eq!(input.selected_text(), Some("€c"));
+        input.insert_str("X\tY\n");
+        assert_eq!(input.value(), "abX Yd");
+
+        let mut secret = TextInput::secret(6);
+        secret.insert_str("ghp_ abc\n");
+        assert_eq!(secret.value(), "ghp_ab");
+        secret.commit();
+        assert!(secret.history.is_empty());
+    }
+
+    #[test]
+    fn test_history_recall_keeps_draft() {
+        let mut input = TextInput::new().with_history();
+        for value in ["first", "second", "second"] {
+            input.set_value(value);
+            input.commit();
+        }
+        input.set_value("draft");
+        let up = key(KeyCode::Up, KeyModifiers::NONE);
+        let down = key(KeyCode::Down, KeyModifiers::NONE);
+        input.handle_key(&up);
+        assert_eq!(input.value(), "second");
+        input.handle_key(&up);
+        input.handle_key(&up);
+        assert_eq!(input.value(), "first");
+        input.handle_key(&down);
+        input.handle_key(&down);
+        assert_eq!(input.value(), "draft");
+        assert!(!input.handle_key(&down));
+    }
+
+    #[test]
+    fn test_debug_hides_secret_values() {
+        let mut secret = TextInput::secret(64);
+        secret.set_value("ghp_example");
+        let debug = format!("{:?}", secret);
+        assert!(!debug.contains("ghp_example"));
+        assert!(debug.contains("11 bytes hidden"));
+        assert!(format!("{:?}", typed("visible")).contains("\"visible\""));
+    }
+
+    #[test]
+    fn test_scroll_offset_keeps_cursor_visible() {
+        let chars: Vec<char> = "abcdefghij".chars().collect();
+        assert_eq!(scroll_offset(&chars, 3, 5), 0);
+        assert_eq!(scroll_offset(&chars, 10, 5), 6);
+        let wide: Vec<char> = "日本語".chars().collect();
+        assert_eq!(scroll_offset(&wide, 3, 5), 1);
+    }
+}
@@ -0,0 +1,479 @@
+use super::components::TextInput;
+use super::focus;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use unicode_width::UnicodeWidthChar;
+
+/// A multi-line text editor with line numbers, soft wrap, vertical
+/// scrolling, incremental search and optional JSON highlighting.
+///
+/// The text is kept as one `String` with `\n` line breaks; the cursor is a
+/// byte offset that always sits on a char boundary. Editing keys match
+/// [`TextInput`] where they make sense on several lines.
+#[derive(Debug, Default)]
+pub struct Editor {
+    value: String,
+    cursor: usize,
+    json: bool,
+    search: Option<Search>,
+    /// First visible row, adjusted while rendering so the cursor stays in
+    /// view; rendering only gets `&self`.
+    scroll: AtomicUsize,
+    /// Rows shown in the last frame, the distance PageUp/PageDown move.
+    page: AtomicUsize,
+}
+
+/// The find prompt, while it is open.
+#[derive(Debug)]
+struct Search {
+    query: TextInput,
+    /// Where the cursor was when the prompt opened; typing searches from
+    /// there, so refining the query does not skip ahead.
+    origin: usize,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Colors JSON keys, strings, numbers and literals.
+    pub fn with_json_highlighting(mut self) -> Self {
+        self.json = true;
+        self
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Replaces the text and moves the cursor to its end.
+    pub fn set_value(&mut self, value: &str) {
+        self.clear();
+        self.insert_str(value);
+    }
+
+    pub fn clear(&mut self) {
+        self.value.clear();
+        self.cursor = 0;
+        self.search = None;
+        self.scroll.store(0, Ordering::Relaxed);
+    }
+
+    /// Whether the find prompt is open and takes the keys.
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Inserts text at the cursor, e.g. a paste. `\r\n` and `\r` line
+    /// breaks become `\n`.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.value.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Applies an editing key. Returns whether the key was used, so tools
+    /// can fall through to their own shortcuts otherwise. While the find
+    /// prompt is open it takes every key.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        if self.search.is_some() {
+            self.handle_search_key(key);
+            return true;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('g') if ctrl => {
+                self.search = Some(Search {
+                    query: TextInput::new(),
+                    origin: self.cursor,
+                })
+            }
+            KeyCode::Char('w') if ctrl => self.delete_to(self.word_start()),
+            KeyCode::Char('u') if ctrl => self.delete_to(self.line_start(self.cursor)),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_str(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Enter => self.insert_str("\n"),
+            KeyCode::Backspace if ctrl || alt => self.delete_to(self.word_start()),
+            KeyCode::Backspace => self.delete_to(self.prev_char(self.cursor)),
+            KeyCode::Delete => self.delete_to(self.next_char(self.cursor)),
+            KeyCode::Left if ctrl => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.prev_char(self.cursor),
+            KeyCode::Right => self.cursor = self.next_char(self.cursor),
+            // Alt+Up/Down are left to the tool's result list.
+            KeyCode::Up if !alt => self.move_lines(-1),
+            KeyCode::Down if !alt => self.move_lines(1),
+            KeyCode::PageUp => self.move_lines(-(self.page_size() as isize)),
+            KeyCode::PageDown => self.move_lines(self.page_size() as isize),
+            KeyCode::Home if ctrl => self.cursor = 0,
+            KeyCode::End if ctrl => self.cursor = self.value.len(),
+            KeyCode::Home if !alt => self.cursor = self.line_start(self.cursor),
+            KeyCode::End if !alt => self.cursor = self.line_end(self.cursor),
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_search_key(&mut self, key: &KeyEvent) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.search = None,
+            KeyCode::Enter => {
+                let from = self.next_char(self.cursor);
+                if let Some(at) = self.find(from) {
+                    self.cursor = at;
+                }
+            }
+            _ if search.query.handle_key(key) => {
+                let origin = search.origin;
+                self.cursor = self.find(origin).unwrap_or(origin);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, block: Block, focused: bool) {
+        let block = focus::border(block, focused);
+        let mut inner = block.inner(area);
+        f.render_widget(block, area);
+
+        if let Some(search) = &self.search {
+            if inner.height < 2 {
+                return;
+            }
+            inner.height -= 1;
+            let prompt = Rect {
+                y: inner.y + inner.height,
+                height: 1,
+                ..inner
+            };
+            let label = format!("Find ({} found): ", self.matches().len());
+            let [label_area, query_area] = Layout::horizontal([
+                Constraint::Length(label.chars().count() as u16),
+                Constraint::Min(0),
+            ])
+            .areas(prompt);
+            f.render_widget(
+                Paragraph::new(Span::styled(label, Style::default().fg(Color::Yellow))),
+                label_area,
+            );
+            search
+                .query
+                .render(f, query_area, Block::default(), focused);
+        }
+
+        let line_count = self.value.split('\n').count();
+        let gutter = line_count.to_string().len() + 1;
+        let width = (inner.width as usize).saturating_sub(gutter);
+        let height = inner.height as usize;
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.page.store(height, Ordering::Relaxed);
+
+        let matches = self.matches();
+        let mut rows: Vec<Line> = Vec::new();
+        let mut cursor_at = (0, 0);
+        let mut line_start = 0;
+        for (number, line) in self.value.split('\n').enumerate() {
+            let styles = if self.json {
+                json_styles(line)
+            } else {
+                vec![Style::default(); line.chars().count()]
+            };
+            let mut spans = vec![Span::styled(
+                format!("{:>1$} ", number + 1, gutter - 1),
+                Style::default().fg(Color::DarkGray),
+            )];
+            let mut used = 0;
+            for ((at, c), style) in line.char_indices().zip(styles) {
+                let at = line_start + at;
+                let w = c.width().unwrap_or(1);
+                if used + w > width {
+                    rows.push(Line::from(std::mem::take(&mut spans)));
+                    spans.push(Span::raw(" ".repeat(gutter)));
+                    used = 0;
+                }
+                if at == self.cursor {
+                    cursor_at = (rows.len(), used);
+                }
+                let style = if matches.iter().any(|m| m.contains(&at)) {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                } else {
+                    style
+                };
+                // Tabs and other control characters would break the layout.
+                let shown = if c.is_control() { ' ' } else { c };
+                spans.push(Span::styled(shown.to_string(), style));
+                used += w;
+            }
+            let line_end = line_start + line.len();
+            if self.cursor == line_end {
+                cursor_at = (rows.len(), used.min(width - 1));
+            }
+            rows.push(Line::from(spans));
+            line_start = line_end + 1;
+        }
+
+        let (row, x) = cursor_at;
+        let mut scroll = self.scroll.load(Ordering::Relaxed);
+        if row < scroll {
+            scroll = row;
+        } else if row >= scroll + height {
+            scroll = row + 1 - height;
+        }
+        self.scroll.store(scroll, Ordering::Relaxed);
+
+        let visible: Vec<Line> = rows.into_iter().skip(scroll).take(height).collect();
+        f.render_widget(Paragraph::new(visible), inner);
+
+        if focused && self.search.is_none() {
+            f.set_cursor_position((
+                inner.x + (gutter + x) as u16,
+                inner.y + (row - scroll) as u16,
+            ));
+        }
+    }
+
+    fn page_size(&self) -> usize {
+        self.page.load(Ordering::Relaxed).max(1)
+    }
+
+    /// Byte ranges of every case-insensitive match of the search query.
+    fn matches(&self) -> Vec<std::ops::Range<usize>> {
+        let Some(query) = self.search_query() else {
+            return Vec::new();
+        };
+        // ASCII lowercasing keeps byte offsets intact.
+        self.value
+            .to_ascii_lowercase()
+            .match_indices(&query)
+            .map(|(at, found)| at..at + found.len())
+            .collect()
+    }
+
+    /// The first match at or after `from`, wrapping around to the start.
+    fn find(&self, from: usize) -> Option<usize> {
+        let query = self.search_query()?;
+        let haystack = self.value.to_ascii_lowercase();
+        haystack[from..]
+            .find(&query)
+            .map(|at| from + at)
+            .or_else(|| haystack.find(&query))
+    }
+
+    fn search_query(&self) -> Option<String> {
+        let query = self.search.as_ref()?.query.value();
+        (!query.is_empty()).then(|| query.to_ascii_lowercase())
+    }
+
+    fn line_start(&self, at: usize) -> usize {
+        self.value[..at].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(&self, at: usize) -> usize {
+        self.value[at..]
+            .find('\n')
+            .map_or(self.value.len(), |i| at + i)
+    }
+
+    fn prev_char(&self, at: usize) -> usize {
+        self.value[..at]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_char(&self, at: usize) -> usize {
+        self.value[at..]
+            .chars()
+            .next()
+            .map_or(at, |c| at + c.len_utf8())
+    }
+
+    /// Moves the cursor by whole lines, keeping its column where the target
+    /// line is long enough.
+    fn move_lines(&mut self, lines: isize) {
+        let mut start = self.line_start(self.cursor);
+        let column = self.value[start..self.cursor].chars().count();
+        for _ in 0..lines.unsigned_abs() {
+            if lines < 0 {
+                if start == 0 {
+                    break;
+                }
+                start = self.line_start(start - 1);
+            } else {
+                let end = self.line_end(start);
+                if end == self.value.len() {
+                    break;
+                }
+                start = end + 1;
+            }
+        }
+        let line = &self.value[start..self.line_end(start)];
+        self.cursor = start
+            + line
+                .char_indices()
+                .nth(column)
+                .map_or(line.len(), |(i, _)| i);
+    }
+
+    /// Deletes between the cursor and `at`.
+    fn delete_to(&mut self, at: usize) {
+        let (start, end) = (at.min(self.cursor), at.max(self.cursor));
+        self.value.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn word_start(&self) -> usize {
+        let mut at = self.cursor;
+        let mut before = self.value[..at].chars().rev().peekable();
+        while let Some(c) = before.next_if(|c| c.is_whitespace()) {
+            at -= c.len_utf8();
+        }
+        while let Some(c) = before.next_if(|c| !c.is_whitespace()) {
+            at -= c.len_utf8();
+        }
+        at
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn word_end(&self) -> usize {
+        let mut at = self.cursor;
+        let mut after = self.value[at..].chars().peekable();
+        while let Some(c) = after.next_if(|c| c.is_whitespace()) {
+            at += c.len_utf8();
+        }
+        while let Some(c) = after.next_if(|c| !c.is_whitespace()) {
+            at += c.len_utf8();
+        }
+        at
+    }
+}
+
+/// Per-char styles for one line of JSON. Lines are colored on their own, so
+/// a string spanning lines is only colored on its first line.
+fn json_styles(line: &str) -> Vec<Style> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut styles = vec![Style::default(); chars.len()];
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let color = match chars[i] {
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                let is_key = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+                if is_key {
+                    Color::Cyan
+                } else {
+                    Color::Green
+                }
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || "+-.eE".contains(chars[i])) {
+                    i += 1;
+                }
+                Color::Yellow
+            }
+            c if c.is_ascii_alphabetic() => {
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                if !matches!(word.as_str(), "true" | "false" | "null") {
+                    continue;
+                }
+                Color::Magenta
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        styles[start..i].fill(Style::default().fg(color));
+    }
+    styles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(editor: &mut Editor, code: KeyCode, modifiers: KeyModifiers) {
+        editor.handle_key(&KeyEvent::new(code, modifiers));
+    }
+
+    #[test]
+    fn test_editing_across_lines() {
+        let mut editor = Editor::new();
+        editor.insert_str("first line\r\nsecond\nthird line");
+        press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, "first line\nsecond".len());
+        press(&mut editor, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, "first ".len());
+
+        press(&mut editor, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(editor.value(), "first linesecond\nthird line");
+        press(&mut editor, KeyCode::Enter, KeyModifiers::NONE);
+        press(&mut editor, KeyCode::End, KeyModifiers::CONTROL);
+        press(&mut editor, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        press(&mut editor, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(editor.value(), "first line\nsecond\n");
+        assert!(!editor.handle_key(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn test_search_is_case_insensitive_and_wraps() {
+        let mut editor = Editor::new();
+        editor.set_value("Needle\nhay needle\nhay");
+        press(&mut editor, KeyCode::Char('g'), KeyModifiers::CONTROL);
+        for c in "NEED".chars() {
+            press(&mut editor, KeyCode::Char(c), KeyModifiers::SHIFT);
+        }
+        assert_eq!(editor.matches().len(), 2);
+        assert_eq!(editor.cursor, 0);
+        press(&mut editor, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, "Needle\nhay ".len());
+        press(&mut editor, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(editor.cursor, 0);
+        press(&mut editor, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(!editor.is_searching());
+        assert_eq!(editor.value(), "Needle\nhay needle\nhay");
+    }
+
+    #[test]
+    fn test_json_styles() {
+        let styles = json_styles(r#"{"key": "va\"l", "n": -1.5e3, "ok": true}"#);
+        let color = |i: usize| styles[i].fg;
+        assert_eq!(color(0), None);
+        assert_eq!(color(1), Some(Color::Cyan));
+        assert_eq!(color(9), Some(Color::Green));
+        assert_eq!(color(14), Some(Color::Green));
+        assert_eq!(color(15), None);
+        assert_eq!(color(23), Some(Color::Yellow));
+        assert_eq!(color(37), Some(Color::Magenta));
+    }
+}
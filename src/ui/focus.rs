@@ -0,0 +1,81 @@
+use ratatui::{prelude::*, widgets::Block};
+
+/// Which of a tool's widgets receives keys. `Tab` and `Shift+Tab` move
+/// through `order`, wrapping around at either end.
+///
+/// Tools list their widgets as a small `Copy` enum, so a tool with a text
+/// field and a result list might hold `Focus::new(&[Field::Query,
+/// Field::Results])`.
+#[derive(Debug, Clone)]
+pub struct Focus<T: 'static> {
+    order: &'static [T],
+    index: usize,
+}
+
+impl<T: Copy + PartialEq> Focus<T> {
+    /// Starts on the first widget of `order`, which must not be empty.
+    pub fn new(order: &'static [T]) -> Self {
+        assert!(!order.is_empty(), "a tool needs at least one widget");
+        Self { order, index: 0 }
+    }
+
+    pub fn current(&self) -> T {
+        self.order[self.index]
+    }
+
+    pub fn is(&self, widget: T) -> bool {
+        self.current() == widget
+    }
+
+    /// Focuses `widget`, e.g. after a shortcut that jumps to it. Widgets
+    /// not in the order are ignored.
+    pub fn set(&mut self, widget: T) {
+        if let Some(index) = self.order.iter().position(|&w| w == widget) {
+            self.index = index;
+        }
+    }
+
+    /// Moves to the next widget, or the previous one when `backward`.
+    pub fn cycle(&mut self, backward: bool) {
+        let len = self.order.len();
+        self.index = if backward {
+            (self.index + len - 1) % len
+        } else {
+            (self.index + 1) % len
+        };
+    }
+}
+
+/// Highlights the border of `block` when its widget has focus.
+pub fn border(block: Block<'_>, focused: bool) -> Block<'_> {
+    if focused {
+        block.border_style(Style::default().fg(Color::Yellow))
+    } else {
+        block
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Field {
+        Query,
+        Toggle,
+        Results,
+    }
+
+    #[test]
+    fn test_cycle_wraps_both_ways() {
+        let mut focus = Focus::new(&[Field::Query, Field::Toggle, Field::Results]);
+        assert!(focus.is(Field::Query));
+        focus.cycle(true);
+        assert_eq!(focus.current(), Field::Results);
+        focus.cycle(false);
+        focus.cycle(false);
+        assert_eq!(focus.current(), Field::Toggle);
+        focus.set(Field::Results);
+        assert!(focus.is(Field::Results));
+    }
+}
@@ -0,0 +1,265 @@
+use super::focus;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use unicode_width::UnicodeWidthChar;
+
+/// Rows moved by one notch of the mouse wheel.
+const WHEEL_ROWS: usize = 3;
+
+/// A scrollable list of rows with a selected row and a scrollbar, for result
+/// areas that may not fit on screen.
+///
+/// The rows themselves are passed in on every render, so tools keep their
+/// data as it is and only hold this for the selection and scroll position.
+/// Long rows wrap rather than being cut off.
+#[derive(Debug, Default)]
+pub struct ScrollList {
+    selected: usize,
+    /// First visible row, adjusted while rendering so the selection stays in
+    /// view; rendering only gets `&self`.
+    offset: AtomicUsize,
+    /// Row count and rows shown in the last frame, for clamping and paging.
+    len: AtomicUsize,
+    page: AtomicUsize,
+    /// Where the last frame was drawn, so mouse events can be matched to it.
+    drawn: Mutex<Drawn>,
+}
+
+#[derive(Debug, Default)]
+struct Drawn {
+    area: Rect,
+    /// The row shown on each screen line inside the border.
+    rows: Vec<usize>,
+}
+
+impl ScrollList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Selects `index`, e.g. when the tool moves the selection itself.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index;
+    }
+
+    /// Goes back to the first row, for when the content is replaced.
+    pub fn reset(&mut self) {
+        self.selected = 0;
+        self.offset.store(0, Ordering::Relaxed);
+    }
+
+    /// Moves the selection by `rows`, staying within the rows of the last
+    /// frame.
+    pub fn scroll(&mut self, rows: isize) {
+        let last = self.len.load(Ordering::Relaxed).saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(rows).min(last);
+    }
+
+    /// The plain text of the selected row, for copying.
+    pub fn selected_text(&self, rows: &[Line]) -> Option<String> {
+        let row = rows.get(self.selected)?;
+        Some(row.spans.iter().map(|span| span.content.as_ref()).collect())
+    }
+
+    /// Handles `PageUp`/`PageDown` and `Alt+Up`/`Alt+Down`, plus
+    /// `Alt+Home`/`Alt+End`. Returns whether the key was used.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let page = self.page.load(Ordering::Relaxed).max(1) as isize;
+        match key.code {
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            KeyCode::Up if alt => self.scroll(-1),
+            KeyCode::Down if alt => self.scroll(1),
+            KeyCode::Home if alt => self.selected = 0,
+            KeyCode::End if alt => self.scroll(isize::MAX),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Like [`handle_key`](Self::handle_key), for when the list has focus:
+    /// plain `Up`/`Down` and `Home`/`End` move the selection as well.
+    pub fn handle_focused_key(&mut self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Up => self.scroll(-1),
+            KeyCode::Down => self.scroll(1),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.scroll(isize::MAX),
+            _ => return self.handle_key(key),
+        }
+        true
+    }
+
+    /// Scrolls with the wheel and selects clicked rows when the pointer is
+    /// over the list. Returns whether the event was used.
+    pub fn handle_mouse(&mut self, mouse: &MouseEvent) -> bool {
+        let clicked = {
+            let drawn = self.drawn.lock().unwrap();
+            if !drawn.area.contains(Position::new(mouse.column, mouse.row)) {
+                return false;
+            }
+            let line = mouse.row.saturating_sub(drawn.area.y + 1) as usize;
+            drawn.rows.get(line).copied()
+        };
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll(-(WHEEL_ROWS as isize)),
+            MouseEventKind::ScrollDown => self.scroll(WHEEL_ROWS as isize),
+            MouseEventKind::Down(MouseButton::Left) => match clicked {
+                Some(row) => self.selected = row,
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Draws `rows` inside `block` with a scrollbar on the right border if
+    /// they do not fit. The selected row is highlighted, more strongly when
+    /// the list has focus.
+    pub fn render(&self, f: &mut Frame, area: Rect, block: Block, rows: Vec<Line>, focused: bool) {
+        let block = focus::border(block, focused);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let width = inner.width as usize;
+        let height = inner.height as usize;
+        self.len.store(rows.len(), Ordering::Relaxed);
+        self.page.store(height, Ordering::Relaxed);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let selected = self.selected.min(rows.len().saturating_sub(1));
+        let wrapped: Vec<Vec<Line>> = rows.into_iter().map(|row| wrap(row, width)).collect();
+        let mut offset = self.offset.load(Ordering::Relaxed).min(selected);
+        while offset < selected && lines_between(&wrapped, offset, selected) > height {
+            offset += 1;
+        }
+        self.offset.store(offset, Ordering::Relaxed);
+
+        let mut lines = Vec::new();
+        let mut line_rows = Vec::new();
+        for (index, row) in wrapped.iter().enumerate().skip(offset) {
+            for line in row {
+                if lines.len() == height {
+                    break;
+                }
+                let line = line.clone();
+                lines.push(match (index == selected, focused) {
+                    (true, true) => {
+                        line.patch_style(Style::default().bg(Color::Blue).fg(Color::White).bold())
+                    }
+                    (true, false) => line.patch_style(Style::default().bg(Color::DarkGray)),
+                    (false, _) => line,
+                });
+                line_rows.push(index);
+            }
+        }
+        f.render_widget(Paragraph::new(lines), inner);
+        *self.drawn.lock().unwrap() = Drawn {
+            area,
+            rows: line_rows,
+        };
+
+        let total = lines_between(&wrapped, 0, wrapped.len().saturating_sub(1));
+        if total > height {
+            let mut state = ScrollbarState::new(wrapped.len()).position(selected);
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 0,
+                }),
+                &mut state,
+            );
+        }
+    }
+}
+
+/// Screen lines taken by rows `from..=to`.
+fn lines_between(wrapped: &[Vec<Line>], from: usize, to: usize) -> usize {
+    wrapped
+        .get(from..=to)
+        .map_or(0, |rows| rows.iter().map(Vec::len).sum())
+}
+
+/// Splits a row into lines of at most `width` columns, keeping span styles.
+fn wrap<'a>(row: Line<'a>, width: usize) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::default().style(row.style)];
+    let mut used = 0;
+    for span in row.spans {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > width && used > 0 {
+                if !text.is_empty() {
+                    let part = Span::styled(std::mem::take(&mut text), span.style);
+                    lines.last_mut().unwrap().spans.push(part);
+                }
+                lines.push(Line::default().style(row.style));
+                used = 0;
+            }
+            text.push(c);
+            used += w;
+        }
+        if !text.is_empty() {
+            lines
+                .last_mut()
+                .unwrap()
+                .spans
+                .push(Span::styled(text, span.style));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_keeps_styles() {
+        let row = Line::from(vec![
+            Span::raw("abcd"),
+            Span::styled("efg", Style::default().fg(Color::Red)),
+        ]);
+        let lines = wrap(row, 3);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1].spans[0].content, "d");
+        assert_eq!(lines[1].spans[1].content, "ef");
+        assert_eq!(lines[1].spans[1].style.fg, Some(Color::Red));
+        assert_eq!(wrap(Line::from(""), 3).len(), 1);
+    }
+
+    #[test]
+    fn test_selection_stays_within_rows() {
+        let mut list = ScrollList::new();
+        list.len.store(10, Ordering::Relaxed);
+        list.page.store(4, Ordering::Relaxed);
+        list.handle_key(&KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE));
+        assert_eq!(list.selected(), 4);
+        list.handle_key(&KeyEvent::new(KeyCode::End, KeyModifiers::ALT));
+        assert_eq!(list.selected(), 9);
+        list.handle_key(&KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        list.handle_key(&KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        list.handle_key(&KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        assert_eq!(list.selected(), 0);
+        assert!(!list.handle_key(&KeyEvent::new(KeyCode::Up, KeyModifiers::NONE)));
+
+        let rows = vec![
+            Line::from("first"),
+            Line::from(vec![Span::raw("a"), Span::raw("b")]),
+        ];
+        list.select(1);
+        assert_eq!(list.selected_text(&rows).as_deref(), Some("ab"));
+    }
+}
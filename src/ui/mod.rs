@@ -1 +1,5 @@
 pub mod components;
+pub mod editor;
+pub mod focus;
+pub mod list;
+pub mod palette;
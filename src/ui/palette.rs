@@ -0,0 +1,368 @@
+use super::components::TextInput;
+use super::list::ScrollList;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// Entries remembered as recently used, by title.
+const RECENT_LIMIT: usize = 20;
+
+/// Something the command palette can run.
+#[derive(Debug, Clone)]
+pub struct Entry<T> {
+    pub title: String,
+    /// Shown dimmed after the title, e.g. the entry's shortcut.
+    pub hint: String,
+    /// Asked for after the entry is picked; the answer is returned with it.
+    pub prompt: Option<&'static str>,
+    pub target: T,
+}
+
+/// A popup listing everything the app can do, filtered by a fuzzy query.
+/// Recently used entries rank first.
+#[derive(Debug)]
+pub struct Palette<T> {
+    open: bool,
+    entries: Vec<Entry<T>>,
+    query: TextInput,
+    /// Indices into `entries` that match the query, best first, with the
+    /// positions of the matched characters.
+    matches: Vec<(usize, Vec<usize>)>,
+    list: ScrollList,
+    /// Titles of the entries run, most recent first. Kept across openings,
+    /// as the entries themselves are rebuilt each time.
+    recent: Vec<String>,
+    /// The entry whose argument is being typed into `argument`.
+    prompting: Option<usize>,
+    argument: TextInput,
+}
+
+impl<T> Default for Palette<T> {
+    fn default() -> Self {
+        Self {
+            open: false,
+            entries: Vec::new(),
+            query: TextInput::new(),
+            matches: Vec::new(),
+            list: ScrollList::new(),
+            recent: Vec::new(),
+            prompting: None,
+            argument: TextInput::new().with_history(),
+        }
+    }
+}
+
+impl<T: Clone> Palette<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Shows the palette with `entries`, listed in the given order until a
+    /// query is typed, after the recently used ones.
+    pub fn open(&mut self, entries: Vec<Entry<T>>) {
+        self.entries = entries;
+        self.open = true;
+        self.prompting = None;
+        self.query.clear();
+        self.update();
+    }
+
+    /// Handles a key while the palette is open. Returns the picked entry's
+    /// target and the answer to its prompt, or `None` while still choosing.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> Option<(T, String)> {
+        if let Some(index) = self.prompting {
+            match key.code {
+                KeyCode::Esc => self.prompting = None,
+                KeyCode::Enter if !self.argument.is_empty() => {
+                    self.argument.commit();
+                    let argument = self.argument.value().to_string();
+                    return Some(self.run(index, argument));
+                }
+                _ => {
+                    self.argument.handle_key(key);
+                }
+            }
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Esc => self.open = false,
+            KeyCode::Enter => {
+                let &(index, _) = self.matches.get(self.list.selected())?;
+                if self.entries[index].prompt.is_none() {
+                    return Some(self.run(index, String::new()));
+                }
+                self.argument.clear();
+                self.prompting = Some(index);
+            }
+            KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown => {
+                self.list.handle_focused_key(key);
+            }
+            _ if self.query.handle_key(key) => self.update(),
+            _ => {}
+        }
+        None
+    }
+
+    /// Inserts pasted text into the query, or the argument while prompting.
+    pub fn insert_str(&mut self, text: &str) {
+        if self.prompting.is_some() {
+            self.argument.insert_str(text);
+        } else {
+            self.query.insert_str(text);
+            self.update();
+        }
+    }
+
+    /// Closes the palette and moves the entry to the top of the recent ones.
+    fn run(&mut self, index: usize, argument: String) -> (T, String) {
+        let title = &self.entries[index].title;
+        self.recent.retain(|recent| recent != title);
+        self.recent.insert(0, title.clone());
+        self.recent.truncate(RECENT_LIMIT);
+        self.open = false;
+        self.prompting = None;
+        (self.entries[index].target.clone(), argument)
+    }
+
+    /// Ranks the entries against the query. Recent use adds a bonus smaller
+    /// than a contiguous match, so it only reorders similar matches.
+    fn update(&mut self) {
+        let query = self.query.value();
+        let mut ranked: Vec<(i64, usize, Vec<usize>)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let (score, positions) = fuzzy_match(query, &entry.title)?;
+                let bonus = self
+                    .recent
+                    .iter()
+                    .position(|recent| *recent == entry.title)
+                    .map_or(0, |rank| (RECENT_LIMIT - rank) as i64);
+                Some((score + bonus, index, positions))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.matches = ranked
+            .into_iter()
+            .map(|(_, index, positions)| (index, positions))
+            .collect();
+        self.list.reset();
+    }
+
+    /// Draws the palette centered over `area`.
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let width = 72.min(area.width);
+        let height = 18.min(area.height);
+        let popup = Rect::new(
+            area.x + area.width.saturating_sub(width) / 2,
+            area.y + area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        );
+        f.render_widget(Clear, popup);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(popup);
+
+        let (input, title) = match self.prompting.map(|index| &self.entries[index]) {
+            Some(entry) => (
+                &self.argument,
+                format!(
+                    "{}: {} (Enter: Run, Esc: Back)",
+                    entry.title,
+                    entry.prompt.unwrap_or_default()
+                ),
+            ),
+            None => (
+                &self.query,
+                "Command Palette (Enter: Run, Esc: Cancel)".to_string(),
+            ),
+        };
+        input.render(
+            f,
+            chunks[0],
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Span::styled(title, Style::default().fg(Color::Green))),
+            true,
+        );
+
+        let block = Block::default().borders(Borders::ALL);
+        if self.matches.is_empty() {
+            f.render_widget(
+                Paragraph::new("No matching commands.").block(block),
+                chunks[1],
+            );
+            return;
+        }
+        let inner_width = chunks[1].width.saturating_sub(2) as usize;
+        let rows = self
+            .matches
+            .iter()
+            .map(|(index, positions)| row(&self.entries[*index], positions, inner_width))
+            .collect();
+        self.list
+            .render(f, chunks[1], block, rows, self.prompting.is_none());
+    }
+}
+
+/// An entry's title with the matched characters highlighted and its hint
+/// right-aligned within `width`.
+fn row<'a, T>(entry: &'a Entry<T>, positions: &[usize], width: usize) -> Line<'a> {
+    let matched = Style::default().fg(Color::Yellow).bold();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in entry.title.chars().enumerate() {
+        let is_matched = positions.contains(&i);
+        if is_matched != run_matched && !run.is_empty() {
+            let text = std::mem::take(&mut run);
+            spans.push(if run_matched {
+                Span::styled(text, matched)
+            } else {
+                Span::raw(text)
+            });
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    spans.push(if run_matched {
+        Span::styled(run, matched)
+    } else {
+        Span::raw(run)
+    });
+
+    if !entry.hint.is_empty() {
+        let used = entry.title.chars().count() + entry.hint.chars().count();
+        let padding = width.saturating_sub(used + 1).max(2);
+        spans.push(Span::raw(" ".repeat(padding)));
+        spans.push(Span::styled(
+            entry.hint.as_str(),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Matches `query` against `text` as a case-insensitive subsequence,
+/// ignoring whitespace in the query. Returns a score, higher for contiguous
+/// runs and matches at word starts, and the matched character positions.
+/// An empty query matches everything with a score of zero.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(lower)
+        .collect();
+    let chars: Vec<char> = text.chars().collect();
+    let folded: Vec<char> = chars.iter().copied().map(lower).collect();
+    let word_start = |i: usize| {
+        i == 0
+            || !chars[i - 1].is_alphanumeric()
+            || (chars[i - 1].is_lowercase() && chars[i].is_uppercase())
+    };
+
+    // Prefers continuing the previous match, then the next word start.
+    // Jumping ahead to a word start can skip characters needed later, so
+    // fall back to taking each first occurrence.
+    let find = |prefer_word_starts: bool| {
+        let mut positions: Vec<usize> = Vec::with_capacity(query.len());
+        for &q in &query {
+            let from = positions.last().map_or(0, |&last| last + 1);
+            let next = (from..chars.len()).find(|&i| folded[i] == q)?;
+            let continues = next == from && !positions.is_empty();
+            let position = if prefer_word_starts && !continues {
+                (next..chars.len())
+                    .find(|&i| folded[i] == q && word_start(i))
+                    .unwrap_or(next)
+            } else {
+                next
+            };
+            positions.push(position);
+        }
+        Some(positions)
+    };
+    let positions = find(true).or_else(|| find(false))?;
+
+    let mut score = 0;
+    for (n, &position) in positions.iter().enumerate() {
+        score += 16;
+        if word_start(position) {
+            score += 16;
+        }
+        if n > 0 {
+            match position - positions[n - 1] - 1 {
+                0 => score += 24,
+                gap => score -= 2 * gap.min(8) as i64,
+            }
+        }
+    }
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(palette: &mut Palette<usize>, text: &str) {
+        for c in text.chars() {
+            palette.handle_key(&KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match_ranks_contiguous_and_word_starts() {
+        assert_eq!(fuzzy_match("", "Anything"), Some((0, vec![])));
+        assert_eq!(fuzzy_match("zz", "JWT Decoder: Decode"), None);
+        assert_eq!(
+            fuzzy_match("jd", "JWT Decoder: Decode").map(|(_, p)| p),
+            Some(vec![0, 4])
+        );
+        // A later word start is preferred, but not at the cost of a match.
+        assert_eq!(fuzzy_match("bc", "xbc B").map(|(_, p)| p), Some(vec![1, 2]));
+
+        let score = |query, text| fuzzy_match(query, text).unwrap().0;
+        assert!(score("vac", "Cache: Vacuum") > score("vac", "View all caches"));
+        assert!(score("hex", "Switch to Hex") > score("hex", "The Extra"));
+    }
+
+    #[test]
+    fn test_recent_entries_rank_first_and_prompts_collect_arguments() {
+        let mut palette = Palette::new();
+        let entries: Vec<Entry<usize>> = ["Go to Cache", "Cache: Vacuum", "Cache: Purge..."]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Entry {
+                title: title.to_string(),
+                hint: String::new(),
+                prompt: (i == 2).then_some("Age"),
+                target: i,
+            })
+            .collect();
+
+        palette.open(entries.clone());
+        type_text(&mut palette, "purge");
+        assert_eq!(palette.handle_key(&KeyEvent::from(KeyCode::Enter)), None);
+        assert!(palette.is_open());
+        type_text(&mut palette, "30d");
+        assert_eq!(
+            palette.handle_key(&KeyEvent::from(KeyCode::Enter)),
+            Some((2, "30d".to_string()))
+        );
+        assert!(!palette.is_open());
+
+        palette.open(entries);
+        let order: Vec<usize> = palette.matches.iter().map(|(index, _)| *index).collect();
+        assert_eq!(order, vec![2, 0, 1]);
+    }
+}
@@ -0,0 +1,199 @@
+use flate2::read::GzDecoder;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// A Unicode Character Database file the Unicode Inspector reads, with the
+/// gzip-compressed copy embedded at build time by `build.rs`.
+#[derive(Debug, Clone, Copy)]
+pub struct UcdFile {
+    pub name: &'static str,
+    embedded: &'static [u8],
+}
+
+pub const UNICODE_DATA: UcdFile = UcdFile {
+    name: "UnicodeData.txt",
+    embedded: include_bytes!(concat!(env!("OUT_DIR"), "/UnicodeData.txt.gz")),
+};
+
+pub const BLOCKS: UcdFile = UcdFile {
+    name: "Blocks.txt",
+    embedded: include_bytes!(concat!(env!("OUT_DIR"), "/Blocks.txt.gz")),
+};
+
+/// Where a UCD file was found.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataSource {
+    /// The path set in `config.toml`.
+    Config(PathBuf),
+    /// A copy in a data directory, e.g. one installed by `unicode update`.
+    DataDir(PathBuf),
+    /// The copy compiled into the binary.
+    Embedded,
+}
+
+impl fmt::Display for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataSource::Config(path) => write!(f, "{} (config)", path.display()),
+            DataSource::DataDir(path) => write!(f, "{}", path.display()),
+            DataSource::Embedded => write!(f, "embedded copy"),
+        }
+    }
+}
+
+/// Directory `unicode update` installs into: `$XDG_DATA_HOME/dev-toolbox/ucd`
+/// or the platform equivalent.
+pub fn user_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("dev-toolbox").join("ucd"))
+}
+
+/// Data directories searched after the configured path, in order: the user
+/// directory, then `dev-toolbox/ucd` and `unicode` (as installed by distro
+/// `unicode-data` packages) under each `$XDG_DATA_DIRS` entry.
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = user_data_dir().into_iter().collect();
+    if cfg!(unix) {
+        let system = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        for base in system.split(':').filter(|b| !b.is_empty()) {
+            dirs.push(Path::new(base).join("dev-toolbox").join("ucd"));
+            dirs.push(Path::new(base).join("unicode"));
+        }
+    }
+    dirs
+}
+
+/// Finds `file`: the configured path if it is set and exists, then the data
+/// directories, then the embedded copy.
+pub fn resolve(configured: &str, file: UcdFile) -> DataSource {
+    resolve_in(configured, file, &search_dirs())
+}
+
+fn resolve_in(configured: &str, file: UcdFile, dirs: &[PathBuf]) -> DataSource {
+    if !configured.is_empty() && Path::new(configured).is_file() {
+        return DataSource::Config(PathBuf::from(configured));
+    }
+    dirs.iter()
+        .map(|dir| dir.join(file.name))
+        .find(|path| path.is_file())
+        .map(DataSource::DataDir)
+        .unwrap_or(DataSource::Embedded)
+}
+
+/// Reads `file` from wherever [`resolve`] finds it.
+pub fn read(configured: &str, file: UcdFile) -> Result<(String, DataSource), Box<dyn Error>> {
+    let source = resolve(configured, file);
+    let content = match &source {
+        DataSource::Config(path) | DataSource::DataDir(path) => fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?,
+        DataSource::Embedded => {
+            let mut content = String::new();
+            GzDecoder::new(file.embedded).read_to_string(&mut content)?;
+            content
+        }
+    };
+    Ok((content, source))
+}
+
+/// Checks that `content` looks like `file`: at least one semicolon-separated
+/// record starting with a hex code point.
+fn validate(file: UcdFile, content: &str) -> Result<(), String> {
+    let valid = content
+        .lines()
+        .filter(|l| !l.starts_with('#') && !l.trim().is_empty())
+        .take(10)
+        .all(|l| {
+            l.split([';', '.'])
+                .next()
+                .is_some_and(|cp| u32::from_str_radix(cp.trim(), 16).is_ok())
+        });
+    if valid && content.lines().any(|l| l.contains(';')) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} does not look like a UCD {} file",
+            file.name, file.name
+        ))
+    }
+}
+
+/// Copies `UnicodeData.txt` and `Blocks.txt` from `from` into the user data
+/// directory after validating them. Returns the installed paths.
+pub fn install(from: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let target = user_data_dir().ok_or("Cannot determine the user data directory")?;
+    install_into(from, &target)
+}
+
+fn install_into(from: &Path, target: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for file in [UNICODE_DATA, BLOCKS] {
+        let path = from.join(file.name);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        validate(file, &content)?;
+        files.push((file, content));
+    }
+
+    fs::create_dir_all(target)?;
+    let mut installed = Vec::new();
+    for (file, content) in files {
+        let path = target.join(file.name);
+        fs::write(&path, content)?;
+        installed.push(path);
+    }
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_order_and_embedded_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let configured = dir.path().join("custom.txt");
+        fs::write(
+            &configured,
+            "0041;LATIN CAPITAL LETTER A;Lu;0;L;;;;;N;;;;0061;\n",
+        )
+        .unwrap();
+        let data_dir = dir.path().join("data");
+        fs::create_dir(&data_dir).unwrap();
+
+        let dirs = vec![data_dir.clone()];
+        assert_eq!(
+            resolve_in(configured.to_str().unwrap(), UNICODE_DATA, &dirs),
+            DataSource::Config(configured.clone())
+        );
+        assert_eq!(
+            resolve_in("missing.txt", UNICODE_DATA, &dirs),
+            DataSource::Embedded
+        );
+
+        install_into(Path::new(env!("CARGO_MANIFEST_DIR")), &data_dir).unwrap();
+        assert_eq!(
+            resolve_in("", BLOCKS, &dirs),
+            DataSource::DataDir(data_dir.join("Blocks.txt"))
+        );
+
+        let mut embedded = String::new();
+        GzDecoder::new(UNICODE_DATA.embedded)
+            .read_to_string(&mut embedded)
+            .unwrap();
+        assert!(embedded.starts_with("0000;<control>;"));
+    }
+
+    #[test]
+    fn test_install_rejects_non_ucd_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("UnicodeData.txt"), "<html>not found</html>").unwrap();
+        fs::write(dir.path().join("Blocks.txt"), "0000..007F; Basic Latin\n").unwrap();
+        assert!(install_into(dir.path(), &dir.path().join("out")).is_err());
+        assert!(!dir.path().join("out").exists());
+    }
+}
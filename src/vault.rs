@@ -0,0 +1,340 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use secrecy::{ExposeSecret, SecretBox};
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// File header: magic, format version, Argon2id parameters, salt and nonce.
+const MAGIC: &[u8; 8] = b"DTVAULT\0";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 12 + SALT_LEN + NONCE_LEN;
+
+/// OWASP-recommended Argon2id parameters: 19 MiB memory, 2 iterations.
+const KDF_MEMORY_KIB: u32 = 19 * 1024;
+const KDF_ITERATIONS: u32 = 2;
+const KDF_PARALLELISM: u32 = 1;
+
+/// Upper bounds on the Argon2id parameters read from a vault header. The
+/// header is not authenticated until the key is derived, so a corrupted or
+/// crafted file could otherwise demand terabytes of memory.
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 32;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+/// Names a file holding the vault passphrase, for unattended runs such as
+/// `dev-toolbox watch` under cron or systemd.
+pub const PASSPHRASE_FILE_ENV: &str = "DEV_TOOLBOX_VAULT_PASSPHRASE_FILE";
+
+pub struct VaultEntry {
+    pub name: String,
+    pub token: SecretBox<str>,
+}
+
+/// Passphrase-protected store of GitHub tokens. On disk the entries are
+/// encrypted with XChaCha20-Poly1305 under a key derived by Argon2id, so the
+/// file is both confidential and tamper-evident. Decrypted tokens only live
+/// in `SecretBox`es; intermediate buffers are zeroized.
+#[derive(Default)]
+pub struct Vault {
+    entries: Vec<VaultEntry>,
+}
+
+/// `vault.bin` next to `config.toml` in the OS config directory.
+pub fn default_path() -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("dev-toolbox");
+    path.push("vault.bin");
+    path
+}
+
+fn derive_key(
+    passphrase: &SecretBox<str>,
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<Zeroizing<[u8; 32]>, Box<dyn Error>> {
+    if memory_kib > MAX_KDF_MEMORY_KIB
+        || iterations > MAX_KDF_ITERATIONS
+        || parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err(format!(
+            "Vault KDF parameters exceed the supported maximum ({} KiB, {} iterations, {} lanes)",
+            memory_kib, iterations, parallelism
+        )
+        .into());
+    }
+    let params = Params::new(memory_kib, iterations, parallelism, Some(32))
+        .map_err(|e| format!("Invalid vault KDF parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.expose_secret().as_bytes(), salt, key.as_mut())
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().expect("4 bytes"))
+}
+
+impl Vault {
+    pub fn open(path: &Path, passphrase: &SecretBox<str>) -> Result<Self, Box<dyn Error>> {
+        let data =
+            fs::read(path).map_err(|e| format!("Cannot read vault {}: {}", path.display(), e))?;
+        Self::decrypt(&data, passphrase)
+    }
+
+    fn decrypt(data: &[u8], passphrase: &SecretBox<str>) -> Result<Self, Box<dyn Error>> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err("Not a dev-toolbox vault file".into());
+        }
+        let version = data[MAGIC.len()];
+        if version != FORMAT_VERSION {
+            return Err(format!("Unsupported vault format version {}", version).into());
+        }
+
+        let params = MAGIC.len() + 1;
+        let salt = params + 12;
+        let nonce = salt + SALT_LEN;
+        let key = derive_key(
+            passphrase,
+            &data[salt..nonce],
+            read_u32(data, params),
+            read_u32(data, params + 4),
+            read_u32(data, params + 8),
+        )?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    XNonce::from_slice(&data[nonce..HEADER_LEN]),
+                    &data[HEADER_LEN..],
+                )
+                .map_err(|_| "Wrong passphrase or corrupted vault")?,
+        );
+
+        let text = std::str::from_utf8(&plaintext).map_err(|_| "Corrupted vault contents")?;
+        let mut entries = Vec::new();
+        for line in text.lines().filter(|l| !l.is_empty()) {
+            let (name, token) = line.split_once('\t').ok_or("Corrupted vault contents")?;
+            entries.push(VaultEntry {
+                name: name.to_string(),
+                token: SecretBox::new(token.into()),
+            });
+        }
+        Ok(Vault { entries })
+    }
+
+    /// Encrypts the vault with a fresh salt and nonce and writes it with
+    /// owner-only permissions where the platform supports them.
+    pub fn save(&self, path: &Path, passphrase: &SecretBox<str>) -> Result<(), Box<dyn Error>> {
+        let data = self.encrypt(passphrase)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp = path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn encrypt(&self, passphrase: &SecretBox<str>) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = derive_key(
+            passphrase,
+            &salt,
+            KDF_MEMORY_KIB,
+            KDF_ITERATIONS,
+            KDF_PARALLELISM,
+        )?;
+
+        let mut plaintext = Zeroizing::new(String::new());
+        for entry in &self.entries {
+            plaintext.push_str(&entry.name);
+            plaintext.push('\t');
+            plaintext.push_str(entry.token.expose_secret());
+            plaintext.push('\n');
+        }
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+        let ciphertext = cipher
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| "Vault encryption failed")?;
+
+        let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        data.extend_from_slice(MAGIC);
+        data.push(FORMAT_VERSION);
+        data.extend_from_slice(&KDF_MEMORY_KIB.to_le_bytes());
+        data.extend_from_slice(&KDF_ITERATIONS.to_le_bytes());
+        data.extend_from_slice(&KDF_PARALLELISM.to_le_bytes());
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+        Ok(data)
+    }
+
+    pub fn entries(&self) -> &[VaultEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SecretBox<str>> {
+        self.entries
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| &e.token)
+    }
+
+    /// Adds a token, or replaces it when `replace` is set. Returns an error if
+    /// the name is taken (and `replace` is not set) or either value is invalid.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        token: SecretBox<str>,
+        replace: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(format!("Invalid credential name: '{}'", name).into());
+        }
+        let value = token.expose_secret();
+        if value.is_empty() || value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err("Token must be non-empty and contain no whitespace".into());
+        }
+
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(entry) if replace => entry.token = token,
+            Some(_) => {
+                return Err(format!(
+                    "'{}' is already in the vault; use `secrets rotate {}`",
+                    name, name
+                )
+                .into())
+            }
+            None if replace => return Err(format!("'{}' is not in the vault", name).into()),
+            None => self.entries.push(VaultEntry {
+                name: name.to_string(),
+                token,
+            }),
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.name != name);
+        self.entries.len() != before
+    }
+}
+
+/// Reads a secret from the terminal without echoing it.
+pub fn prompt_secret(prompt: &str) -> Result<SecretBox<str>, Box<dyn Error>> {
+    let value = Zeroizing::new(rpassword::prompt_password(prompt)?);
+    Ok(SecretBox::new(value.trim().into()))
+}
+
+/// The passphrase from the file named by `DEV_TOOLBOX_VAULT_PASSPHRASE_FILE`,
+/// if that variable is set.
+pub fn passphrase_from_env() -> Result<Option<SecretBox<str>>, Box<dyn Error>> {
+    let Some(path) = std::env::var_os(PASSPHRASE_FILE_ENV) else {
+        return Ok(None);
+    };
+    let contents = Zeroizing::new(fs::read_to_string(&path).map_err(|e| {
+        format!(
+            "{}: cannot read {}: {}",
+            PASSPHRASE_FILE_ENV,
+            Path::new(&path).display(),
+            e
+        )
+    })?);
+    Ok(Some(SecretBox::new(contents.trim().into())))
+}
+
+/// Asks for a new passphrase twice and rejects mismatches.
+pub fn prompt_new_passphrase() -> Result<SecretBox<str>, Box<dyn Error>> {
+    let first = prompt_secret("New vault passphrase: ")?;
+    if first.expose_secret().is_empty() {
+        return Err("The vault passphrase must not be empty".into());
+    }
+    let second = prompt_secret("Repeat passphrase: ")?;
+    if first.expose_secret() != second.expose_secret() {
+        return Err("Passphrases do not match".into());
+    }
+    Ok(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret(s: &str) -> SecretBox<str> {
+        SecretBox::new(s.into())
+    }
+
+    #[test]
+    fn test_vault_round_trip_and_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.bin");
+
+        let mut vault = Vault::default();
+        vault.insert("default", secret("ghp_one"), false).unwrap();
+        vault
+            .insert("audit", secret("github_pat_two"), false)
+            .unwrap();
+        assert!(vault.insert("audit", secret("ghp_x"), false).is_err());
+        assert!(vault.insert("bad name", secret("ghp_x"), false).is_err());
+        vault.save(&path, &secret("correct horse")).unwrap();
+
+        let raw = fs::read(&path).unwrap();
+        assert!(!raw.windows(7).any(|w| w == b"ghp_one"));
+
+        let opened = Vault::open(&path, &secret("correct horse")).unwrap();
+        assert_eq!(
+            opened.get("audit").unwrap().expose_secret(),
+            "github_pat_two"
+        );
+        assert!(Vault::open(&path, &secret("wrong")).is_err());
+
+        let mut tampered = raw.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(Vault::decrypt(&tampered, &secret("correct horse")).is_err());
+
+        // An absurd memory cost in the header is refused before Argon2 runs.
+        let mut greedy = raw.clone();
+        greedy[MAGIC.len() + 1..MAGIC.len() + 5].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = Vault::decrypt(&greedy, &secret("correct horse"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("exceed the supported maximum"));
+    }
+
+    #[test]
+    fn test_vault_rotate_and_remove() {
+        let mut vault = Vault::default();
+        vault.insert("default", secret("ghp_old"), false).unwrap();
+        vault.insert("default", secret("ghp_new"), true).unwrap();
+        assert_eq!(vault.get("default").unwrap().expose_secret(), "ghp_new");
+        assert!(vault.insert("missing", secret("ghp_x"), true).is_err());
+        assert!(vault.remove("default"));
+        assert!(!vault.remove("default"));
+    }
+}
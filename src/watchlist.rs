@@ -0,0 +1,343 @@
+use crate::db::Database;
+use crate::models::github::Repository;
+use crate::snapshots::{self, OrgDiff, OrgSnapshot, RepoDiff, SnapshotKind};
+use reqwest::Client;
+use rusqlite::{params, Connection};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    User,
+    Org,
+    Repo,
+}
+
+impl WatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchKind::User => "user",
+            WatchKind::Org => "org",
+            WatchKind::Repo => "repo",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "user" => Some(WatchKind::User),
+            "org" => Some(WatchKind::Org),
+            "repo" => Some(WatchKind::Repo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchEntry {
+    pub kind: WatchKind,
+    pub target: String,
+    pub added_at: i64,
+}
+
+impl WatchEntry {
+    pub fn label(&self) -> String {
+        format!("{}:{}", self.kind.as_str(), self.target)
+    }
+}
+
+/// Parses `user:NAME`, `org:NAME`, `repo:OWNER/NAME` or a bare `OWNER/NAME`.
+pub fn parse_target(input: &str) -> Result<(WatchKind, String), String> {
+    let input = input.trim();
+    let (kind, target) = match input.split_once(':') {
+        Some((kind, target)) => (
+            WatchKind::parse(kind).ok_or(format!("Unknown watch type: {}", kind))?,
+            target.trim(),
+        ),
+        None if input.contains('/') => (WatchKind::Repo, input),
+        None => return Err("Use user:NAME, org:NAME or OWNER/REPO".into()),
+    };
+
+    let valid = match kind {
+        WatchKind::Repo => {
+            matches!(target.split_once('/'), Some((o, r)) if !o.is_empty() && !r.is_empty() && !r.contains('/'))
+        }
+        _ => !target.is_empty() && !target.contains('/'),
+    };
+    if !valid {
+        return Err(format!("Invalid {} target: {}", kind.as_str(), target));
+    }
+    Ok((kind, target.to_string()))
+}
+
+pub fn add(conn: &Connection, kind: WatchKind, target: &str) -> Result<bool, Box<dyn Error>> {
+    let changed = conn.execute(
+        "INSERT OR IGNORE INTO watchlist (kind, target, added_at) VALUES (?1, ?2, ?3)",
+        params![kind.as_str(), target, snapshots::now_unix()],
+    )?;
+    Ok(changed > 0)
+}
+
+pub fn remove(conn: &Connection, kind: WatchKind, target: &str) -> Result<bool, Box<dyn Error>> {
+    let changed = conn.execute(
+        "DELETE FROM watchlist WHERE kind = ?1 AND target = ?2",
+        params![kind.as_str(), target],
+    )?;
+    Ok(changed > 0)
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<WatchEntry>, Box<dyn Error>> {
+    let mut stmt =
+        conn.prepare_cached("SELECT kind, target, added_at FROM watchlist ORDER BY kind, target")?;
+    let rows = stmt.query_map([], |row| {
+        let kind: String = row.get(0)?;
+        Ok((kind, row.get(1)?, row.get(2)?))
+    })?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (kind, target, added_at) = row?;
+        if let Some(kind) = WatchKind::parse(&kind) {
+            entries.push(WatchEntry {
+                kind,
+                target,
+                added_at,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    Release,
+    Repository,
+    Security,
+    Error,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Release => "release",
+            NotificationKind::Repository => "repo",
+            NotificationKind::Security => "security",
+            NotificationKind::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for Notification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.kind.as_str(),
+            self.target,
+            self.message
+        )
+    }
+}
+
+fn notify(kind: NotificationKind, target: &str, message: String) -> Notification {
+    Notification {
+        kind,
+        target: target.to_string(),
+        message,
+    }
+}
+
+pub fn repo_notifications(target: &str, diff: &RepoDiff) -> Vec<Notification> {
+    use NotificationKind::*;
+    let mut out = Vec::new();
+    for (repo, tag) in &diff.new_releases {
+        out.push(notify(
+            Release,
+            target,
+            format!("{} released {}", repo, tag),
+        ));
+    }
+    for repo in &diff.added {
+        out.push(notify(
+            Repository,
+            target,
+            format!("new repository {}", repo),
+        ));
+    }
+    for repo in &diff.removed {
+        out.push(notify(
+            Security,
+            target,
+            format!("{} was deleted or made private", repo),
+        ));
+    }
+    for repo in &diff.archived {
+        out.push(notify(Security, target, format!("{} was archived", repo)));
+    }
+    for (repo, old, new) in &diff.visibility_changes {
+        out.push(notify(
+            Security,
+            target,
+            format!("{} visibility changed from {} to {}", repo, old, new),
+        ));
+    }
+    out
+}
+
+pub fn member_notifications(target: &str, diff: &OrgDiff) -> Vec<Notification> {
+    let joined = diff
+        .members_joined
+        .iter()
+        .map(|m| format!("public member {} joined", m));
+    let left = diff
+        .members_left
+        .iter()
+        .map(|m| format!("public member {} left", m));
+    joined
+        .chain(left)
+        .map(|message| notify(NotificationKind::Security, target, message))
+        .collect()
+}
+
+/// Stores a new repos snapshot and diffs it against the previous one.
+async fn record_repos(
+    db: &Database,
+    subject: &str,
+    repos: Vec<Repository>,
+) -> Result<Option<RepoDiff>, Box<dyn Error>> {
+    let subject = subject.to_string();
+    db.call(move |conn| {
+        let previous: Option<Vec<Repository>> =
+            snapshots::latest(conn, SnapshotKind::Repos, &subject)?;
+        snapshots::save(conn, SnapshotKind::Repos, &subject, &repos)?;
+        Ok(previous.map(|prev| snapshots::diff_repos(&prev, &repos)))
+    })
+    .await
+}
+
+/// Fetches the current state of one entry, records it as a snapshot and
+/// returns notifications for everything that changed since the last refresh.
+pub async fn refresh_entry(
+    db: &Database,
+    client: &Client,
+    token: &str,
+    entry: &WatchEntry,
+) -> Result<Vec<Notification>, Box<dyn Error>> {
+    let label = entry.label();
+    let mut notifications = Vec::new();
+
+    match entry.kind {
+        WatchKind::User | WatchKind::Org => {
+            let (repos, _) = crate::github::fetch_repos(client, token, &entry.target).await?;
+            if let Some(diff) = record_repos(db, &entry.target, repos).await? {
+                notifications.extend(repo_notifications(&label, &diff));
+            }
+        }
+        WatchKind::Repo => {
+            let (owner, name) = entry
+                .target
+                .split_once('/')
+                .ok_or(format!("Invalid repository: {}", entry.target))?;
+            let repo = crate::github::fetch_repo(client, token, owner, name).await?;
+            if let Some(diff) = record_repos(db, &entry.target, vec![repo]).await? {
+                notifications.extend(repo_notifications(&label, &diff));
+            }
+        }
+    }
+
+    if entry.kind == WatchKind::Org {
+        let members = crate::github::fetch_public_members(client, token, &entry.target).await?;
+        let subject = format!("members:{}", entry.target);
+        let snapshot = OrgSnapshot {
+            orgs: Vec::new(),
+            members,
+        };
+        let diff = db
+            .call(move |conn| {
+                let previous: Option<OrgSnapshot> =
+                    snapshots::latest(conn, SnapshotKind::Orgs, &subject)?;
+                snapshots::save(conn, SnapshotKind::Orgs, &subject, &snapshot)?;
+                Ok(previous.map(|prev| snapshots::diff_orgs(&prev, &snapshot)))
+            })
+            .await?;
+        if let Some(diff) = diff {
+            notifications.extend(member_notifications(&label, &diff));
+        }
+    }
+
+    Ok(notifications)
+}
+
+/// Refreshes every watched entry. Failures are reported as error
+/// notifications so one bad entry does not hide the rest of the digest.
+pub async fn refresh_all(
+    db: &Database,
+    client: &Client,
+    token: &str,
+) -> Result<Vec<Notification>, Box<dyn Error>> {
+    let entries = db.call(|conn| list(conn)).await?;
+
+    let mut notifications = Vec::new();
+    for entry in &entries {
+        match refresh_entry(db, client, token, entry).await {
+            Ok(found) => notifications.extend(found),
+            Err(e) => notifications.push(notify(
+                NotificationKind::Error,
+                &entry.label(),
+                e.to_string(),
+            )),
+        }
+    }
+    Ok(notifications)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("user:octocat").unwrap(),
+            (WatchKind::User, "octocat".to_string())
+        );
+        assert_eq!(
+            parse_target("rust-lang/rust").unwrap(),
+            (WatchKind::Repo, "rust-lang/rust".to_string())
+        );
+        assert!(parse_target("octocat").is_err());
+        assert!(parse_target("org:a/b").is_err());
+        assert!(parse_target("repo:rust-lang").is_err());
+        assert!(parse_target("team:core").is_err());
+    }
+
+    #[test]
+    fn test_repo_notifications_flag_security_changes() {
+        let diff = RepoDiff {
+            added: vec!["new-tool".into()],
+            removed: vec!["secret-sauce".into()],
+            new_releases: vec![("cli".into(), "v2.0.0".into())],
+            ..Default::default()
+        };
+        let notifications = repo_notifications("org:acme", &diff);
+        let kinds: Vec<_> = notifications.iter().map(|n| n.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NotificationKind::Release,
+                NotificationKind::Repository,
+                NotificationKind::Security
+            ]
+        );
+        assert_eq!(
+            notifications[0].to_string(),
+            "[release] org:acme: cli released v2.0.0"
+        );
+    }
+}